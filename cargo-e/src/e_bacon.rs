@@ -1,42 +1,65 @@
 // src/e_bacon.rs
 
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use crate::e_target::CargoTarget;
+use crate::e_target::{CargoTarget, TargetKind};
 
 // src/e_bacon.rs
 
 // #[cfg(windows)]
 // use std::os::windows::io::AsRawHandle;
 
-/// Runs the "bacon" command on the given sample in detached mode,
-/// capturing the output (stdout and stderr) into "output_bacon.txt".
-/// It passes the project directory (derived from the sample’s manifest_path)
-/// via the "--path" flag and appends any extra arguments.
+/// Runs the "bacon" command on the given sample.
 ///
-/// On Windows, if the environment variable DEBUG_BACON is set, it uses `/K` and echoes
-/// the folder parameter so that you can inspect it; otherwise it uses normal detached flags.
-pub fn run_bacon(sample: &CargoTarget, _extra_args: &[String]) -> Result<(), Box<dyn Error>> {
+/// The project directory (derived from the sample's manifest_path) is passed
+/// via the "--path" flag, followed by `extra_args`. Without extra arguments, the
+/// sample's `run-*` job is selected if `bacon.toml` defines one (see [`generate`]).
+///
+/// When `detached`, bacon opens in a new window: `START` on Windows, elsewhere
+/// the launcher picked by [`crate::e_terminal::Launcher::detect`] (`--terminal`,
+/// the current tmux/zellij session, `$TERMINAL`, then the first terminal found).
+/// Otherwise it runs in the current terminal and this call waits for it to exit.
+pub fn run_bacon(
+    sample: &CargoTarget,
+    extra_args: &[String],
+    detached: bool,
+) -> Result<(), Box<dyn Error>> {
     println!("Running bacon for sample: {}", sample.name);
 
     // Determine the project directory from the sample's manifest_path.
     let manifest_path = Path::new(&sample.manifest_path);
     let project_dir = manifest_path.parent().unwrap_or(manifest_path);
 
-    let mut cmd = Command::new("cmd");
-    let args = &[
-        "/c",
-        "START",
-        "",
-        "bacon",
-        &format!("--path={}", project_dir.to_str().unwrap_or_default()),
-    ];
-    cmd.args(args);
+    let mut args = vec![format!(
+        "--path={}",
+        project_dir.to_str().unwrap_or_default()
+    )];
+    if !extra_args.is_empty() {
+        args.extend(extra_args.iter().cloned());
+    } else {
+        // A name shared with another package's target gets its package in the job name.
+        let bacon_toml = project_dir.join("bacon.toml");
+        let job = [package_job_names(sample), job_names(sample)]
+            .into_iter()
+            .flatten()
+            .map(|j| j.run)
+            .find(|job| bacon_toml_has_job(&bacon_toml, job));
+        args.extend(job);
+    }
+
+    if !detached {
+        let status = Command::new("bacon").args(&args).status()?;
+        if !status.success() {
+            return Err(format!("bacon exited with {}", status).into());
+        }
+        return Ok(());
+    }
 
     // Spawn the bacon process detached. We do not wait on it.
-    let child = cmd.spawn()?;
+    let child = terminal_command(project_dir, &args)?.spawn()?;
     std::mem::forget(child);
 
     // crossterm::terminal::enable_raw_mode()?;
@@ -44,6 +67,239 @@ pub fn run_bacon(sample: &CargoTarget, _extra_args: &[String]) -> Result<(), Box
     Ok(())
 }
 
+/// A command that opens bacon with `args` in a new terminal window.
+#[cfg(windows)]
+fn terminal_command(_project_dir: &Path, args: &[String]) -> Result<Command, Box<dyn Error>> {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/c", "START", "", "bacon"]).args(args);
+    Ok(cmd)
+}
+
+/// A command that opens bacon with `args` in a new terminal window or multiplexer pane.
+#[cfg(not(windows))]
+fn terminal_command(project_dir: &Path, args: &[String]) -> Result<Command, Box<dyn Error>> {
+    let preference = crate::GLOBAL_CLI.get().and_then(|cli| cli.terminal.clone());
+    let launcher = crate::e_terminal::Launcher::detect(preference.as_deref())
+        .ok_or("no terminal emulator found; set $TERMINAL or run without --detached")?;
+    let opts = crate::e_terminal::LaunchOptions {
+        cwd: Some(project_dir.to_path_buf()),
+        title: Some("bacon".to_string()),
+        ..Default::default()
+    };
+    Ok(launcher.command("bacon", args, &opts))
+}
+
+fn bacon_toml_has_job(path: &Path, job: &str) -> bool {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| s.parse::<toml::Table>().ok())
+        .and_then(|t| t.get("jobs")?.as_table()?.get(job).cloned())
+        .is_some()
+}
+
+/// Names of the bacon jobs generated for one target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobNames {
+    pub run: String,
+    pub check: String,
+    pub clippy: String,
+}
+
+impl JobNames {
+    fn new(kind: &str, name: &str) -> Self {
+        JobNames {
+            run: format!("run-{}-{}", kind, name),
+            check: format!("check-{}-{}", kind, name),
+            clippy: format!("clippy-{}-{}", kind, name),
+        }
+    }
+}
+
+fn job_kind(target: &CargoTarget) -> Option<&'static str> {
+    match target.kind {
+        TargetKind::Example | TargetKind::ExtendedExample => Some("example"),
+        TargetKind::Binary | TargetKind::ExtendedBinary => Some("bin"),
+        _ => None,
+    }
+}
+
+/// Job names for `target`, or None for targets bacon cannot drive with plain cargo.
+pub fn job_names(target: &CargoTarget) -> Option<JobNames> {
+    job_kind(target).map(|kind| JobNames::new(kind, &target.name))
+}
+
+/// Job names that also carry `target`'s package, e.g. `run-example-<package>-<name>`;
+/// [`generate`] uses them when two packages have a target of the same kind and name.
+pub fn package_job_names(target: &CargoTarget) -> Option<JobNames> {
+    let kind = job_kind(target)?;
+    Some(JobNames::new(
+        kind,
+        &format!("{}-{}", package_name(&target.manifest_path), target.name),
+    ))
+}
+
+/// The `[package] name` in `manifest_path`, else the name of its directory.
+fn package_name(manifest_path: &Path) -> String {
+    std::fs::read_to_string(manifest_path)
+        .ok()
+        .and_then(|s| s.parse::<toml::Table>().ok())
+        .and_then(|t| t.get("package")?.get("name")?.as_str().map(str::to_string))
+        .or_else(|| {
+            manifest_path
+                .parent()
+                .and_then(|dir| dir.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_default()
+}
+
+/// The `bacon.toml` contents with run, check and clippy jobs for every example
+/// and binary in `targets`. Manifests other than `base_dir/Cargo.toml` are
+/// passed with `--manifest-path`, relative to `base_dir` when possible. When
+/// targets of different packages share a kind and name, their jobs are named
+/// with [`package_job_names`] so neither replaces the other.
+pub fn generate(targets: &[CargoTarget], base_dir: &Path) -> String {
+    let mut jobs = toml::Table::new();
+    let base_manifest = std::fs::canonicalize(base_dir.join("Cargo.toml")).ok();
+    let canonical_manifest = |target: &CargoTarget| {
+        std::fs::canonicalize(&target.manifest_path)
+            .unwrap_or_else(|_| target.manifest_path.clone())
+    };
+    let mut manifests_by_job: HashMap<String, HashSet<PathBuf>> = HashMap::new();
+    for target in targets {
+        if let Some(names) = job_names(target) {
+            manifests_by_job
+                .entry(names.run)
+                .or_default()
+                .insert(canonical_manifest(target));
+        }
+    }
+    for target in targets {
+        let Some(mut names) = job_names(target) else {
+            continue;
+        };
+        if manifests_by_job
+            .get(&names.run)
+            .is_some_and(|m| m.len() > 1)
+        {
+            names = package_job_names(target).unwrap_or(names);
+        }
+        let selector = match target.kind {
+            TargetKind::Example | TargetKind::ExtendedExample => "--example",
+            _ => "--bin",
+        };
+        let mut target_args = vec![selector.to_string(), target.name.clone()];
+        let manifest = canonical_manifest(target);
+        if base_manifest.as_ref() != Some(&manifest) {
+            let base = std::fs::canonicalize(base_dir).unwrap_or_else(|_| base_dir.to_path_buf());
+            let shown: PathBuf = manifest
+                .strip_prefix(&base)
+                .map(Path::to_path_buf)
+                .unwrap_or(manifest.clone());
+            target_args.push("--manifest-path".to_string());
+            target_args.push(shown.to_string_lossy().replace('\\', "/"));
+        }
+        if let Some(features) = target.required_features() {
+            target_args.push("--features".to_string());
+            target_args.push(features);
+        }
+        for (name, subcommand) in [
+            (&names.run, "run"),
+            (&names.check, "check"),
+            (&names.clippy, "clippy"),
+        ] {
+            let mut command = vec!["cargo".to_string(), subcommand.to_string()];
+            command.extend(target_args.iter().cloned());
+            if subcommand != "run" {
+                command.push("--color".to_string());
+                command.push("always".to_string());
+            }
+            let mut job = toml::Table::new();
+            job.insert("command".into(), command.into());
+            job.insert("need_stdout".into(), (subcommand == "run").into());
+            if subcommand == "run" {
+                job.insert("allow_warnings".into(), true.into());
+                job.insert("background".into(), false.into());
+                job.insert("on_change_strategy".into(), "kill_then_restart".into());
+            }
+            jobs.insert(name.clone(), job.into());
+        }
+    }
+    let mut root = toml::Table::new();
+    root.insert("jobs".into(), jobs.into());
+    format!(
+        "# Generated by `cargo e bacon --generate`; rerun it after adding targets.\n\n{}",
+        toml::to_string_pretty(&root).unwrap_or_default()
+    )
+}
+
+#[derive(clap::Parser, Debug)]
+#[command(
+    name = "cargo e bacon",
+    about = "Run bacon, or generate bacon.toml jobs."
+)]
+pub struct BaconCli {
+    #[arg(long, help = "Write bacon.toml jobs for every example and binary.")]
+    pub generate: bool,
+    #[arg(long, help = "Overwrite an existing bacon.toml.")]
+    pub force: bool,
+    #[arg(
+        long,
+        default_value = "bacon.toml",
+        help = "File written by --generate."
+    )]
+    pub output: PathBuf,
+    #[arg(long, help = "Open bacon in a new terminal window.")]
+    pub detached: bool,
+    /// Arguments passed to bacon, e.g. a job name.
+    #[arg(trailing_var_arg = true)]
+    pub bacon_args: Vec<String>,
+}
+
+/// Entry point for `cargo e bacon ...`; `args[0]` is the `bacon` word itself.
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    use clap::Parser;
+    let parsed = BaconCli::try_parse_from(args).unwrap_or_else(|e| e.exit());
+    let cwd = std::env::current_dir()?;
+    if parsed.generate {
+        if parsed.output.exists() && !parsed.force {
+            anyhow::bail!(
+                "{} already exists; pass --force to overwrite it",
+                parsed.output.display()
+            );
+        }
+        let threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4);
+        let targets = crate::e_collect::collect_all_targets(None, false, threads, true, false)
+            .map_err(|e| anyhow::anyhow!("failed to collect targets: {}", e))?;
+        let contents = generate(&targets, &cwd);
+        std::fs::write(&parsed.output, contents)?;
+        let count = targets.iter().filter(|t| job_names(t).is_some()).count();
+        println!(
+            "wrote {} jobs for {} targets to {}",
+            count * 3,
+            count,
+            parsed.output.display()
+        );
+        return Ok(());
+    }
+    let mut cmd = if parsed.detached {
+        terminal_command(&cwd, &parsed.bacon_args).map_err(|e| anyhow::anyhow!("{}", e))?
+    } else {
+        let mut cmd = Command::new("bacon");
+        cmd.args(&parsed.bacon_args);
+        cmd
+    };
+    if parsed.detached {
+        cmd.spawn()?;
+    } else {
+        let status = cmd.status()?;
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
 // /// Runs the "bacon" command on the given sample in detached mode,
 // /// capturing the output (stdout and stderr) into "output_bacon.txt".
 // /// It passes the project directory (derived from the sample’s manifest_path)
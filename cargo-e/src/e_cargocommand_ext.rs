@@ -1,12 +1,16 @@
 use crate::e_command_builder::{CargoCommandBuilder, TerminalError};
-use crate::e_eventdispatcher::{EventDispatcher, ThreadLocalContext};
+use crate::e_eventdispatcher::{
+    CargoDiagnosticLevel, CargoDiagnosticSpan, CargoDiagnosticSpanLine, EventDispatcher,
+    ThreadLocalContext,
+};
 #[allow(unused_imports)]
 use cargo_metadata::Message;
 use nu_ansi_term::{Color, Style};
 #[cfg(feature = "uses_serde")]
 use serde_json;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
 use std::process::ExitStatus;
 use std::process::{Child, Command, Stdio};
 #[allow(unused_imports)]
@@ -41,6 +45,8 @@ pub struct CargoStats {
     pub compiler_artifact_time: Option<SystemTime>,
     pub build_script_executed_time: Option<SystemTime>,
     pub build_finished_time: Option<SystemTime>,
+    /// When a server reported "listening on".
+    pub listening_time: Option<SystemTime>,
 }
 
 #[derive(Clone)]
@@ -55,6 +61,10 @@ pub struct CargoDiagnostic {
     pub uses_color: bool,
     pub diag_number: Option<usize>,
     pub diag_num_padding: Option<usize>,
+    /// Source spans reported by rustc (only filled from JSON messages).
+    pub spans: Vec<CargoDiagnosticSpan>,
+    /// Child diagnostics (notes, helps, suggestions) reported by rustc.
+    pub children: Vec<CargoDiagnostic>,
 }
 
 impl CargoDiagnostic {
@@ -91,6 +101,8 @@ impl CargoDiagnostic {
             uses_color,
             diag_number,
             diag_num_padding,
+            spans: Vec::new(),
+            children: Vec::new(),
         }
     }
 
@@ -140,6 +152,154 @@ impl CargoDiagnostic {
     }
 }
 
+impl CargoDiagnostic {
+    /// Build a `CargoDiagnostic` from a rustc diagnostic delivered through
+    /// `--message-format=json-diagnostic-rendered-ansi`.
+    ///
+    /// Spans, the error code, child notes/helps and suggested replacements are
+    /// kept as-is; `lineref`, `suggestion`, `note` and `help` are derived from
+    /// them so the numbered Debug output matches the regex-parsed diagnostics.
+    /// Returns `None` for levels we don't track (failure notes) and for the
+    /// trailing "generated N warnings" / "aborting due to" summaries.
+    pub fn from_compiler_diagnostic(
+        diag: &cargo_metadata::diagnostic::Diagnostic,
+        manifest_path: &PathBuf,
+        diag_number: Option<usize>,
+    ) -> Option<Self> {
+        use cargo_metadata::diagnostic::DiagnosticLevel;
+        let level = match diag.level {
+            DiagnosticLevel::Ice | DiagnosticLevel::Error => "error",
+            DiagnosticLevel::Warning => "warning",
+            DiagnosticLevel::Note => "note",
+            DiagnosticLevel::Help => "help",
+            _ => return None,
+        };
+        if diag.spans.is_empty() {
+            static RE_SUMMARY: once_cell::sync::Lazy<regex::Regex> =
+                once_cell::sync::Lazy::new(|| {
+                    regex::Regex::new(r"generated\s+\d+|aborting due to").unwrap()
+                });
+            if RE_SUMMARY.is_match(&diag.message) {
+                return None;
+            }
+        }
+
+        let spans: Vec<CargoDiagnosticSpan> = diag
+            .spans
+            .iter()
+            .map(|span| {
+                let file =
+                    crate::e_command_builder::resolve_file_path(manifest_path, &span.file_name);
+                CargoDiagnosticSpan {
+                    file_name: file.to_str().unwrap_or_default().to_string(),
+                    line_start: span.line_start,
+                    line_end: span.line_end,
+                    column_start: span.column_start,
+                    column_end: span.column_end,
+                    is_primary: span.is_primary,
+                    text: span
+                        .text
+                        .iter()
+                        .map(|t| CargoDiagnosticSpanLine {
+                            text: t.text.clone(),
+                            highlight_start: t.highlight_start,
+                            highlight_end: t.highlight_end,
+                        })
+                        .collect(),
+                    label: span.label.clone(),
+                    suggested_replacement: span.suggested_replacement.clone(),
+                    suggestion_applicability: span
+                        .suggestion_applicability
+                        .as_ref()
+                        .map(|a| format!("{:?}", a)),
+                }
+            })
+            .collect();
+        let children: Vec<CargoDiagnostic> = diag
+            .children
+            .iter()
+            .filter_map(|child| Self::from_compiler_diagnostic(child, manifest_path, None))
+            .collect();
+
+        let primary = spans
+            .iter()
+            .find(|s| s.is_primary)
+            .or_else(|| spans.first());
+        let lineref = primary
+            .map(|s| format!("{}:{}:{}", s.file_name, s.line_start, s.column_start))
+            .unwrap_or_default();
+
+        // Rebuild the "NN | source" / "| ^^^ label" snippet the regex path collects.
+        let suggestion = primary.filter(|s| !s.text.is_empty()).map(|span| {
+            let mut lines: Vec<String> = span
+                .text
+                .iter()
+                .enumerate()
+                .map(|(i, t)| format!("{} | {}", span.line_start + i, t.text))
+                .collect();
+            if let Some(last) = span.text.last() {
+                let carets = "^".repeat(
+                    last.highlight_end
+                        .saturating_sub(last.highlight_start)
+                        .max(1),
+                );
+                lines.push(format!(
+                    "| {} {}",
+                    carets,
+                    span.label.clone().unwrap_or_default()
+                ));
+            }
+            lines.join("\n")
+        });
+
+        let notes: Vec<String> = children
+            .iter()
+            .filter(|c| c.level == "note")
+            .map(|c| format!("note: {}", c.message))
+            .collect();
+        let helps: Vec<String> = children
+            .iter()
+            .filter(|c| c.level == "help")
+            .map(|c| {
+                let replacement = c
+                    .spans
+                    .iter()
+                    .find_map(|s| s.suggested_replacement.as_ref());
+                match replacement {
+                    Some(r) => format!("\x1b[38;5;214mhelp: {}: `{}`\x1b[0m", c.message, r),
+                    None => format!("\x1b[38;5;214mhelp: {}\x1b[0m", c.message),
+                }
+            })
+            .collect();
+
+        Some(CargoDiagnostic {
+            lineref,
+            level: level.to_string(),
+            message: diag.message.clone(),
+            error_code: diag.code.as_ref().map(|c| c.code.clone()),
+            suggestion,
+            note: (!notes.is_empty()).then(|| notes.join("\n")),
+            help: (!helps.is_empty()).then(|| helps.join("\n")),
+            uses_color: true,
+            diag_number,
+            diag_num_padding: Some(2),
+            spans,
+            children,
+        })
+    }
+
+    /// Maps the textual level back to our internal diagnostic level.
+    pub fn diagnostic_level(&self) -> Option<CargoDiagnosticLevel> {
+        match self.level.as_str() {
+            "error" => Some(CargoDiagnosticLevel::Error),
+            "warning" => Some(CargoDiagnosticLevel::Warning),
+            "help" => Some(CargoDiagnosticLevel::Help),
+            "note" => Some(CargoDiagnosticLevel::Note),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Debug for CargoDiagnostic {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Capitalize the first letter of the level
@@ -301,6 +461,10 @@ impl fmt::Debug for CargoDiagnostic {
 #[derive(Debug, Default, Clone)]
 pub struct CargoProcessResult {
     pub target_name: String,
+    /// Run profile the target was started with, if any.
+    pub profile: Option<String>,
+    /// Manifest of the target's package; relative paths in `diagnostics` resolve against it.
+    pub manifest_path: PathBuf,
     pub cmd: String,
     pub args: Vec<String>,
     pub pid: u32,
@@ -318,9 +482,32 @@ pub struct CargoProcessResult {
     pub diagnostics: Vec<CargoDiagnostic>,
     pub is_filter: bool,
     pub is_could_not_compile: bool,
+    /// CPU and memory of the process tree, sampled while it ran.
+    pub resources: crate::e_resources::ResourceUsage,
+    /// Set when `resources` went over `--max-rss` or `--max-cpu`; the run counts as failed.
+    pub resource_violation: Option<String>,
+    /// Outcome of the target's smoke-test expectations in `--run-all`, if it declared any.
+    pub expectation: Option<crate::e_expect::Verdict>,
+    /// What `--snapshot` recorded or found for the run's stdout.
+    pub snapshot: Option<crate::e_snapshot::SnapshotOutcome>,
+    /// Set when `--run-all` stopped the target at its `--wait` timeout while it was still
+    /// running; such a run has no exit status but counts as having run.
+    pub timed_out: bool,
+    /// Notes added by `after_build` plugins, shown in the run report.
+    pub annotations: Vec<String>,
+    /// Set by an `after_build` plugin to fail the run; the plugin's reason.
+    pub plugin_failure: Option<String>,
 }
 
 impl CargoProcessResult {
+    /// The target name, with `@profile` appended when a run profile was used.
+    pub fn label(&self) -> String {
+        match &self.profile {
+            Some(profile) => format!("{}@{}", self.target_name, profile),
+            None => self.target_name.clone(),
+        }
+    }
+
     /// Print every diagnostic in full detail.
     pub fn print_exact(&self) {
         if self.diagnostics.is_empty() {
@@ -746,6 +933,8 @@ impl CargoCommandExt for Command {
         // Create the CargoProcessHandle
         let result = CargoProcessResult {
             target_name: builder.target_name.clone(),
+            profile: builder.profile.clone(),
+            manifest_path: builder.manifest_path.clone(),
             cmd,
             args,
             pid,
@@ -763,6 +952,13 @@ impl CargoCommandExt for Command {
             diagnostics: Vec::new(),
             is_filter: builder.is_filter,
             is_could_not_compile: false,
+            resources: Default::default(),
+            resource_violation: None,
+            expectation: None,
+            snapshot: None,
+            timed_out: false,
+            annotations: Vec::new(),
+            plugin_failure: None,
         };
 
         // Return the CargoProcessHandle that owns the child process
@@ -804,6 +1000,29 @@ impl CargoCommandExt for Command {
         estimate_bytes: Option<usize>,
     ) -> CargoProcessHandle {
         self.stdout(Stdio::piped()).stderr(Stdio::piped());
+        // Targets that need a terminal get a pty for stdin/stdout; see e_pty.
+        #[cfg(unix)]
+        let pty = if builder.use_pty
+            && builder.output_sink.is_none()
+            && !builder.detached
+            && builder.stdin_file.is_none()
+            && crate::e_pty::should_use()
+        {
+            match crate::e_pty::Pty::open().and_then(|pty| pty.attach(self).map(|_| pty)) {
+                Ok(pty) => Some(pty),
+                Err(e) => {
+                    log::warn!("Could not open a pty, using pipes: {}", e);
+                    self.stdin(Stdio::inherit()).stdout(Stdio::piped());
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        #[cfg(not(unix))]
+        let pty: Option<()> = None;
+        // Runtime output is already on screen when it came through the pty.
+        let echo_runtime = pty.is_none();
         let builder_for_result = builder.clone();
         let builder_for_closure = builder.clone();
         let builder_stdout = builder.clone();
@@ -839,13 +1058,22 @@ impl CargoCommandExt for Command {
 
         // Spawn a thread to process stdout.
         let _stderr_compiler_msg_clone = Arc::clone(&stderr_compiler_msg);
-        let stdout = child.stdout.take().expect("Failed to capture stdout");
+        let stdout: Box<dyn Read + Send> = match pty {
+            #[cfg(unix)]
+            Some(pty) => {
+                // Release our copies of the slave so the pty closes with the child.
+                self.stdin(Stdio::inherit()).stdout(Stdio::null());
+                Box::new(pty.start())
+            }
+            _ => Box::new(child.stdout.take().expect("Failed to capture stdout")),
+        };
         // println!("{}: Capturing stdout", pid);
         let stdout_handle = thread::spawn(move || {
             ThreadLocalContext::set_context(
                 &builder_stdout.target_name.clone(),
                 builder_stdout.manifest_path.to_str().unwrap_or_default(),
             );
+            crate::e_eventdispatcher::set_output_sink(builder_stdout.output_sink.clone());
 
             let stdout_reader = BufReader::new(stdout);
             // This flag marks whether we are still in the build phase.
@@ -853,6 +1081,9 @@ impl CargoCommandExt for Command {
             let mut _in_build_phase = true;
             let stdout_buffer = Arc::new(Mutex::new(Vec::<String>::new()));
             let buf = Arc::clone(&stdout_buffer);
+            // Per-level numbering for diagnostics parsed from JSON messages.
+            #[allow(unused_mut, unused_variables)]
+            let mut json_diag_counts: HashMap<CargoDiagnosticLevel, usize> = HashMap::new();
             {
                 for line in stdout_reader.lines().map(|line| line) {
                     if let Ok(line) = line {
@@ -860,7 +1091,9 @@ impl CargoCommandExt for Command {
                         // Try to parse the line as a JSON cargo message.
 
                         #[cfg(not(feature = "uses_serde"))]
-                        println!("{}", line);
+                        if echo_runtime {
+                            crate::out_println!("{}", line);
+                        }
                         #[cfg(feature = "uses_serde")]
                         match serde_json::from_str::<Message>(&line) {
                             Ok(msg) => {
@@ -935,6 +1168,35 @@ impl CargoCommandExt for Command {
                                                 );
                                             }
                                         }
+                                        if builder_stdout.uses_json_diagnostics() {
+                                            // Structured path: no need to re-parse the rendered text.
+                                            // It still reaches the stderr dispatcher below for the other
+                                            // callbacks; its diagnostic callbacks are switched off.
+                                            if let Some(mut diag) =
+                                                CargoDiagnostic::from_compiler_diagnostic(
+                                                    &msg.message,
+                                                    &builder_stdout.manifest_path,
+                                                    None,
+                                                )
+                                            {
+                                                if let Some(level) = diag.diagnostic_level() {
+                                                    let count =
+                                                        json_diag_counts.entry(level).or_insert(0);
+                                                    *count += 1;
+                                                    diag.diag_number = Some(*count);
+                                                }
+                                                #[cfg(feature = "uses_plugins")]
+                                                crate::plugins::hooks::on_diagnostic(
+                                                    &builder_stdout.target_name,
+                                                    &diag,
+                                                );
+                                                builder_stdout
+                                                    .diagnostics
+                                                    .lock()
+                                                    .unwrap()
+                                                    .push(diag);
+                                            }
+                                        }
                                         let mut msg_vec =
                                             _stderr_compiler_msg_clone.lock().unwrap();
                                         msg_vec.push_back(format!(
@@ -1027,14 +1289,14 @@ impl CargoCommandExt for Command {
                                         );
                                     }
                                     buf.lock().unwrap().push(line.to_string());
-                                } else {
+                                } else if echo_runtime {
                                     // build is done: first flush anything we buffered
                                     let mut b = buf.lock().unwrap();
                                     for l in b.drain(..) {
-                                        println!("{}", l);
+                                        crate::out_println!("{}", l);
                                     }
                                     // then print live
-                                    println!("{}", line);
+                                    crate::out_println!("{}", line);
                                 }
                                 if let Some(ref disp) = _stdout_disp_clone {
                                     disp.dispatch(&line, stats_stdout_clone.clone());
@@ -1042,9 +1304,10 @@ impl CargoCommandExt for Command {
                                 // Print the runtime output.
                                 // println!("{}: {}", pid, line);
                                 if line.contains("not a terminal") {
-                                    println!(
+                                    crate::out_println!(
                                         "{}NOT A TERMINAL - MARK AND RUN AGAIN: {}",
-                                        pid, line
+                                        pid,
+                                        line
                                     );
                                 }
                                 _runtime_counter_stdout.fetch_add(line.len(), Ordering::Relaxed);
@@ -1083,6 +1346,7 @@ impl CargoCommandExt for Command {
                 &builder_stderr.target_name.clone(),
                 builder_stderr.manifest_path.to_str().unwrap_or_default(),
             );
+            crate::e_eventdispatcher::set_output_sink(builder_stderr.output_sink.clone());
             //    let mut msg_vec = stderr_compiler_msg_clone.lock().unwrap();
             loop {
                 // println!("looping stderr thread {}", pid);
@@ -1091,7 +1355,10 @@ impl CargoCommandExt for Command {
                     let mut guard = match stderr_compiler_msg_clone.lock() {
                         Ok(guard) => guard,
                         Err(err) => {
-                            eprintln!("Failed to lock stderr_compiler_msg_clone: {}", err);
+                            crate::out_eprintln!(
+                                "Failed to lock stderr_compiler_msg_clone: {}",
+                                err
+                            );
                             return; // Exit the function or loop in case of an error
                         }
                     };
@@ -1107,7 +1374,11 @@ impl CargoCommandExt for Command {
                                 if let Some(response) = ret {
                                     if response.terminal_status == Some(TerminalError::NoTerminal) {
                                         // If the response indicates a terminal error, set the flag.
-                                        println!("{} IS A TERMINAL PROCESS - {}", pid, line);
+                                        crate::out_println!(
+                                            "{} IS A TERMINAL PROCESS - {}",
+                                            pid,
+                                            line
+                                        );
                                     } else if response.terminal_status
                                         == Some(TerminalError::NoError)
                                     {
@@ -1116,7 +1387,11 @@ impl CargoCommandExt for Command {
                                         == Some(TerminalError::NoTerminal)
                                     {
                                         // If the response indicates not a terminal, set the flag to NoTerminal.
-                                        println!("{} IS A TERMINAL PROCESS - {}", pid, line);
+                                        crate::out_println!(
+                                            "{} IS A TERMINAL PROCESS - {}",
+                                            pid,
+                                            line
+                                        );
                                     }
                                     // if let Some(ref msg) = response.message {
                                     //     println!("DISPATCH RESULT {} {}", pid, msg);
@@ -1215,10 +1490,10 @@ impl CargoCommandExt for Command {
                             }
                             if !has_match && !line.trim().is_empty() && !line.eq("...") {
                                 // If the line doesn't match any pattern, print it as is.
-                                println!("{}", line);
+                                crate::out_println!("{}", line);
                             }
                         } else {
-                            println!("ALLLINES {}", line.trim()); //all lines
+                            crate::out_println!("ALLLINES {}", line.trim()); //all lines
                         }
                         // if let Some(ref disp) = stderr_disp_clone {
                         //     if let Some(ret) = disp.dispatch(&line) {
@@ -1259,6 +1534,8 @@ impl CargoCommandExt for Command {
         };
         let result = CargoProcessResult {
             target_name: builder_for_closure.target_name.clone(),
+            profile: builder_for_closure.profile.clone(),
+            manifest_path: builder_for_closure.manifest_path.clone(),
             cmd,
             args,
             pid,
@@ -1276,6 +1553,13 @@ impl CargoCommandExt for Command {
             diagnostics: final_diagnostics,
             is_filter: builder_for_closure.is_filter,
             is_could_not_compile: stats_snapshot.is_could_not_compile,
+            resources: Default::default(),
+            resource_violation: None,
+            expectation: None,
+            snapshot: None,
+            timed_out: false,
+            annotations: Vec::new(),
+            plugin_failure: None,
         };
         CargoProcessHandle {
             child,
@@ -1316,6 +1600,7 @@ impl Clone for CargoStats {
             compiler_artifact_time: self.compiler_artifact_time,
             build_script_executed_time: self.build_script_executed_time,
             build_finished_time: self.build_finished_time,
+            listening_time: self.listening_time,
         }
     }
 }
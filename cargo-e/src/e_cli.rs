@@ -44,6 +44,32 @@ pub struct Cli {
 
     #[arg(long, help = "Create GIST run_report.md on exit.")]
     pub gist: bool,
+
+    /// Format of the run report written on exit.
+    #[arg(
+        long = "report-format",
+        value_name = "FORMAT",
+        default_value_t = ReportFormat::Markdown,
+        value_parser,
+        help = "Run report format: markdown, json or junit."
+    )]
+    pub report_format: ReportFormat,
+
+    /// Where to write the run report.
+    #[arg(
+        long = "report-path",
+        value_name = "PATH",
+        help = "Path of the run report [default: run_report.md, run_report.json or run_report.xml]."
+    )]
+    pub report_path: Option<std::path::PathBuf>,
+
+    /// Git ref whose recorded results `--run-all` is compared against.
+    #[arg(
+        long,
+        value_name = "REF",
+        help = "With --run-all, exit non-zero if a target that passed at REF now fails."
+    )]
+    pub baseline: Option<String>,
     #[arg(long, help = "Build and run in release mode.")]
     pub release: bool,
     #[arg(
@@ -52,13 +78,18 @@ pub struct Cli {
         help = "Suppress cargo output when running the sample."
     )]
     pub quiet: bool,
+    #[arg(
+        long,
+        help = "Rebuild and restart the target whenever its sources or manifest change."
+    )]
+    pub watch: bool,
     // /// Comma-separated list of package names.
     // #[clap(long, value_delimiter = ',', help = "Optional list of package names to run examples for. If omitted, defaults to ALL_PACKAGES.")]
     // pub specified_packages: Vec<String>,
-    /// Pre-build examples before running.
+    /// Build all selected examples and binaries once, then run their executables directly.
     #[clap(
         long,
-        help = "If enabled, pre-build the examples before executing them."
+        help = "Build all examples and binaries in one cargo build, then run the executables directly."
     )]
     pub pre_build: bool,
 
@@ -89,6 +120,12 @@ pub struct Cli {
         help = "Enable filter mode. cargo output is filtered and captured."
     )]
     pub filter: bool,
+    #[arg(
+        long = "no-pty",
+        default_value_t = false,
+        help = "With -f, capture the target through pipes instead of a pseudo-terminal (Unix)."
+    )]
+    pub no_pty: bool,
     /// Print version and feature flags in JSON format.
     #[arg(
         long,
@@ -180,6 +217,44 @@ pub struct Cli {
     )]
     pub run_at_a_time: usize,
 
+    #[arg(
+        long = "sample-interval",
+        value_name = "MS",
+        default_value_t = 500,
+        help = "Milliseconds between CPU/memory samples of running targets; 0 disables sampling."
+    )]
+    pub sample_interval: u64,
+
+    #[arg(
+        long = "max-rss",
+        value_name = "SIZE",
+        value_parser = crate::e_resources::parse_size,
+        help = "Mark a run as failed when its peak memory (RSS, children included) exceeds SIZE, e.g. 512M or 2G."
+    )]
+    pub max_rss: Option<u64>,
+
+    #[arg(
+        long = "max-cpu",
+        value_name = "PERCENT",
+        help = "Mark a run as failed when its average CPU use exceeds PERCENT (100 = one core)."
+    )]
+    pub max_cpu: Option<f32>,
+
+    /// Record or check golden runtime output under `snapshots/`.
+    #[arg(
+        long = "snapshot",
+        value_name = "MODE",
+        help = "Record each target's runtime stdout to snapshots/<target>.out, or check it against the recorded file: record or check. Implies --run-all."
+    )]
+    pub snapshot: Option<SnapshotMode>,
+
+    #[arg(
+        long = "snapshot-redact",
+        value_name = "REGEX[=>REPLACEMENT]",
+        help = "Extra pattern to normalize in snapshot output before it is stored or compared (repeatable; replaced with [REDACTED] unless REPLACEMENT is given)."
+    )]
+    pub snapshot_redact: Vec<String>,
+
     #[arg(
         long = "nS",
         default_value_t = false,
@@ -211,6 +286,30 @@ pub struct Cli {
     #[arg(long = "nW", default_value_t = false, help = "Disable window popups.")]
     pub no_window: bool,
 
+    #[arg(
+        long = "regex-diagnostics",
+        default_value_t = false,
+        help = "Parse diagnostics from rustc's rendered stderr instead of cargo's JSON messages."
+    )]
+    pub regex_diagnostics: bool,
+
+    /// Ignore the cached target list and rediscover targets.
+    #[arg(
+        long = "no-cache",
+        visible_alias = "refresh",
+        default_value_t = false,
+        help = "Ignore target/cargo-e/targets.json and rediscover targets, refreshing the cache."
+    )]
+    pub no_cache: bool,
+
+    /// Print the resolved configuration and exit.
+    #[arg(
+        long = "print-config",
+        default_value_t = false,
+        help = "Print the resolved configuration, and where each value came from, then exit."
+    )]
+    pub print_config: bool,
+
     /// Enable logging to a file or stdout.
     #[arg(
         long = "log",
@@ -255,6 +354,32 @@ pub struct Cli {
         help = "Time in seconds for detached windows to delay before executing target"
     )]
     pub detached_delay: Option<u32>,
+    #[clap(
+        long,
+        value_name = "NAME",
+        help = "Terminal for --detached on Linux/macOS: alacritty, kitty, wezterm, foot, gnome-terminal, konsole, xterm, tmux, zellij, terminal-app, or any program taking -e. [default: current multiplexer, $TERMINAL, then the first found]"
+    )]
+    pub terminal: Option<String>,
+    #[clap(
+        long,
+        value_name = "EDITOR",
+        help = "Editor for opening source locations: a program such as nvim, emacs, hx, zed, subl or idea, or a template like \"{editor} {file}:{line}:{col}\". [default: $VISUAL, $EDITOR, then code]"
+    )]
+    pub editor: Option<String>,
+    #[clap(
+        long,
+        value_name = "DIAG",
+        help = "Open a numbered diagnostic (e.g. E01, W3, or my_example:E01 after --run-all) of the last filtered run in the editor, then exit."
+    )]
+    pub open: Option<String>,
+    #[clap(
+        long,
+        value_name = "SOCKET",
+        num_args = 0..=1,
+        default_missing_value = "stdio",
+        help = "Serve JSON-RPC for editor extensions (listTargets, run, kill, subscribeDiagnostics, subscribeOutput, openLocation) on stdio, or on a Unix socket at SOCKET."
+    )]
+    pub serve: Option<String>,
 }
 
 /// Print the version and the JSON array of feature flags.
@@ -368,6 +493,83 @@ impl std::fmt::Display for RunAll {
     }
 }
 
+/// Output format of the run report (`--report-format`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Json,
+    Junit,
+}
+
+impl ReportFormat {
+    /// The report file name used when `--report-path` is not given.
+    pub fn default_path(&self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "run_report.md",
+            ReportFormat::Json => "run_report.json",
+            ReportFormat::Junit => "run_report.xml",
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Ok(ReportFormat::Markdown),
+            "json" => Ok(ReportFormat::Json),
+            "junit" | "xml" => Ok(ReportFormat::Junit),
+            other => Err(format!(
+                "unknown report format `{}` (expected markdown, json or junit)",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportFormat::Markdown => write!(f, "markdown"),
+            ReportFormat::Json => write!(f, "json"),
+            ReportFormat::Junit => write!(f, "junit"),
+        }
+    }
+}
+
+/// What `--snapshot` does with each target's runtime output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotMode {
+    /// Write the output to the snapshot file.
+    Record,
+    /// Compare the output with the snapshot file.
+    Check,
+}
+
+impl FromStr for SnapshotMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "record" => Ok(SnapshotMode::Record),
+            "check" => Ok(SnapshotMode::Check),
+            other => Err(format!(
+                "unknown snapshot mode `{}` (expected record or check)",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for SnapshotMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotMode::Record => write!(f, "record"),
+            SnapshotMode::Check => write!(f, "check"),
+        }
+    }
+}
+
 pub fn custom_cli(args: &mut Vec<String>) -> (Option<usize>, Vec<&String>) {
     // If the first argument after the binary name is "e", remove it.
     if args.len() > 1 && args[1].as_str() == "e" {
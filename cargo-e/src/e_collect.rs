@@ -248,6 +248,134 @@ pub fn collect_examples(
     Ok(examples)
 }
 
+/// Runs a single `cargo metadata --no-deps` for the workspace containing `manifest_path`
+/// and returns its member packages keyed by their canonicalized manifest path.
+///
+/// Unlike `collect_examples`/`collect_binaries`, this does not compile anything, so it is
+/// fast on large workspaces. It fails when the manifest itself cannot be parsed; callers
+/// fall back to probing in that case.
+pub fn collect_metadata_packages(
+    manifest_path: &Path,
+) -> Result<HashMap<PathBuf, cargo_metadata::Package>, Box<dyn Error>> {
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(manifest_path)
+        .no_deps()
+        .exec()?;
+    let mut packages = HashMap::new();
+    for package in metadata.workspace_packages() {
+        let package_manifest = package.manifest_path.clone().into_std_path_buf();
+        let key = fs::canonicalize(&package_manifest).unwrap_or(package_manifest);
+        packages.insert(key, package.clone());
+    }
+    Ok(packages)
+}
+
+/// Maps the bins, examples, tests and benches of a `cargo metadata` package into
+/// `CargoTarget`s with a `TargetOrigin::CargoMetadata` origin.
+pub fn collect_metadata_targets(
+    prefix: &str,
+    manifest_path: &Path,
+    package: &cargo_metadata::Package,
+    extended: bool,
+) -> Vec<CargoTarget> {
+    let is_tauri = manifest_path
+        .parent()
+        .and_then(|p| p.file_name())
+        .and_then(|s| s.to_str())
+        == Some("src-tauri");
+
+    package
+        .targets
+        .iter()
+        .filter_map(|target| {
+            let name = target.name.clone();
+            let (kind, toml_specified, display_name) = if target.is_bin() {
+                let kind = if is_tauri {
+                    TargetKind::ManifestTauri
+                } else if extended {
+                    TargetKind::ExtendedBinary
+                } else {
+                    TargetKind::Binary
+                };
+                let display_name = if prefix.starts_with('$') {
+                    format!("{} > binary > {}", prefix, name)
+                } else if prefix.starts_with("builtin") {
+                    format!("builtin binary: {}", name)
+                } else {
+                    format!("{} {}", prefix, name)
+                };
+                (kind, true, display_name)
+            } else if target.is_example() {
+                let kind = if is_tauri {
+                    TargetKind::ManifestTauri
+                } else if extended {
+                    TargetKind::ExtendedExample
+                } else {
+                    TargetKind::Example
+                };
+                (kind, true, name.clone())
+            } else if target.is_test() {
+                (TargetKind::Test, false, name.clone())
+            } else if target.is_bench() {
+                (TargetKind::Bench, false, name.clone())
+            } else {
+                return None;
+            };
+            Some(CargoTarget {
+                name,
+                display_name,
+                manifest_path: manifest_path.into(),
+                kind,
+                extended,
+                toml_specified,
+                origin: Some(TargetOrigin::CargoMetadata {
+                    src_path: target.src_path.clone().into_std_path_buf(),
+                    edition: target.edition.as_str().to_string(),
+                    required_features: target.required_features.clone(),
+                }),
+            })
+        })
+        .collect()
+}
+
+/// Collects `cargo metadata` packages for every manifest, issuing one call per workspace.
+/// Manifests whose metadata could not be loaded are absent from the result.
+fn collect_metadata_for_manifests(
+    manifest_infos: &[(String, PathBuf, bool)],
+) -> HashMap<PathBuf, cargo_metadata::Package> {
+    let mut packages: HashMap<PathBuf, cargo_metadata::Package> = HashMap::new();
+    let mut attempted: Vec<PathBuf> = Vec::new();
+    for (_prefix, manifest_path, _extended) in manifest_infos {
+        let key = fs::canonicalize(manifest_path).unwrap_or_else(|_| manifest_path.clone());
+        if packages.contains_key(&key) || attempted.contains(&key) {
+            continue;
+        }
+        attempted.push(key);
+        match collect_metadata_packages(manifest_path) {
+            Ok(found) => packages.extend(found),
+            Err(e) => debug!(
+                "DEBUG: cargo metadata failed for {}, falling back to probing: {}",
+                manifest_path.display(),
+                e
+            ),
+        }
+    }
+    packages
+}
+
+/// Returns the metadata targets for `manifest_path` if its package was resolved.
+fn metadata_targets_for(
+    packages: &HashMap<PathBuf, cargo_metadata::Package>,
+    prefix: &str,
+    manifest_path: &Path,
+    extended: bool,
+) -> Option<Vec<CargoTarget>> {
+    let key = fs::canonicalize(manifest_path).unwrap_or_else(|_| manifest_path.to_path_buf());
+    packages
+        .get(&key)
+        .map(|package| collect_metadata_targets(prefix, manifest_path, package, extended))
+}
+
 // --- Concurrent or sequential collection ---
 pub fn collect_samples(
     _workspace_mode: bool,
@@ -255,6 +383,10 @@ pub fn collect_samples(
     __max_concurrency: usize,
 ) -> Result<Vec<CargoTarget>, Box<dyn Error>> {
     let mut all_samples = Vec::new();
+    // One `cargo metadata` call per workspace replaces the per-manifest `cargo run` probes.
+    let start_metadata = Instant::now();
+    let metadata_packages = Arc::new(collect_metadata_for_manifests(&manifest_infos));
+    debug!("timing: cargo metadata took {:?}", start_metadata.elapsed());
 
     #[cfg(feature = "concurrent")]
     {
@@ -266,20 +398,40 @@ pub fn collect_samples(
         for (_prefix, manifest_path, _extended) in manifest_infos {
             let tx = tx.clone();
             let manifest_clone = manifest_path.clone();
+            let metadata_packages = Arc::clone(&metadata_packages);
             pool.execute(move || {
                 let prefix_clone = _prefix.clone(); // Define prefix_clone here
                                                     // 1. Collect the builtin stuff
                 let mut builtin_examples = Vec::new();
-                if let Ok(mut ex) =
-                    collect_examples(&prefix_clone, &manifest_clone, _workspace_mode)
-                {
-                    builtin_examples.append(&mut ex);
-                }
                 let mut builtin_bins = Vec::new();
-                if let Ok(mut bins) =
-                    collect_binaries(&prefix_clone, &manifest_clone, _workspace_mode)
-                {
-                    builtin_bins.append(&mut bins);
+                let mut metadata_others = Vec::new();
+                if let Some(targets) = metadata_targets_for(
+                    &metadata_packages,
+                    &prefix_clone,
+                    &manifest_clone,
+                    _workspace_mode,
+                ) {
+                    for target in targets {
+                        match target.kind {
+                            TargetKind::Test | TargetKind::Bench => metadata_others.push(target),
+                            TargetKind::Binary | TargetKind::ExtendedBinary => {
+                                builtin_bins.push(target)
+                            }
+                            _ => builtin_examples.push(target),
+                        }
+                    }
+                } else {
+                    // Broken manifest: fall back to probing `cargo run`.
+                    if let Ok(mut ex) =
+                        collect_examples(&prefix_clone, &manifest_clone, _workspace_mode)
+                    {
+                        builtin_examples.append(&mut ex);
+                    }
+                    if let Ok(mut bins) =
+                        collect_binaries(&prefix_clone, &manifest_clone, _workspace_mode)
+                    {
+                        builtin_bins.append(&mut bins);
+                    }
                 }
                 debug!(
                     "DEBUG: {} builtin examples = {:?}",
@@ -300,6 +452,7 @@ pub fn collect_samples(
                     && runnable_examples.is_empty()
                     && builtin_bins.is_empty()
                     && builtin_examples.is_empty()
+                    && metadata_others.is_empty()
                 {
                     return;
                 }
@@ -309,10 +462,13 @@ pub fn collect_samples(
 
                 // 2) For each runnable:
                 //    – if it matches a builtin by name, overwrite that slot
+                //      (metadata targets are kept unless the runnable is a subproject)
                 //    – otherwise push to the end
                 for runnable in runnable_bins {
                     if let Some(idx) = bins.iter().position(|b| b.name == runnable.name) {
-                        bins[idx] = runnable;
+                        if prefer_runnable(&bins[idx], &runnable) {
+                            bins[idx] = runnable;
+                        }
                     } else {
                         bins.push(runnable);
                     }
@@ -321,7 +477,9 @@ pub fn collect_samples(
                 let mut examples = builtin_examples;
                 for runnable in runnable_examples {
                     if let Some(idx) = examples.iter().position(|e| e.name == runnable.name) {
-                        examples[idx] = runnable;
+                        if prefer_runnable(&examples[idx], &runnable) {
+                            examples[idx] = runnable;
+                        }
                     } else {
                         examples.push(runnable);
                     }
@@ -348,10 +506,16 @@ pub fn collect_samples(
                 // let examples: Vec<_> = ex_map.into_values().collect();
 
                 debug!("DEBUG: merged examples = {:#?}", examples);
-                // 5. Now combine everything
+                // 5. Now combine everything; metadata benches/tests replace the manifest-parsed ones.
+                let (benches, tests) = if metadata_others.is_empty() {
+                    (benches, tests)
+                } else {
+                    (Vec::new(), Vec::new())
+                };
                 let all_targets = bins
                     .into_iter()
                     .chain(examples)
+                    .chain(metadata_others)
                     .chain(benches)
                     .chain(tests)
                     .collect::<Vec<_>>();
@@ -460,6 +624,26 @@ pub fn collect_samples(
             let (bins, examples, benches, tests) =
                 crate::e_manifest::get_runnable_targets(&manifest_path).unwrap_or_default();
 
+            // Prefer cargo metadata; the manifest-parsed targets only add what it does not know about.
+            if let Some(mut targets) = metadata_targets_for(
+                &metadata_packages,
+                &_prefix,
+                &manifest_path,
+                _workspace_mode,
+            ) {
+                for runnable in bins.into_iter().chain(examples) {
+                    if let Some(idx) = targets.iter().position(|t| t.name == runnable.name) {
+                        if prefer_runnable(&targets[idx], &runnable) {
+                            targets[idx] = runnable;
+                        }
+                    } else {
+                        targets.push(runnable);
+                    }
+                }
+                all_samples.extend(targets);
+                continue;
+            }
+
             // Merge all targets into one collection.
             all_samples.extend(bins);
             all_samples.extend(examples);
@@ -581,6 +765,13 @@ pub fn collect_samples(
     // Ok(all_samples)
 }
 
+/// Whether a manifest-parsed target should replace a previously collected one of the same name.
+/// `cargo metadata` targets already carry the resolved source path, so only subprojects win.
+fn prefer_runnable(existing: &CargoTarget, runnable: &CargoTarget) -> bool {
+    !matches!(existing.origin, Some(TargetOrigin::CargoMetadata { .. }))
+        || matches!(runnable.origin, Some(TargetOrigin::SubProject(_)))
+}
+
 use std::fs;
 use std::path::Path;
 
@@ -591,22 +782,33 @@ pub fn path_depth(path: &Path) -> usize {
 
 /// Deduplicates targets by their canonicalized origin, gives priority to `toml_specified`,
 /// and ensures single-file targets override default binaries when appropriate.
+/// `cargo metadata` targets are keyed by kind and source path; targets without a
+/// source path are all kept.
 pub fn dedup_single_file_over_default_binary(targets: Vec<CargoTarget>) -> Vec<CargoTarget> {
-    let mut map: HashMap<Option<String>, CargoTarget> = HashMap::new();
+    let mut map: HashMap<String, CargoTarget> = HashMap::new();
+    let mut unkeyed = Vec::new();
+    let canonical = |path: &Path| {
+        fs::canonicalize(path)
+            .ok()
+            .map(|p| p.to_string_lossy().into_owned())
+    };
 
     for target in targets {
         // Compute canonical origin key
         let origin_key = target.origin.as_ref().and_then(|origin| match origin {
             TargetOrigin::SingleFile(path)
             | TargetOrigin::DefaultBinary(path)
-            | TargetOrigin::SubProject(path) => fs::canonicalize(path)
-                .ok()
-                .map(|p| p.to_string_lossy().into_owned()),
+            | TargetOrigin::SubProject(path) => canonical(path),
+            TargetOrigin::CargoMetadata { src_path, .. } => {
+                canonical(src_path).map(|path| format!("{}:{}", target.kind.section_name(), path))
+            }
             _ => None,
         });
 
-        // Use Some(key) or None as map key
-        let entry_key = origin_key.clone();
+        let Some(entry_key) = origin_key else {
+            unkeyed.push(target);
+            continue;
+        };
 
         if let Some(existing) = map.get(&entry_key) {
             // 1) Prioritize toml_specified
@@ -643,7 +845,7 @@ pub fn dedup_single_file_over_default_binary(targets: Vec<CargoTarget>) -> Vec<C
         }
     }
 
-    map.into_values().collect()
+    map.into_values().chain(unkeyed).collect()
 }
 
 #[cfg(feature = "concurrent")]
@@ -762,7 +964,10 @@ pub fn collect_all_targets(
         manifest_infos.push(("-".to_string(), bi.clone(), false));
     }
 
-    let samples = collect_samples(use_workspace, manifest_infos, max_concurrency)?;
+    let manifests: Vec<PathBuf> = manifest_infos.iter().map(|(_, m, _)| m.clone()).collect();
+    let samples = crate::e_target_cache::cached_collect(&manifests, use_workspace, || {
+        collect_samples(use_workspace, manifest_infos, max_concurrency)
+    })?;
     // Deduplicate targets: if a SingleFile and DefaultBinary share the same origin, keep only the SingleFile.
     // let deduped_samples = dedup_single_file_over_default_binary(samples);
     // Ok(deduped_samples)
@@ -797,7 +1002,10 @@ pub fn collect_all_targets_silent(
         manifest_infos.push(("-".to_string(), bi.clone(), false));
     }
 
-    let samples = collect_samples(use_workspace, manifest_infos, max_concurrency)?;
+    let manifests: Vec<PathBuf> = manifest_infos.iter().map(|(_, m, _)| m.clone()).collect();
+    let samples = crate::e_target_cache::cached_collect(&manifests, use_workspace, || {
+        collect_samples(use_workspace, manifest_infos, max_concurrency)
+    })?;
     let deduped_samples = dedup_single_file_over_default_binary(samples);
     Ok(deduped_samples)
 }
@@ -59,9 +59,32 @@ pub struct CargoCommandBuilder {
     pub time_limit: Option<u32>,
     pub detached_hold: Option<u32>,
     pub detached_delay: Option<u32>,
+    /// Preferred terminal launcher for `--detached` (see [`crate::e_terminal`]).
+    pub terminal: Option<String>,
+    /// Run filtered targets under a pseudo-terminal when cargo-e is in one (Unix).
+    pub use_pty: bool,
+    /// Where captured output goes instead of the terminal (the TUI output pane).
+    pub output_sink: Option<crate::e_eventdispatcher::OutputSink>,
     pub cwd_wsr: bool,
+    /// Read diagnostics from cargo's JSON messages instead of regex-matching stderr.
+    pub json_diagnostics: bool,
+    /// Shared with the stderr dispatcher; cleared when the JSON path is active.
+    pub regex_diagnostics_enabled: Arc<AtomicBool>,
+    /// Environment variables set on the spawned command.
+    pub envs: Vec<(String, String)>,
+    /// Name of the run profile applied with `with_profile`.
+    pub profile: Option<String>,
+    /// File connected to the spawned command's stdin.
+    pub stdin_file: Option<PathBuf>,
+    /// Executable built ahead of time, run directly instead of through cargo.
+    pub artifact: Option<PathBuf>,
 }
 
+/// Subcommands that accept `--message-format` and `--color`.
+const MESSAGE_FORMAT_SUBCOMMANDS: [&str; 8] = [
+    "run", "build", "test", "bench", "clean", "doc", "publish", "update",
+];
+
 impl std::fmt::Display for CargoCommandBuilder {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -135,7 +158,16 @@ impl CargoCommandBuilder {
             time_limit: None,
             detached_hold: None,
             detached_delay: None,
+            terminal: None,
+            use_pty: true,
+            output_sink: None,
             cwd_wsr,
+            json_diagnostics: true,
+            regex_diagnostics_enabled: Arc::new(AtomicBool::new(true)),
+            envs: Vec::new(),
+            profile: None,
+            stdin_file: None,
+            artifact: None,
         };
         builder.set_default_dispatchers();
         builder
@@ -147,6 +179,8 @@ impl CargoCommandBuilder {
         F: FnOnce(u32, Arc<Mutex<CargoProcessHandle>>),
     {
         let mut command = self.build_command();
+        #[cfg(feature = "uses_plugins")]
+        command.envs(crate::plugins::hooks::before_build(&self));
 
         // Now, spawn the cargo process in passthrough mode
         let cargo_process_handle = command.spawn_cargo_passthrough(Arc::clone(&self));
@@ -176,34 +210,21 @@ impl CargoCommandBuilder {
                  stats: std::sync::Arc<std::sync::Mutex<crate::e_cargocommand_ext::CargoStats>>,
                  _prior_response: Option<crate::e_eventdispatcher::CallbackResponse>|
                  -> Option<crate::e_eventdispatcher::CallbackResponse> {
-                    println!("(STDOUT) Dispatcher caught: {}", line);
-                    // Use a regex to capture a URL from the line.
-                    // Move the regex construction outside the closure to avoid lifetime issues.
-                    static URL_REGEX: once_cell::sync::Lazy<Regex> =
-                        once_cell::sync::Lazy::new(|| Regex::new(r"(http://[^\s]+)").unwrap());
-                    if let Some(url_caps) = URL_REGEX.captures(line) {
-                        if let Some(url_match) = url_caps.get(1) {
-                            let url = url_match.as_str();
-                            // Call open::that on the captured URL.
-                            if let Err(e) = open::that_detached(url) {
-                                eprintln!("Failed to open URL: {}. Error: {}", url, e);
-                            } else {
-                                println!("Opened URL: {}", url);
-                            }
-                        }
-                    }
+                    crate::out_println!("(STDOUT) Dispatcher caught: {}", line);
+                    crate::e_diagnostics_dispatchers::open_server_url(line);
                     let mut stats = stats.lock().unwrap();
                     // Add debug statements to trace stats changes
-                    println!("[DEBUG] Locked stats: {:?}", *stats);
+                    crate::out_println!("[DEBUG] Locked stats: {:?}", *stats);
+                    let now = SystemTime::now();
                     if stats.build_finished_time.is_none() {
-                        let now = SystemTime::now();
                         stats.build_finished_time = Some(now);
                         // Add debug statements to trace stats changes
-                        println!(
+                        crate::out_println!(
                             "[DEBUG] Updated stats.build_finished_time: {:?}",
                             stats.build_finished_time
                         );
                     }
+                    stats.listening_time.get_or_insert(now);
                     None
                 },
             )
@@ -225,15 +246,15 @@ impl CargoCommandBuilder {
         stdout_dispatcher.add_callback(
             r"BuildFinished",
             Box::new(move |line, _captures, _state, stats, _prior_response| {
-                println!("******* {}", line);
+                crate::out_println!("******* {}", line);
                 let mut stats = stats.lock().unwrap();
                 // Add debug statements to trace stats changes
-                println!("[DEBUG] Locked stats: {:?}", *stats);
+                crate::out_println!("[DEBUG] Locked stats: {:?}", *stats);
                 if stats.build_finished_time.is_none() {
                     let now = SystemTime::now();
                     stats.build_finished_time = Some(now);
                     // Add debug statements to trace stats changes
-                    println!(
+                    crate::out_println!(
                         "[DEBUG] Updated stats.build_finished_time: {:?}",
                         stats.build_finished_time
                     );
@@ -246,7 +267,7 @@ impl CargoCommandBuilder {
             Box::new(move |line, _captures, state, stats, _prior_response| {
                 // If we're not already in multiline mode, this is the initial match.
                 if !state.load(Ordering::Relaxed) {
-                    println!("Matched 'server listening at:' in: {}", line);
+                    crate::out_println!("Matched 'server listening at:' in: {}", line);
                     state.store(true, Ordering::Relaxed);
                     Some(CallbackResponse {
                         callback_type: CallbackType::Note, // Choose as appropriate
@@ -259,12 +280,12 @@ impl CargoCommandBuilder {
                     })
                 } else {
                     // We are in multiline mode; process subsequent lines.
-                    println!("Multiline callback received: {}", line);
+                    crate::out_println!("Multiline callback received: {}", line);
                     // Use a regex to capture a URL from the line.
                     let url_regex = match Regex::new(r"(http://[^\s]+)") {
                         Ok(regex) => regex,
                         Err(e) => {
-                            eprintln!("Failed to create URL regex: {}", e);
+                            crate::out_eprintln!("Failed to create URL regex: {}", e);
                             return None;
                         }
                     };
@@ -272,8 +293,10 @@ impl CargoCommandBuilder {
                         let url = url_caps.get(1).unwrap().as_str();
                         // Call open::that on the captured URL.
                         match open::that_detached(url) {
-                            Ok(_) => println!("Opened URL: {}", url),
-                            Err(e) => eprintln!("Failed to open URL: {}. Error: {}", url, e),
+                            Ok(_) => crate::out_println!("Opened URL: {}", url),
+                            Err(e) => {
+                                crate::out_eprintln!("Failed to open URL: {}. Error: {}", url, e)
+                            }
                         }
                         let mut stats = stats.lock().unwrap();
                         if stats.build_finished_time.is_none() {
@@ -337,8 +360,8 @@ impl CargoCommandBuilder {
                             .unwrap_or("0")
                             .parse()
                             .unwrap_or(0);
-                        println!("\n\n\n");
-                        println!("{}", line);
+                        crate::out_println!("\n\n\n");
+                        crate::out_println!("{}", line);
                         // Use a global TTS instance via OnceCell for program lifetime
 
                         #[cfg(feature = "uses_tts")]
@@ -364,7 +387,7 @@ impl CargoCommandBuilder {
                                     "thread {} panic, {} line {}",
                                     thread, filename, line_num
                                 );
-                                println!("TTS: {}", speech);
+                                crate::out_println!("TTS: {}", speech);
                                 crate::e_runner::wait_for_tts_to_finish(15000);
                                 let mut tts = tts_mutex.lock().expect("Failed to lock TTS mutex");
                                 let _ = tts.speak(&speech, false);
@@ -372,11 +395,15 @@ impl CargoCommandBuilder {
                             }
                         }
 
-                        println!(
+                        crate::out_println!(
                             "Panic detected: thread='{}', message='{}', file='{}:{}:{}'",
-                            thread, message, file, line_num, col_num
+                            thread,
+                            message,
+                            file,
+                            line_num,
+                            col_num
                         );
-                        println!("\n\n\n");
+                        crate::out_println!("\n\n\n");
                         Some(CallbackResponse {
                             callback_type: CallbackType::Error,
                             message: Some(format!(
@@ -409,7 +436,7 @@ impl CargoCommandBuilder {
                                 pid_for_panic.unwrap_or_default(),
                                 stats.clone(),
                             );
-                            println!("[DEBUG] dispatch stats: {:?}", stats);
+                            crate::out_println!("[DEBUG] dispatch stats: {:?}", stats);
                         }
                         #[cfg(feature = "uses_tts")]
                         {
@@ -421,7 +448,7 @@ impl CargoCommandBuilder {
                                 });
 
                                 let speech = format!("panic says {}", line);
-                                println!("TTS: {}", speech);
+                                crate::out_println!("TTS: {}", speech);
                                 crate::e_runner::wait_for_tts_to_finish(15000);
                                 let mut tts = tts_mutex.lock().expect("Failed to lock TTS mutex");
                                 let _ = tts.speak(&speech, true);
@@ -438,7 +465,7 @@ impl CargoCommandBuilder {
         stderr_dispatcher.add_callback(
             r"error: could not compile `(?P<crate_name>.+)` \((?P<due_to>.+)\) due to (?P<error_count>\d+) previous errors; (?P<warning_count>\d+) warnings emitted",
             Box::new(|line, captures, _state, stats, _prior_response| {
-                println!("{}", line);
+                crate::out_println!("{}", line);
             if let Some(caps) = captures {
                 // Extract dynamic fields from the error message
                 let crate_name = caps.name("crate_name").map(|m| m.as_str()).unwrap_or("unknown");
@@ -453,7 +480,7 @@ impl CargoCommandBuilder {
                 .unwrap_or(0);
 
                 // Log the captured information (optional)
-                println!(
+                crate::out_println!(
                 "Detected compilation failure: crate=`{}`, due_to=`{}`, errors={}, warnings={}",
                 crate_name, due_to, error_count, warning_count
                 );
@@ -468,10 +495,17 @@ impl CargoCommandBuilder {
 
         // Clone diagnostics_arc for this closure to avoid move
         let diagnostics_arc_for_diag = Arc::clone(&diagnostics_arc);
+        #[cfg(feature = "uses_plugins")]
+        let hook_target = self.target_name.clone();
+        let regex_diagnostics_enabled = Arc::clone(&self.regex_diagnostics_enabled);
         stderr_dispatcher.add_callback(
             r"^(?P<level>\w+)(\[(?P<error_code>E\d+)\])?:\s+(?P<msg>.+)$", // Regex for diagnostic line
             Box::new(
                 move |_line, caps, _multiline_flag, _stats, _prior_response| {
+                    if !regex_diagnostics_enabled.load(Ordering::Relaxed) {
+                        // Diagnostics are coming from cargo's JSON messages instead.
+                        return None;
+                    }
                     if let Some(caps) = caps {
                         let mut counts = counts.lock().unwrap();
                         // Create a PendingDiag and save the message
@@ -480,6 +514,8 @@ impl CargoCommandBuilder {
                         if let Some(existing_diag) = pending_diag.take() {
                             let mut diags = diagnostics_arc_for_diag.lock().unwrap();
                             last_lineref = existing_diag.lineref.clone();
+                            #[cfg(feature = "uses_plugins")]
+                            crate::plugins::hooks::on_diagnostic(&hook_target, &existing_diag);
                             diags.push(existing_diag.clone());
                         }
                         log::trace!("Diagnostic line: {}", _line);
@@ -500,7 +536,7 @@ impl CargoCommandBuilder {
                             "help" => CargoDiagnosticLevel::Help,
                             "note" => CargoDiagnosticLevel::Note,
                             _ => {
-                                println!("Unknown diagnostic level: {}", level);
+                                crate::out_println!("Unknown diagnostic level: {}", level);
                                 return None; // Ignore unknown levels
                             }
                         };
@@ -519,6 +555,8 @@ impl CargoCommandBuilder {
                             uses_color: true,
                             diag_num_padding: Some(2),
                             diag_number: Some(*current_count),
+                            spans: Vec::new(),
+                            children: Vec::new(),
                         };
 
                         // Save the new diagnostic
@@ -535,7 +573,7 @@ impl CargoCommandBuilder {
                             terminal_status: None,
                         });
                     } else {
-                        println!("No captures found in line: {}", _line);
+                        crate::out_println!("No captures found in line: {}", _line);
                         None
                     }
                 },
@@ -599,6 +637,8 @@ impl CargoCommandBuilder {
                 let backtrace_lines = Arc::clone(&backtrace_lines);
                 let pending_diag = Arc::clone(&pending_diag);
                 let diagnostics_arc = Arc::clone(&diagnostics_arc);
+                #[cfg(feature = "uses_plugins")]
+                let hook_target = self.target_name.clone();
 
                 // Regex for numbered backtrace line: "  0: type::path"
                 let re_number_type = Regex::new(r"^\s*(\d+):\s+(.*)$").unwrap();
@@ -678,6 +718,11 @@ impl CargoCommandBuilder {
                                             }
                                             note.push_str(&bt_lines.join("\n"));
                                             let mut diags = diagnostics_arc.lock().unwrap();
+                                            #[cfg(feature = "uses_plugins")]
+                                            crate::plugins::hooks::on_diagnostic(
+                                                &hook_target,
+                                                diag,
+                                            );
                                             diags.push(diag.clone());
                                         }
                                     }
@@ -765,6 +810,8 @@ impl CargoCommandBuilder {
             let suggestion_m = Arc::clone(&suggestion_mode);
             let pending_diag_clone = Arc::clone(&pending_diag);
             let diagnostics_arc = Arc::clone(&self.diagnostics);
+            #[cfg(feature = "uses_plugins")]
+            let hook_target = self.target_name.clone();
             // Callback for handling when an empty line or new diagnostic is received
             stderr_dispatcher.add_callback(
                 r"^\s*$", // Regex to capture empty line
@@ -777,6 +824,8 @@ impl CargoCommandBuilder {
                             //println!("{:?}", pending_diag);
                             // Use diagnostics_arc instead of self.diagnostices
                             let mut diags = diagnostics_arc.lock().unwrap();
+                            #[cfg(feature = "uses_plugins")]
+                            crate::plugins::hooks::on_diagnostic(&hook_target, &pending_diag);
                             diags.push(pending_diag.clone());
                         } else {
                             // println!("No pending diagnostic to process.");
@@ -896,7 +945,7 @@ impl CargoCommandBuilder {
                                     let mut pending_diag = match pending_diag.lock() {
                                         Ok(lock) => lock,
                                         Err(e) => {
-                                            eprintln!("Failed to acquire lock: {}", e);
+                                            crate::out_eprintln!("Failed to acquire lock: {}", e);
                                             return None; // Handle the error appropriately
                                         }
                                     };
@@ -992,7 +1041,7 @@ impl CargoCommandBuilder {
                             suggestion_mode.store(true, Ordering::Relaxed);
                             return Some(resp.clone());
                         } else {
-                            println!("No captures found in line: {}", _line);
+                            crate::out_println!("No captures found in line: {}", _line);
                         }
                         // }
                         None
@@ -1062,10 +1111,10 @@ impl CargoCommandBuilder {
         if let Some(caps) = captures {
             let url = caps.get(1).unwrap().as_str();
             let url = url.replace("0.0.0.0", "127.0.0.1");
-            println!("(STDERR) Captured URL: {}", url);
+            crate::out_println!("(STDERR) Captured URL: {}", url);
             match open::that_detached(&url) {
-                Ok(_) => println!("(STDERR) Opened URL: {}",&url),
-                Err(e) => eprintln!("(STDERR) Failed to open URL: {}. Error: {:?}", url, e),
+                Ok(_) => crate::out_println!("(STDERR) Opened URL: {}",&url),
+                Err(e) => crate::out_eprintln!("(STDERR) Failed to open URL: {}. Error: {:?}", url, e),
             }
              let mut stats = stats.lock().unwrap();
              if stats.build_finished_time.is_none() {
@@ -1082,7 +1131,7 @@ impl CargoCommandBuilder {
                 terminal_status: None,
             })
         } else {
-            println!("(STDERR) No URL captured in line: {}", line);
+            crate::out_println!("(STDERR) No URL captured in line: {}", line);
             None
         }
     }),
@@ -1138,7 +1187,7 @@ impl CargoCommandBuilder {
             let cmd      = caps["cmd"].to_string();
             let fixes: usize = caps["fixes"].parse().unwrap_or(0);
 
-            println!("SUMMARIZATION CALLBACK {}",
+            crate::out_println!("SUMMARIZATION CALLBACK {}",
                     &format!("{}: `{}` ({}) generated {} {}; run `{}` to apply {} fixes",
                     level, name, otype, count, kind, cmd, fixes));
             Some(CallbackResponse {
@@ -1231,7 +1280,7 @@ impl CargoCommandBuilder {
         stderr_dispatcher.add_callback(
             r"IO\(Custom \{ kind: NotConnected",
             Box::new(move |line, _captures, _state, _stats, _prior_response| {
-                println!("(STDERR) Terminal error detected: {:?}", &line);
+                crate::out_println!("(STDERR) Terminal error detected: {:?}", &line);
                 let result = if line.contains("NotConnected") {
                     TerminalError::NoTerminal
                 } else {
@@ -1300,7 +1349,11 @@ impl CargoCommandBuilder {
             return self.switch_to_passthrough_mode(on_spawn);
         }
 
+        self.regex_diagnostics_enabled
+            .store(!self.uses_json_diagnostics(), Ordering::Relaxed);
         let mut command = self.build_command();
+        #[cfg(feature = "uses_plugins")]
+        command.envs(crate::plugins::hooks::before_build(&self));
         let mut cargo_process_handle = command.spawn_cargo_capture(
             self.clone(),
             self.stdout_dispatcher.clone(),
@@ -1667,7 +1720,8 @@ impl CargoCommandBuilder {
                 // Try candidate's parent (if origin is SingleFile or DefaultBinary).
                 let candidate_dir_opt = match &target.origin {
                     Some(TargetOrigin::SingleFile(path))
-                    | Some(TargetOrigin::DefaultBinary(path)) => path.parent(),
+                    | Some(TargetOrigin::DefaultBinary(path))
+                    | Some(TargetOrigin::CargoMetadata { src_path: path, .. }) => path.parent(),
                     _ => None,
                 };
 
@@ -1958,11 +2012,176 @@ impl CargoCommandBuilder {
         if cli.detached {
             self.detached = true;
         }
+        if cli.terminal.is_some() {
+            self.terminal = cli.terminal.clone();
+        }
+        // Only one target can own the terminal at a time.
+        if cli.no_pty || cli.run_at_a_time > 1 {
+            self.use_pty = false;
+        }
+        if cli.regex_diagnostics {
+            self.json_diagnostics = false;
+        }
         // Append extra arguments (if any) after a "--" separator.
         if !cli.extra.is_empty() {
             self.args.push("--".into());
             self.args.extend(cli.extra.iter().cloned());
         }
+        if let Some(ov) = crate::e_config::target_override(&self.target_name) {
+            self = self.with_target_override(&ov);
+        }
+        self
+    }
+
+    /// Collect everything the capture threads print into `sink` rather than the terminal.
+    pub fn with_output_sink(mut self, sink: crate::e_eventdispatcher::OutputSink) -> Self {
+        self.output_sink = Some(sink);
+        self
+    }
+
+    /// Feed every line the target prints to `monitor` too. Only filtered runs are captured.
+    pub fn with_expectations(self, monitor: Arc<crate::e_expect::Monitor>) -> Self {
+        use crate::e_expect::Stream;
+        for (dispatcher, stream) in [
+            (&self.stdout_dispatcher, Stream::Stdout),
+            (&self.stderr_dispatcher, Stream::Stderr),
+        ] {
+            if let Some(dispatcher) = dispatcher {
+                // Clones share their callback list, so this adds to the builder's dispatcher.
+                let mut dispatcher = EventDispatcher::clone(dispatcher);
+                let monitor = Arc::clone(&monitor);
+                dispatcher.add_callback(
+                    r".*",
+                    Box::new(move |line, _captures, _state, _stats, _prior_response| {
+                        monitor.line(stream, line);
+                        None
+                    }),
+                );
+            }
+        }
+        self
+    }
+
+    /// Collect the target's runtime stdout, whatever it prints once cargo has finished building.
+    pub fn with_snapshot(self, capture: Arc<crate::e_snapshot::Capture>) -> Self {
+        if let Some(dispatcher) = &self.stdout_dispatcher {
+            let mut dispatcher = EventDispatcher::clone(dispatcher);
+            dispatcher.add_callback(
+                r".*",
+                Box::new(move |line, _captures, _state, stats, _prior_response| {
+                    let running = stats
+                        .lock()
+                        .map(|s| s.build_finished_time.is_some())
+                        .unwrap_or(false);
+                    if running {
+                        capture.push(line);
+                    }
+                    None
+                }),
+            );
+        }
+        self
+    }
+
+    /// Run `exe`, already built, directly instead of through `cargo run`.
+    ///
+    /// Only the target's own arguments (after `--`) are kept, and `diagnostics` from
+    /// its build are reported with the run. As under `cargo run`, `CARGO_MANIFEST_DIR`
+    /// is set and the build's `deps` directory is on the dynamic library search path.
+    pub fn with_artifact(mut self, exe: &Path, diagnostics: &[CargoDiagnostic]) -> Self {
+        self.args = match self.args.iter().position(|arg| arg == "--") {
+            Some(pos) => self.args.split_off(pos + 1),
+            None => Vec::new(),
+        };
+        self.alternate_cmd = Some(exe.to_string_lossy().to_string());
+        self.artifact = Some(exe.to_path_buf());
+        self.diagnostics
+            .lock()
+            .unwrap()
+            .extend(diagnostics.iter().cloned());
+
+        let manifest_path = self
+            .manifest_path
+            .canonicalize()
+            .unwrap_or_else(|_| self.manifest_path.clone());
+        if let Some(dir) = manifest_path.parent() {
+            self.envs.insert(
+                0,
+                (
+                    "CARGO_MANIFEST_DIR".to_string(),
+                    dir.to_string_lossy().to_string(),
+                ),
+            );
+        }
+        // target/<profile>/examples/name or target/<profile>/name
+        let profile_dir = exe
+            .parent()
+            .map(|dir| match dir.file_name() {
+                Some(name) if name == "examples" => dir.parent().unwrap_or(dir),
+                _ => dir,
+            })
+            .map(Path::to_path_buf);
+        if let Some(profile_dir) = profile_dir {
+            let var = if cfg!(target_os = "windows") {
+                "PATH"
+            } else if cfg!(target_os = "macos") {
+                "DYLD_FALLBACK_LIBRARY_PATH"
+            } else {
+                "LD_LIBRARY_PATH"
+            };
+            let mut paths = vec![profile_dir.join("deps"), profile_dir];
+            if let Some(existing) = env::var_os(var) {
+                paths.extend(env::split_paths(&existing));
+            }
+            if let Ok(joined) = env::join_paths(paths) {
+                // Ahead of anything a profile or the configuration sets.
+                self.envs
+                    .insert(0, (var.to_string(), joined.to_string_lossy().to_string()));
+            }
+        }
+        self
+    }
+
+    /// Apply a named run profile: its arguments, environment and stdin.
+    pub fn with_profile(mut self, profile: &crate::e_config::RunProfile) -> Self {
+        if !profile.args.is_empty() {
+            if !self.args.iter().any(|arg| arg == "--") {
+                self.args.push("--".into());
+            }
+            self.args.extend(profile.args.iter().cloned());
+        }
+        self.envs
+            .extend(profile.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+        if profile.stdin.is_some() {
+            self.stdin_file = profile.stdin.clone();
+        }
+        self.profile = Some(profile.name.clone());
+        self
+    }
+
+    /// Apply per-target settings from the configuration files.
+    pub fn with_target_override(mut self, ov: &crate::e_config::TargetOverride) -> Self {
+        if let Some(runner) = &ov.runner {
+            // Cargo options must come before the "--" separator.
+            let pos = self
+                .args
+                .iter()
+                .position(|arg| arg == "--")
+                .unwrap_or(self.args.len());
+            self.args.insert(pos, "--config".into());
+            self.args.insert(
+                pos + 1,
+                format!("target.'cfg(all())'.runner = {:?}", runner),
+            );
+        }
+        if !ov.args.is_empty() {
+            if !self.args.iter().any(|arg| arg == "--") {
+                self.args.push("--".into());
+            }
+            self.args.extend(ov.args.iter().cloned());
+        }
+        self.envs
+            .extend(ov.env.iter().map(|(k, v)| (k.clone(), v.clone())));
         self
     }
     /// Append required features based on the manifest, target kind, and name.
@@ -1970,6 +2189,17 @@ impl CargoCommandBuilder {
     /// appends "--features" and the feature list.
     pub fn with_required_features(mut self, manifest: &PathBuf, target: &CargoTarget) -> Self {
         if !self.args.contains(&"--features".to_string()) {
+            // cargo metadata already resolved the required features; no need to re-read the manifest.
+            if let Some(TargetOrigin::CargoMetadata {
+                required_features, ..
+            }) = &target.origin
+            {
+                if !required_features.is_empty() {
+                    self.args.push("--features".to_string());
+                    self.args.push(required_features.join(","));
+                }
+                return self;
+            }
             if let Some(features) = crate::e_manifest::get_required_features_from_manifest(
                 manifest,
                 &target.kind,
@@ -2017,19 +2247,45 @@ impl CargoCommandBuilder {
         false
     }
 
+    /// Returns true when diagnostics are read from cargo's JSON messages
+    /// (`--message-format=json-diagnostic-rendered-ansi`) rather than
+    /// regex-matched from rendered stderr. Non-cargo runners such as trunk
+    /// and dx keep the regex path.
+    pub fn uses_json_diagnostics(&self) -> bool {
+        if !self.is_filter || !self.json_diagnostics {
+            return false;
+        }
+        if self.use_cache || self.default_binary_is_runner {
+            return false;
+        }
+        if let Some(alternate) = &self.alternate_cmd {
+            if alternate != "cargo" {
+                return false;
+            }
+        }
+        self.args
+            .iter()
+            .any(|arg| MESSAGE_FORMAT_SUBCOMMANDS.contains(&arg.as_str()))
+    }
+
     pub fn injected_args(&self) -> (String, Vec<String>) {
+        if let Some(exe) = &self.artifact {
+            return (exe.to_string_lossy().to_string(), self.args.clone());
+        }
         let mut new_args = self.args.clone();
-        let supported_subcommands = [
-            "run", "build", "test", "bench", "clean", "doc", "publish", "update",
-        ];
 
         if self.is_filter {
             if let Some(pos) = new_args
                 .iter()
-                .position(|arg| supported_subcommands.contains(&arg.as_str()))
+                .position(|arg| MESSAGE_FORMAT_SUBCOMMANDS.contains(&arg.as_str()))
             {
                 // If the command is a supported subcommand like "cargo run", insert the JSON output format and color options.
-                new_args.insert(pos + 1, "--message-format=json".into());
+                let message_format = if self.uses_json_diagnostics() {
+                    "--message-format=json-diagnostic-rendered-ansi"
+                } else {
+                    "--message-format=json"
+                };
+                new_args.insert(pos + 1, message_format.into());
                 new_args.insert(pos + 2, "--color".into());
                 new_args.insert(pos + 3, "always".into());
             }
@@ -2088,7 +2344,7 @@ impl CargoCommandBuilder {
 
     pub fn print_command(&self) {
         let (program, new_args) = self.injected_args();
-        println!("{} {}", program, new_args.join(" "));
+        crate::out_println!("{} {}", program, new_args.join(" "));
     }
 
     /// builds a std::process::Command.
@@ -2162,26 +2418,25 @@ impl CargoCommandBuilder {
                 }
                 detached_cmd
             }
-            #[cfg(target_os = "linux")]
-            {
-                let mut detached_cmd = Command::new("xterm");
-                detached_cmd.args(&["-e", &program]);
-                detached_cmd.args(&new_args);
-                detached_cmd
-            }
-            #[cfg(target_os = "macos")]
+            #[cfg(not(target_os = "windows"))]
             {
-                let mut detached_cmd = Command::new("osascript");
-                detached_cmd.args(&[
-                    "-e",
-                    &format!(
-                        "tell application \"Terminal\" to do script \"{} {}; sleep {}; exit\"",
-                        program,
-                        new_args.join(" "),
-                        self.detached_hold.unwrap_or(0)
-                    ),
-                ]);
-                detached_cmd
+                let opts = crate::e_terminal::LaunchOptions {
+                    delay: self.detached_delay,
+                    hold: self.detached_hold,
+                    cwd: self.execution_dir.clone(),
+                    title: Some(self.target_name.clone()),
+                };
+                match crate::e_terminal::Launcher::detect(self.terminal.as_deref()) {
+                    Some(launcher) => launcher.command(&program, &new_args, &opts),
+                    None => {
+                        eprintln!(
+                            "cargo-e: no terminal found for --detached; set --terminal or $TERMINAL. Running in this terminal."
+                        );
+                        let mut cmd = Command::new(program);
+                        cmd.args(&new_args);
+                        cmd
+                    }
+                }
             }
         } else {
             let mut cmd = Command::new(program);
@@ -2189,6 +2444,15 @@ impl CargoCommandBuilder {
             cmd
         };
 
+        cmd.envs(self.envs.iter().map(|(k, v)| (k, v)));
+        if let Some(path) = &self.stdin_file {
+            match std::fs::File::open(path) {
+                Ok(file) => {
+                    cmd.stdin(file);
+                }
+                Err(e) => eprintln!("cargo-e: cannot open stdin {}: {}", path.display(), e),
+            }
+        }
         if let Some(dir) = &self.execution_dir {
             cmd.current_dir(dir);
         }
@@ -2263,16 +2527,17 @@ fn show_graphical_panic(
                     })
                 });
                 let stripped_file = full_path.to_string_lossy().replace("\\\\?\\", "");
-                let code_path = which("code").unwrap_or_else(|_| "code".to_string().into());
-                String::from(format!(
-                    "\nanchor: code {} {} {}|\"{}\" --goto \"{}:{}:{}\"\n",
-                    stripped_file,
+                let open = crate::e_editor::Editor::configured().anchor_command(
+                    Path::new(&stripped_file),
                     prior.line.unwrap_or(0),
                     prior.column.unwrap_or(0),
-                    code_path.display(),
+                );
+                String::from(format!(
+                    "\nanchor: open {} {} {}|{}\n",
                     stripped_file,
                     prior.line.unwrap_or(0),
-                    prior.column.unwrap_or(0)
+                    prior.column.unwrap_or(0),
+                    open
                 ))
             }
         };
@@ -2398,4 +2663,32 @@ mod tests {
         assert!(args.contains(&"--flag".to_string()));
         assert!(args.contains(&"value".to_string()));
     }
+
+    #[test]
+    fn test_json_diagnostics_only_for_cargo() {
+        let manifest_path = PathBuf::from("Cargo.toml");
+        let mut builder = CargoCommandBuilder::new(
+            "my_example",
+            &manifest_path,
+            "run",
+            true,
+            false,
+            false,
+            false,
+            false,
+            false,
+        );
+        builder.args.push("run".into());
+        assert!(builder.uses_json_diagnostics());
+        let (_, args) = builder.injected_args();
+        assert!(args.contains(&"--message-format=json-diagnostic-rendered-ansi".to_string()));
+
+        builder.alternate_cmd = Some("trunk".into());
+        assert!(!builder.uses_json_diagnostics());
+
+        builder.alternate_cmd = None;
+        builder.json_diagnostics = false;
+        let (_, args) = builder.injected_args();
+        assert!(args.contains(&"--message-format=json".to_string()));
+    }
 }
@@ -0,0 +1,668 @@
+//! Layered configuration for cargo-e.
+//!
+//! Layers, from lowest to highest precedence:
+//! 1. global `~/.cargo-e/config.toml`
+//! 2. project `.cargo-e.toml` (nearest one above the manifest or current directory)
+//! 3. `[workspace.metadata.cargo-e]`, then `[package.metadata.cargo-e]` in the manifest
+//! 4. command line flags
+//!
+//! Top-level keys are `Cli` options, named by field (`no_tts`) or long flag (`nT`, `run-at-a-time`).
+//! Per-target overrides live under `[targets.<name>]`:
+//!
+//! ```toml
+//! filter = true
+//! nT = true
+//! wait = 5
+//!
+//! [targets.wgpu_image]
+//! args = ["--width", "800"]
+//! env = { RUST_LOG = "debug" }
+//! timeout = 30
+//! skip = false
+//! runner = "valgrind"
+//! ready = 'listening on'
+//! survive = 5
+//!
+//! [[profile]]
+//! name = "hires"
+//! target = "wgpu_image"
+//! args = ["--width", "3840"]
+//! env = { ASSET_DIR = "assets/hires" }
+//! stdin = "input.txt"
+//! ```
+//!
+//! The smoke-test keys (`ready`, `survive`, `expect-exit`, `expect-stdout`, ...)
+//! are described in [`crate::e_expect`].
+//!
+//! A profile is selected with `cargo e wgpu_image@hires`; `--run-all` runs every profile.
+//!
+//! Plugins are enabled or disabled by name under `[plugins]` (`npm = false`);
+//! `cargo e plugins enable|disable <name>` edits this table.
+use crate::prelude::*;
+use crate::Cli;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, CommandFactory, FromArgMatches};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use toml::Value;
+
+/// Where a resolved configuration value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    Global(PathBuf),
+    Project(PathBuf),
+    Manifest(PathBuf),
+    Cli,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::Global(path) => write!(f, "global {}", path.display()),
+            ConfigSource::Project(path) => write!(f, "project {}", path.display()),
+            ConfigSource::Manifest(path) => write!(f, "manifest {}", path.display()),
+            ConfigSource::Cli => write!(f, "command line"),
+        }
+    }
+}
+
+/// Settings applied to a single target, merged field by field across layers.
+#[derive(Debug, Clone, Default)]
+pub struct TargetOverride {
+    /// Arguments passed to the target after `--`.
+    pub args: Vec<String>,
+    /// Environment variables set for the target.
+    pub env: BTreeMap<String, String>,
+    /// Run time in seconds for this target in `--run-all`.
+    pub timeout: Option<u64>,
+    /// Skip this target in `--run-all`.
+    pub skip: bool,
+    /// Program cargo uses to launch the built target (`target.<cfg>.runner`).
+    pub runner: Option<String>,
+    /// What a passing `--run-all` run of this target looks like.
+    pub expect: crate::e_expect::Expectations,
+    /// Layers that contributed to this override.
+    pub sources: Vec<ConfigSource>,
+}
+
+/// A named set of arguments, environment and stdin for one target (`[[profile]]`).
+#[derive(Debug, Clone)]
+pub struct RunProfile {
+    pub name: String,
+    pub target: String,
+    /// Arguments passed to the target after `--`.
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    /// File fed to the target's stdin, relative paths resolved against the defining file.
+    pub stdin: Option<PathBuf>,
+    pub source: ConfigSource,
+}
+
+impl RunProfile {
+    /// The `target@profile` form used on the command line and in reports.
+    pub fn label(&self) -> String {
+        format!("{}@{}", self.target, self.name)
+    }
+}
+
+/// Splits `target@profile` into its target name and optional profile name.
+pub fn split_profile(spec: &str) -> (&str, Option<&str>) {
+    match spec.rsplit_once('@') {
+        Some((target, profile)) if !target.is_empty() && !profile.is_empty() => {
+            (target, Some(profile))
+        }
+        _ => (spec, None),
+    }
+}
+
+/// The outcome of resolving every configuration layer against the command line.
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedConfig {
+    /// Configuration files that were found, lowest precedence first.
+    pub layers: Vec<ConfigSource>,
+    /// Every `Cli` option as (long flag or id, resolved value, source).
+    pub values: Vec<(String, String, ConfigSource)>,
+    /// Per-target overrides keyed by target name.
+    pub targets: BTreeMap<String, TargetOverride>,
+    /// Run profiles in definition order; a later layer replaces a profile with the same name.
+    pub profiles: Vec<RunProfile>,
+    /// Plugin enabled state by plugin name, from `[plugins]`.
+    pub plugins: BTreeMap<String, (bool, ConfigSource)>,
+}
+
+impl ResolvedConfig {
+    /// Returns the override for `target_name`, if any layer defines one.
+    pub fn target(&self, target_name: &str) -> Option<&TargetOverride> {
+        self.targets.get(target_name)
+    }
+
+    /// Returns every profile defined for `target_name`.
+    pub fn profiles_for(&self, target_name: &str) -> Vec<&RunProfile> {
+        self.profiles
+            .iter()
+            .filter(|p| p.target == target_name)
+            .collect()
+    }
+
+    /// Returns the profile `name` of `target_name`.
+    pub fn profile(&self, target_name: &str, name: &str) -> Option<&RunProfile> {
+        self.profiles
+            .iter()
+            .find(|p| p.target == target_name && p.name == name)
+    }
+
+    /// Whether the plugin `name` may be loaded; plugins are enabled unless a layer disables them.
+    pub fn plugin_enabled(&self, name: &str) -> bool {
+        self.plugins.get(name).is_none_or(|(enabled, _)| *enabled)
+    }
+
+    /// Where the `Cli` option `key` (long flag or id) came from, if it was resolved.
+    pub fn source_of(&self, key: &str) -> Option<&ConfigSource> {
+        self.values
+            .iter()
+            .find(|(k, _, _)| k == key)
+            .map(|(_, _, source)| source)
+    }
+
+    /// How long `--run-all` lets `target_name` run: `--run-all [secs]` given on the
+    /// command line wins, then the target's `timeout`, then `run-all` from a
+    /// configuration file, then `wait`.
+    pub fn run_all_timeout(
+        &self,
+        target_name: &str,
+        run_all: &crate::e_cli::RunAll,
+        wait: u64,
+    ) -> std::time::Duration {
+        use crate::e_cli::RunAll;
+        use std::time::Duration;
+        let configured = self.target(target_name).and_then(|ov| ov.timeout);
+        let from_cli = self.source_of("run-all") == Some(&ConfigSource::Cli);
+        match (run_all, configured) {
+            (RunAll::Forever, _) if from_cli => Duration::from_secs(u64::MAX),
+            (RunAll::Timeout(secs), _) if from_cli => Duration::from_secs(*secs),
+            (_, Some(secs)) => Duration::from_secs(secs),
+            (RunAll::Forever, None) => Duration::from_secs(u64::MAX),
+            (RunAll::Timeout(secs), None) => Duration::from_secs(*secs),
+            (RunAll::NotSpecified, None) => Duration::from_secs(wait),
+        }
+    }
+
+    /// Prints the resolved values and where each one came from (`--print-config`).
+    pub fn print(&self) {
+        println!("config layers:");
+        if self.layers.is_empty() {
+            println!("  (none found)");
+        }
+        for layer in &self.layers {
+            println!("  {}", layer);
+        }
+        println!("options:");
+        let width = self
+            .values
+            .iter()
+            .map(|(k, _, _)| k.len())
+            .max()
+            .unwrap_or(0);
+        for (key, value, source) in &self.values {
+            println!(
+                "  {:<width$} = {:<12} [{}]",
+                key,
+                value,
+                source,
+                width = width
+            );
+        }
+        if !self.targets.is_empty() {
+            println!("targets:");
+        }
+        for (name, ov) in &self.targets {
+            let sources: Vec<String> = ov.sources.iter().map(|s| s.to_string()).collect();
+            println!("  {} [{}]", name, sources.join(", "));
+            if !ov.args.is_empty() {
+                println!("    args    = {:?}", ov.args);
+            }
+            for (k, v) in &ov.env {
+                println!("    env     {}={}", k, v);
+            }
+            if let Some(timeout) = ov.timeout {
+                println!("    timeout = {}", timeout);
+            }
+            if ov.skip {
+                println!("    skip    = true");
+            }
+            if let Some(runner) = &ov.runner {
+                println!("    runner  = {}", runner);
+            }
+            if !ov.expect.is_empty() {
+                println!("    expect  = {:?}", ov.expect);
+            }
+        }
+        if !self.profiles.is_empty() {
+            println!("profiles:");
+        }
+        for profile in &self.profiles {
+            println!("  {} [{}]", profile.label(), profile.source);
+            if !profile.args.is_empty() {
+                println!("    args    = {:?}", profile.args);
+            }
+            for (k, v) in &profile.env {
+                println!("    env     {}={}", k, v);
+            }
+            if let Some(stdin) = &profile.stdin {
+                println!("    stdin   = {}", stdin.display());
+            }
+        }
+        if !self.plugins.is_empty() {
+            println!("plugins:");
+        }
+        for (name, (enabled, source)) in &self.plugins {
+            println!(
+                "  {} = {} [{}]",
+                name,
+                if *enabled { "enabled" } else { "disabled" },
+                source
+            );
+        }
+    }
+}
+
+/// Returns the override for `target_name` from the globally resolved configuration.
+pub fn target_override(target_name: &str) -> Option<TargetOverride> {
+    crate::GLOBAL_CONFIG
+        .get()
+        .and_then(|config| config.target(target_name).cloned())
+}
+
+/// Returns how long `--run-all` runs `target_name`, see [`ResolvedConfig::run_all_timeout`].
+pub fn run_all_timeout(target_name: &str, cli: &Cli) -> std::time::Duration {
+    match crate::GLOBAL_CONFIG.get() {
+        Some(config) => config.run_all_timeout(target_name, &cli.run_all, cli.wait),
+        None => ResolvedConfig::default().run_all_timeout(target_name, &cli.run_all, cli.wait),
+    }
+}
+
+/// Returns the profiles defined for `target_name` in the global configuration.
+pub fn run_profiles(target_name: &str) -> Vec<RunProfile> {
+    crate::GLOBAL_CONFIG
+        .get()
+        .map(|config| {
+            config
+                .profiles_for(target_name)
+                .into_iter()
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the profile `name` of `target_name` from the global configuration.
+pub fn run_profile(target_name: &str, name: &str) -> Option<RunProfile> {
+    crate::GLOBAL_CONFIG
+        .get()
+        .and_then(|config| config.profile(target_name, name).cloned())
+}
+
+/// Whether the plugin `name` is enabled in the global configuration.
+pub fn plugin_enabled(name: &str) -> bool {
+    crate::GLOBAL_CONFIG
+        .get()
+        .is_none_or(|config| config.plugin_enabled(name))
+}
+
+/// Records `[plugins] <name> = <enabled>` in the global config file or the project `.cargo-e.toml`
+/// (the nearest one above the current directory, else a new one in it). Returns the file written.
+pub fn set_plugin_enabled(name: &str, enabled: bool, global: bool) -> anyhow::Result<PathBuf> {
+    let path = if global {
+        global_config_path().ok_or_else(|| anyhow::anyhow!("no home directory found"))?
+    } else {
+        let cwd = env::current_dir()?;
+        cwd.ancestors()
+            .map(|dir| dir.join(".cargo-e.toml"))
+            .find(|p| p.is_file())
+            .unwrap_or_else(|| cwd.join(".cargo-e.toml"))
+    };
+    let mut table = if path.is_file() {
+        match fs::read_to_string(&path)?.parse::<Value>()? {
+            Value::Table(table) => table,
+            _ => anyhow::bail!("{} is not a TOML table", path.display()),
+        }
+    } else {
+        toml::value::Table::new()
+    };
+    let plugins = table
+        .entry("plugins")
+        .or_insert_with(|| Value::Table(toml::value::Table::new()));
+    let Value::Table(plugins) = plugins else {
+        anyhow::bail!("`plugins` in {} must be a table", path.display());
+    };
+    plugins.insert(name.to_string(), Value::Boolean(enabled));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, toml::to_string_pretty(&table)?)?;
+    Ok(path)
+}
+
+fn global_config_path() -> Option<PathBuf> {
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(".cargo-e").join("config.toml"))
+}
+
+fn read_table(path: &Path) -> Option<toml::value::Table> {
+    let content = fs::read_to_string(path).ok()?;
+    match content.parse::<Value>() {
+        Ok(Value::Table(table)) => Some(table),
+        Ok(_) => None,
+        Err(e) => {
+            eprintln!("cargo-e: ignoring {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn metadata_table(manifest: &Path, section: &str) -> Option<toml::value::Table> {
+    read_table(manifest)?
+        .get(section)?
+        .get("metadata")?
+        .get("cargo-e")?
+        .as_table()
+        .cloned()
+}
+
+/// Loads every configuration layer that exists, lowest precedence first.
+pub fn load_layers(manifest_path: Option<&Path>) -> Vec<(ConfigSource, toml::value::Table)> {
+    let mut layers = Vec::new();
+    if let Some(path) = global_config_path() {
+        if let Some(table) = read_table(&path) {
+            layers.push((ConfigSource::Global(path), table));
+        }
+    }
+
+    let start = manifest_path
+        .and_then(|m| m.parent().map(Path::to_path_buf))
+        .filter(|p| !p.as_os_str().is_empty())
+        .or_else(|| env::current_dir().ok());
+    if let Some(start) = start {
+        let start = fs::canonicalize(&start).unwrap_or(start);
+        if let Some(path) = start
+            .ancestors()
+            .map(|dir| dir.join(".cargo-e.toml"))
+            .find(|p| p.is_file())
+        {
+            if let Some(table) = read_table(&path) {
+                layers.push((ConfigSource::Project(path), table));
+            }
+        }
+    }
+
+    if let Some(manifest) = manifest_path {
+        let mut manifests = Vec::new();
+        if let Ok(ws) = crate::e_manifest::locate_manifest(true) {
+            manifests.push(PathBuf::from(ws));
+        }
+        let manifest = fs::canonicalize(manifest).unwrap_or_else(|_| manifest.to_path_buf());
+        if !manifests
+            .iter()
+            .any(|m| fs::canonicalize(m).ok().as_ref() == Some(&manifest))
+        {
+            manifests.push(manifest);
+        }
+        for section in ["workspace", "package"] {
+            for m in &manifests {
+                if let Some(table) = metadata_table(m, section) {
+                    layers.push((ConfigSource::Manifest(m.clone()), table));
+                }
+            }
+        }
+    }
+    layers
+}
+
+fn toml_scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Integer(i) => Some(i.to_string()),
+        Value::Float(f) => Some(f.to_string()),
+        Value::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Converts a config value into the command line tokens clap would accept for `arg`.
+fn option_tokens(arg: &Arg, value: &Value) -> Option<Vec<String>> {
+    if arg.is_last_set() {
+        let values: Option<Vec<String>> = match value {
+            Value::Array(items) => items.iter().map(toml_scalar).collect(),
+            other => toml_scalar(other).map(|s| vec![s]),
+        };
+        return values.map(|v| std::iter::once("--".to_string()).chain(v).collect());
+    }
+    let long = arg.get_long()?;
+    match arg.get_action() {
+        ArgAction::SetTrue => match value {
+            Value::Boolean(true) => Some(vec![format!("--{}", long)]),
+            Value::Boolean(false) => Some(Vec::new()),
+            _ => None,
+        },
+        ArgAction::SetFalse => match value {
+            Value::Boolean(false) => Some(vec![format!("--{}", long)]),
+            Value::Boolean(true) => Some(Vec::new()),
+            _ => None,
+        },
+        _ => match value {
+            Value::Array(items) => items
+                .iter()
+                .map(|v| toml_scalar(v).map(|s| format!("--{}={}", long, s)))
+                .collect(),
+            other => toml_scalar(other).map(|s| vec![format!("--{}={}", long, s)]),
+        },
+    }
+}
+
+fn find_arg<'a>(cmd: &'a clap::Command, key: &str) -> Option<&'a Arg> {
+    let id = key.replace('-', "_");
+    cmd.get_arguments()
+        .find(|a| a.get_id().as_str() == id || a.get_long() == Some(key))
+}
+
+fn parse_profile(table: &toml::value::Table, source: &ConfigSource) -> Option<RunProfile> {
+    let name = table.get("name")?.as_str()?.to_string();
+    let target = table.get("target")?.as_str()?.to_string();
+    let args = table
+        .get("args")
+        .and_then(Value::as_array)
+        .map(|a| a.iter().filter_map(toml_scalar).collect())
+        .unwrap_or_default();
+    let env = table
+        .get("env")
+        .and_then(Value::as_table)
+        .map(|t| {
+            t.iter()
+                .filter_map(|(k, v)| toml_scalar(v).map(|v| (k.clone(), v)))
+                .collect()
+        })
+        .unwrap_or_default();
+    let base_dir = match source {
+        ConfigSource::Global(p) | ConfigSource::Project(p) | ConfigSource::Manifest(p) => {
+            p.parent().map(Path::to_path_buf)
+        }
+        _ => None,
+    };
+    let stdin = table.get("stdin").and_then(Value::as_str).map(|s| {
+        let path = PathBuf::from(s);
+        match &base_dir {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path,
+        }
+    });
+    Some(RunProfile {
+        name,
+        target,
+        args,
+        env,
+        stdin,
+        source: source.clone(),
+    })
+}
+
+fn merge_target(table: &toml::value::Table, source: &ConfigSource, into: &mut TargetOverride) {
+    if let Some(args) = table.get("args").and_then(Value::as_array) {
+        into.args = args.iter().filter_map(toml_scalar).collect();
+    }
+    if let Some(env) = table.get("env").and_then(Value::as_table) {
+        for (k, v) in env {
+            if let Some(v) = toml_scalar(v) {
+                into.env.insert(k.clone(), v);
+            }
+        }
+    }
+    if let Some(timeout) = table.get("timeout").and_then(Value::as_integer) {
+        into.timeout = u64::try_from(timeout).ok();
+    }
+    if let Some(skip) = table.get("skip").and_then(Value::as_bool) {
+        into.skip = skip;
+    }
+    if let Some(runner) = table.get("runner").and_then(Value::as_str) {
+        into.runner = Some(runner.to_string());
+    }
+    into.expect.merge_toml(table);
+    into.sources.push(source.clone());
+}
+
+/// Resolves `layers` against the command line `args` (program name first).
+/// Values given on the command line always win over configuration files.
+pub fn resolve(
+    args: Vec<String>,
+    layers: Vec<(ConfigSource, toml::value::Table)>,
+) -> (Cli, ResolvedConfig) {
+    let cmd = Cli::command();
+    let cli_matches = cmd
+        .clone()
+        .try_get_matches_from(&args)
+        .unwrap_or_else(|e| e.exit());
+
+    let mut resolved = ResolvedConfig::default();
+    let mut entries: HashMap<String, (Vec<String>, ConfigSource)> = HashMap::new();
+    for (source, table) in layers {
+        resolved.layers.push(source.clone());
+        for (key, value) in &table {
+            if key == "profile" {
+                for entry in value.as_array().into_iter().flatten() {
+                    match entry.as_table().and_then(|t| parse_profile(t, &source)) {
+                        Some(profile) => {
+                            resolved.profiles.retain(|p| {
+                                !(p.target == profile.target && p.name == profile.name)
+                            });
+                            resolved.profiles.push(profile);
+                        }
+                        None => eprintln!(
+                            "cargo-e: [[profile]] in {} needs `name` and `target`",
+                            source
+                        ),
+                    }
+                }
+                continue;
+            }
+            if key == "plugins" {
+                for (name, enabled) in value.as_table().into_iter().flatten() {
+                    match enabled.as_bool() {
+                        Some(enabled) => {
+                            resolved
+                                .plugins
+                                .insert(name.clone(), (enabled, source.clone()));
+                        }
+                        None => eprintln!(
+                            "cargo-e: plugins.{} in {} must be true or false",
+                            name, source
+                        ),
+                    }
+                }
+                continue;
+            }
+            if key == "targets" {
+                for (name, target) in value.as_table().into_iter().flatten() {
+                    if let Some(target) = target.as_table() {
+                        let ov = resolved.targets.entry(name.clone()).or_default();
+                        merge_target(target, &source, ov);
+                    }
+                }
+                continue;
+            }
+            let Some(arg) = find_arg(&cmd, key) else {
+                eprintln!("cargo-e: unknown config key `{}` in {}", key, source);
+                continue;
+            };
+            match option_tokens(arg, value) {
+                Some(tokens) => {
+                    entries.insert(arg.get_id().to_string(), (tokens, source.clone()));
+                }
+                None => eprintln!("cargo-e: invalid value for `{}` in {}", key, source),
+            }
+        }
+    }
+    // The command line overrides every configuration layer.
+    entries.retain(|id, _| cli_matches.value_source(id) != Some(ValueSource::CommandLine));
+
+    let mut merged = vec![args.first().cloned().unwrap_or_else(|| "cargo-e".into())];
+    let mut trailing = Vec::new();
+    for arg in cmd.get_arguments() {
+        if let Some((tokens, _)) = entries.get(arg.get_id().as_str()) {
+            if arg.is_last_set() {
+                trailing.extend(tokens.iter().cloned());
+            } else {
+                merged.extend(tokens.iter().cloned());
+            }
+        }
+    }
+    merged.extend(args.iter().skip(1).cloned());
+    merged.extend(trailing);
+
+    let matches = cmd
+        .clone()
+        .try_get_matches_from(&merged)
+        .unwrap_or_else(|e| e.exit());
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    for arg in cmd.get_arguments() {
+        if matches!(arg.get_action(), ArgAction::Help | ArgAction::Version) {
+            continue;
+        }
+        let id = arg.get_id().as_str();
+        let key = arg.get_long().unwrap_or(id).to_string();
+        let value = matches
+            .get_raw(id)
+            .map(|vals| {
+                vals.map(|v| v.to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .unwrap_or_default();
+        let source = match matches.value_source(id) {
+            Some(ValueSource::CommandLine) => entries
+                .get(id)
+                .map(|(_, s)| s.clone())
+                .unwrap_or(ConfigSource::Cli),
+            _ => ConfigSource::Default,
+        };
+        resolved.values.push((key, value, source));
+    }
+    (cli, resolved)
+}
+
+/// Parses the command line, layering configuration files underneath it.
+pub fn parse_cli_with_config(args: Vec<String>) -> (Cli, ResolvedConfig) {
+    let manifest_path = Cli::command()
+        .try_get_matches_from(&args)
+        .unwrap_or_else(|e| e.exit())
+        .get_one::<PathBuf>("manifest_path")
+        .cloned()
+        .or_else(|| {
+            crate::e_manifest::locate_manifest(false)
+                .ok()
+                .map(PathBuf::from)
+        });
+    let layers = load_layers(manifest_path.as_deref());
+    resolve(args, layers)
+}
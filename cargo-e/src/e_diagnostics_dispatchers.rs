@@ -17,6 +17,28 @@ use open;
 use regex::Regex;
 // --- Dispatcher creation helpers for diagnostics (no struct, just functions) ---
 
+/// Open the `http://` URL in a "listening on" line, once per URL per session,
+/// so restarting a server (e.g. under `--watch`) does not open a new browser tab.
+pub fn open_server_url(line: &str) {
+    static URL_REGEX: once_cell::sync::Lazy<Regex> =
+        once_cell::sync::Lazy::new(|| Regex::new(r"(http://[^\s]+)").unwrap());
+    static OPENED: once_cell::sync::Lazy<Mutex<std::collections::HashSet<String>>> =
+        once_cell::sync::Lazy::new(Default::default);
+    let Some(url) = URL_REGEX.captures(line).and_then(|c| c.get(1)) else {
+        return;
+    };
+    let url = url.as_str();
+    if !OPENED.lock().unwrap().insert(url.to_string()) {
+        crate::out_println!("Server is back at {}", url);
+        return;
+    }
+    if let Err(e) = open::that_detached(url) {
+        crate::out_eprintln!("Failed to open URL: {}. Error: {}", url, e);
+    } else {
+        crate::out_println!("Opened URL: {}", url);
+    }
+}
+
 /// Create a configured EventDispatcher for stdout diagnostics.
 pub fn create_stdout_dispatcher() -> EventDispatcher {
     let mut dispatcher = EventDispatcher::new();
@@ -24,33 +46,21 @@ pub fn create_stdout_dispatcher() -> EventDispatcher {
     dispatcher.add_callback(
         r"listening on",
         Box::new(|line, _captures, _state, stats, _prior_response| {
-            println!("(STDOUT) Dispatcher caught: {}", line);
-            if let Ok(url_regex) = Regex::new(r"(http://[^\s]+)") {
-                if let Some(url_caps) = url_regex.captures(line) {
-                    if let Some(url_match) = url_caps.get(1) {
-                        let url = url_match.as_str();
-                        if let Err(e) = open::that_detached(url) {
-                            eprintln!("Failed to open URL: {}. Error: {}", url, e);
-                        } else {
-                            println!("Opened URL: {}", url);
-                        }
-                    }
-                }
-            } else {
-                eprintln!("Failed to create URL regex");
-            }
+            crate::out_println!("(STDOUT) Dispatcher caught: {}", line);
+            open_server_url(line);
             let mut stats = stats.lock().unwrap();
+            let now = SystemTime::now();
             if stats.build_finished_time.is_none() {
-                let now = SystemTime::now();
                 stats.build_finished_time = Some(now);
             }
+            stats.listening_time.get_or_insert(now);
             None
         }),
     );
     dispatcher.add_callback(
         r"BuildFinished",
         Box::new(|line, _captures, _state, stats, _prior_response| {
-            println!("******* {}", line);
+            crate::out_println!("******* {}", line);
             let mut stats = stats.lock().unwrap();
             if stats.build_finished_time.is_none() {
                 let now = SystemTime::now();
@@ -63,7 +73,7 @@ pub fn create_stdout_dispatcher() -> EventDispatcher {
         r"server listening at:",
         Box::new(|line, _captures, state, stats, _prior_response| {
             if !state.load(Ordering::Relaxed) {
-                println!("Matched 'server listening at:' in: {}", line);
+                crate::out_println!("Matched 'server listening at:' in: {}", line);
                 state.store(true, Ordering::Relaxed);
                 Some(CallbackResponse {
                     callback_type: CallbackType::Note,
@@ -75,19 +85,19 @@ pub fn create_stdout_dispatcher() -> EventDispatcher {
                     terminal_status: None,
                 })
             } else {
-                println!("Multiline callback received: {}", line);
+                crate::out_println!("Multiline callback received: {}", line);
                 let url_regex = match Regex::new(r"(http://[^\s]+)") {
                     Ok(regex) => regex,
                     Err(e) => {
-                        eprintln!("Failed to create URL regex: {}", e);
+                        crate::out_eprintln!("Failed to create URL regex: {}", e);
                         return None;
                     }
                 };
                 if let Some(url_caps) = url_regex.captures(line) {
                     let url = url_caps.get(1).unwrap().as_str();
                     match open::that_detached(url) {
-                        Ok(_) => println!("Opened URL: {}", url),
-                        Err(e) => eprintln!("Failed to open URL: {}. Error: {}", url, e),
+                        Ok(_) => crate::out_println!("Opened URL: {}", url),
+                        Err(e) => crate::out_eprintln!("Failed to open URL: {}. Error: {}", url, e),
                     }
                     let mut stats = stats.lock().unwrap();
                     if stats.build_finished_time.is_none() {
@@ -160,8 +170,8 @@ pub fn create_stderr_dispatcher(
                     .unwrap_or("0")
                     .parse()
                     .unwrap_or(0);
-                println!("\n\n\n");
-                println!("{}", line);
+                crate::out_println!("\n\n\n");
+                crate::out_println!("{}", line);
                 // Use a global TTS instance via OnceCell for program lifetime
 
                 #[cfg(feature = "uses_tts")]
@@ -176,15 +186,19 @@ pub fn create_stderr_dispatcher(
                         .and_then(|s| s.to_str())
                         .unwrap_or("unknown file");
                     let speech = format!("thread {} panic, {} line {}", thread, filename, line_num);
-                    println!("TTS: {}", speech);
+                    crate::out_println!("TTS: {}", speech);
                     let _ = tts.speak(&speech, false);
                 }
 
-                println!(
+                crate::out_println!(
                     "Panic detected: thread='{}', message='{}', file='{}:{}:{}'",
-                    thread, message, file, line_num, col_num
+                    thread,
+                    message,
+                    file,
+                    line_num,
+                    col_num
                 );
-                println!("\n\n\n");
+                crate::out_println!("\n\n\n");
                 Some(CallbackResponse {
                     callback_type: CallbackType::Error,
                     message: Some(format!(
@@ -206,7 +220,7 @@ pub fn create_stderr_dispatcher(
                     let mut tts = tts_mutex.lock().expect("Failed to lock TTS mutex");
 
                     let speech = format!("panic says {}", line);
-                    println!("TTS: {}", speech);
+                    crate::out_println!("TTS: {}", speech);
                     let _ = tts.speak(&speech, true);
 
                     if let Ok(e_window_path) = which("e_window") {
@@ -248,7 +262,7 @@ pub fn create_stderr_dispatcher(
     dispatcher.add_callback(
         r"error: could not compile `(?P<crate_name>.+)` \((?P<due_to>.+)\) due to (?P<error_count>\d+) previous errors; (?P<warning_count>\d+) warnings emitted",
         Box::new(|line, captures, _state, stats, _prior_response| {
-            println!("{}", line);
+            crate::out_println!("{}", line);
             if let Some(caps) = captures {
                 let crate_name = caps.name("crate_name").map(|m| m.as_str()).unwrap_or("unknown");
                 let due_to = caps.name("due_to").map(|m| m.as_str()).unwrap_or("unknown");
@@ -261,7 +275,7 @@ pub fn create_stderr_dispatcher(
                     .map(|m| m.as_str().parse().unwrap_or(0))
                     .unwrap_or(0);
 
-                println!(
+                crate::out_println!(
                     "Detected compilation failure: crate=`{}`, due_to=`{}`, errors={}, warnings={}",
                     crate_name, due_to, error_count, warning_count
                 );
@@ -303,7 +317,7 @@ pub fn create_stderr_dispatcher(
                         "help" => CargoDiagnosticLevel::Help,
                         "note" => CargoDiagnosticLevel::Note,
                         _ => {
-                            println!("Unknown diagnostic level: {}", level);
+                            crate::out_println!("Unknown diagnostic level: {}", level);
                             return None;
                         }
                     };
@@ -321,6 +335,8 @@ pub fn create_stderr_dispatcher(
                         uses_color: true,
                         diag_num_padding: Some(2),
                         diag_number: Some(*current_count),
+                        spans: Vec::new(),
+                        children: Vec::new(),
                     };
 
                     *pending_diag = Some(diag);
@@ -335,7 +351,7 @@ pub fn create_stderr_dispatcher(
                         terminal_status: None,
                     });
                 } else {
-                    println!("No captures found in line: {}", _line);
+                    crate::out_println!("No captures found in line: {}", _line);
                     None
                 }
             },
@@ -552,7 +568,7 @@ pub fn create_stderr_dispatcher(
                                 let mut pending_diag = match pending_diag.lock() {
                                     Ok(lock) => lock,
                                     Err(e) => {
-                                        eprintln!("Failed to acquire lock: {}", e);
+                                        crate::out_eprintln!("Failed to acquire lock: {}", e);
                                         return None;
                                     }
                                 };
@@ -629,7 +645,7 @@ pub fn create_stderr_dispatcher(
                         suggestion_mode.store(true, Ordering::Relaxed);
                         return Some(resp.clone());
                     } else {
-                        println!("No captures found in line: {}", _line);
+                        crate::out_println!("No captures found in line: {}", _line);
                     }
                     None
                 },
@@ -686,10 +702,12 @@ pub fn create_stderr_dispatcher(
             if let Some(caps) = captures {
                 let url = caps.get(1).unwrap().as_str();
                 let url = url.replace("0.0.0.0", "127.0.0.1");
-                println!("(STDERR) Captured URL: {}", url);
+                crate::out_println!("(STDERR) Captured URL: {}", url);
                 match open::that_detached(&url) {
-                    Ok(_) => println!("(STDERR) Opened URL: {}", &url),
-                    Err(e) => eprintln!("(STDERR) Failed to open URL: {}. Error: {:?}", url, e),
+                    Ok(_) => crate::out_println!("(STDERR) Opened URL: {}", &url),
+                    Err(e) => {
+                        crate::out_eprintln!("(STDERR) Failed to open URL: {}. Error: {:?}", url, e)
+                    }
                 }
                 let mut stats = stats.lock().unwrap();
                 if stats.build_finished_time.is_none() {
@@ -706,7 +724,7 @@ pub fn create_stderr_dispatcher(
                     terminal_status: None,
                 })
             } else {
-                println!("(STDERR) No URL captured in line: {}", line);
+                crate::out_println!("(STDERR) No URL captured in line: {}", line);
                 None
             }
         }),
@@ -759,7 +777,7 @@ pub fn create_stderr_dispatcher(
                     let cmd      = caps["cmd"].to_string();
                     let fixes: usize = caps["fixes"].parse().unwrap_or(0);
 
-                    println!("SUMMARIZATION CALLBACK {}",
+                    crate::out_println!("SUMMARIZATION CALLBACK {}",
                         &format!("{}: `{}` ({}) generated {} {}; run `{}` to apply {} fixes",
                         level, name, otype, count, kind, cmd, fixes));
                     Some(CallbackResponse {
@@ -784,7 +802,7 @@ pub fn create_stderr_dispatcher(
     dispatcher.add_callback(
         r"IO\(Custom \{ kind: NotConnected",
         Box::new(move |line, _captures, _state, _stats, _prior_response| {
-            println!("(STDERR) Terminal error detected: {:?}", &line);
+            crate::out_println!("(STDERR) Terminal error detected: {:?}", &line);
             // let result = if line.contains("NotConnected") {
             //     TerminalError::NoTerminal
             // } else {
@@ -807,7 +825,7 @@ pub fn create_stderr_dispatcher(
         r".*",
         Box::new(|line, _captures, _state, _stats, _prior_response| {
             log::trace!("stdraw[{:?}]", line);
-            println!("{}", line);
+            crate::out_println!("{}", line);
             None
         }),
     );
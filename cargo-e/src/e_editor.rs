@@ -0,0 +1,467 @@
+//! Opening a file at a line and column in the user's editor.
+//!
+//! The editor is `--editor` (or `editor` in the config), then `$VISUAL`, then
+//! `$EDITOR`, then VS Code. `--editor` takes a program, possibly with
+//! arguments, or a template such as `"{editor} {file}:{line}:{col}"`, where
+//! `{editor}` stands for `$VISUAL`/`$EDITOR` (or `code`).
+//!
+//! Known editors get their own way of jumping to a position: VS Code
+//! `--goto file:line:col`, vim/neovim `+line`, emacs `+line:col`, helix, zed
+//! and sublime `file:line:col`, JetBrains IDEs `--line --column`. Used by the
+//! TUI's `e` key, e_window anchors and `cargo e --open <diag#>`.
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// How an editor is told where to put the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GotoStyle {
+    /// `--goto file:line:col` (VS Code and forks).
+    VsCode,
+    /// `+line file` (vi, vim, neovim, nano).
+    PlusLine,
+    /// `+line:col file` (emacs, emacsclient).
+    PlusLineCol,
+    /// `file:line:col` (helix, zed, sublime, micro).
+    Colon,
+    /// `--line line --column col file` (JetBrains IDEs).
+    JetBrains,
+    /// Just the file, for editors we do not know.
+    FileOnly,
+}
+
+/// Program names, without extension, and how each jumps to a position.
+pub const KNOWN_EDITORS: [(&str, GotoStyle); 24] = [
+    ("code", GotoStyle::VsCode),
+    ("code-insiders", GotoStyle::VsCode),
+    ("codium", GotoStyle::VsCode),
+    ("cursor", GotoStyle::VsCode),
+    ("vi", GotoStyle::PlusLine),
+    ("vim", GotoStyle::PlusLine),
+    ("gvim", GotoStyle::PlusLine),
+    ("nvim", GotoStyle::PlusLine),
+    ("nano", GotoStyle::PlusLine),
+    ("emacs", GotoStyle::PlusLineCol),
+    ("emacsclient", GotoStyle::PlusLineCol),
+    ("hx", GotoStyle::Colon),
+    ("helix", GotoStyle::Colon),
+    ("zed", GotoStyle::Colon),
+    ("zeditor", GotoStyle::Colon),
+    ("subl", GotoStyle::Colon),
+    ("sublime_text", GotoStyle::Colon),
+    ("micro", GotoStyle::Colon),
+    ("idea", GotoStyle::JetBrains),
+    ("clion", GotoStyle::JetBrains),
+    ("rustrover", GotoStyle::JetBrains),
+    ("pycharm", GotoStyle::JetBrains),
+    ("goland", GotoStyle::JetBrains),
+    ("webstorm", GotoStyle::JetBrains),
+];
+
+/// Editors that run inside the terminal rather than opening a window.
+const TERMINAL_EDITORS: [&str; 7] = ["vi", "vim", "nvim", "nano", "hx", "helix", "micro"];
+
+/// An editor command line, ready to open files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Editor {
+    /// A program, the arguments it always gets, and how it jumps to a position.
+    Program {
+        program: String,
+        args: Vec<String>,
+        style: GotoStyle,
+    },
+    /// Words with `{file}`, `{line}` and `{col}` placeholders. A `{editor}`
+    /// word has already been replaced by the editor's own words.
+    Template(Vec<String>),
+}
+
+impl Editor {
+    /// An editor from a command line such as `nvim` or `emacsclient -nw`.
+    pub fn from_command(command: &str) -> Editor {
+        let mut words = command.split_whitespace().map(str::to_string);
+        let program = words.next().unwrap_or_else(|| "code".to_string());
+        let name = program_name(&program);
+        let style = KNOWN_EDITORS
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, style)| *style)
+            .unwrap_or(GotoStyle::FileOnly);
+        Editor::Program {
+            program,
+            args: words.collect(),
+            style,
+        }
+    }
+
+    /// An editor from a template; `editor` is what `{editor}` expands to.
+    pub fn from_template(template: &str, editor: &str) -> Editor {
+        let words = template
+            .split_whitespace()
+            .flat_map(|word| {
+                if word == "{editor}" {
+                    editor.split_whitespace().map(str::to_string).collect()
+                } else {
+                    vec![word.replace("{editor}", editor)]
+                }
+            })
+            .collect();
+        Editor::Template(words)
+    }
+
+    /// Pick the editor: `preference`, then `$VISUAL`, then `$EDITOR`, then VS Code.
+    pub fn resolve(preference: Option<&str>) -> Editor {
+        Self::resolve_with(preference, |key| std::env::var(key).ok())
+    }
+
+    /// [`Editor::resolve`] with the environment supplied by the caller.
+    pub fn resolve_with(preference: Option<&str>, env: impl Fn(&str) -> Option<String>) -> Editor {
+        let from_env = env("VISUAL")
+            .filter(|v| !v.trim().is_empty())
+            .or_else(|| env("EDITOR").filter(|v| !v.trim().is_empty()))
+            .unwrap_or_else(|| "code".to_string());
+        match preference.filter(|p| !p.trim().is_empty()) {
+            Some(p) if p.contains('{') => Editor::from_template(p, &from_env),
+            Some(p) => Editor::from_command(p),
+            None => Editor::from_command(&from_env),
+        }
+    }
+
+    /// The editor chosen by the running cargo-e's `--editor` and environment.
+    pub fn configured() -> Editor {
+        Editor::resolve(
+            crate::GLOBAL_CLI
+                .get()
+                .and_then(|cli| cli.editor.as_deref()),
+        )
+    }
+
+    /// The full command line, program first, that opens `file` at `line`:`col`.
+    pub fn argv(&self, file: &Path, line: usize, col: usize) -> Vec<String> {
+        let file = file.to_string_lossy().into_owned();
+        let (line, col) = (line.max(1), col.max(1));
+        match self {
+            Editor::Template(words) => words
+                .iter()
+                .map(|w| {
+                    w.replace("{file}", &file)
+                        .replace("{line}", &line.to_string())
+                        .replace("{col}", &col.to_string())
+                })
+                .collect(),
+            Editor::Program {
+                program,
+                args,
+                style,
+            } => {
+                let mut argv = vec![program.clone()];
+                argv.extend(args.iter().cloned());
+                match style {
+                    GotoStyle::VsCode => {
+                        argv.push("--goto".into());
+                        argv.push(format!("{}:{}:{}", file, line, col));
+                    }
+                    GotoStyle::PlusLine => {
+                        argv.push(format!("+{}", line));
+                        argv.push(file);
+                    }
+                    GotoStyle::PlusLineCol => {
+                        argv.push(format!("+{}:{}", line, col));
+                        argv.push(file);
+                    }
+                    GotoStyle::Colon => argv.push(format!("{}:{}:{}", file, line, col)),
+                    GotoStyle::JetBrains => {
+                        argv.extend([
+                            "--line".into(),
+                            line.to_string(),
+                            "--column".into(),
+                            col.to_string(),
+                            file,
+                        ]);
+                    }
+                    GotoStyle::FileOnly => argv.push(file),
+                }
+                argv
+            }
+        }
+    }
+
+    /// Whether the editor takes over the terminal instead of opening a window.
+    pub fn in_terminal(&self) -> bool {
+        let (program, args) = match self {
+            Editor::Program { program, args, .. } => (program, args.as_slice()),
+            Editor::Template(words) => match words.split_first() {
+                Some((program, args)) => (program, args),
+                None => return false,
+            },
+        };
+        let name = program_name(program);
+        TERMINAL_EDITORS.contains(&name.as_str())
+            || (name.starts_with("emacs") && args.iter().any(|a| a == "-nw" || a == "-t"))
+    }
+
+    /// A command that opens `file` at `line`:`col`.
+    pub fn command(&self, file: &Path, line: usize, col: usize) -> Command {
+        let argv = self.argv(file, line, col);
+        // Editors installed as `.cmd` shims (VS Code) only start through cmd.
+        #[cfg(target_os = "windows")]
+        let cmd = {
+            let mut cmd = Command::new("cmd");
+            cmd.arg("/C").args(&argv);
+            cmd
+        };
+        #[cfg(not(target_os = "windows"))]
+        let cmd = {
+            let mut cmd = Command::new(&argv[0]);
+            cmd.args(&argv[1..]);
+            cmd
+        };
+        cmd
+    }
+
+    /// Open `file` at `line`:`col`. A terminal editor runs in our terminal
+    /// until it exits; any other editor is started and left running.
+    pub fn open(&self, file: &Path, line: usize, col: usize) -> io::Result<()> {
+        let mut cmd = self.command(file, line, col);
+        if self.in_terminal() {
+            let status = cmd.status()?;
+            if !status.success() {
+                return Err(io::Error::other(format!("editor exited with {}", status)));
+            }
+        } else {
+            cmd.stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?;
+        }
+        Ok(())
+    }
+
+    /// The command line to open `file` from outside a terminal (an e_window
+    /// anchor, the `--serve` daemon): the program is resolved to a full path,
+    /// and terminal editors are opened through the `--terminal` launcher.
+    pub fn windowed_argv(&self, file: &Path, line: usize, col: usize) -> Vec<String> {
+        let mut argv = self.argv(file, line, col);
+        if let Ok(path) = which::which(&argv[0]) {
+            argv[0] = path.to_string_lossy().into_owned();
+        }
+        if self.in_terminal() && !cfg!(target_os = "windows") {
+            let preference = crate::GLOBAL_CLI
+                .get()
+                .and_then(|cli| cli.terminal.as_deref());
+            if let Some(launcher) = crate::e_terminal::Launcher::detect(preference) {
+                let cmd = launcher.command(
+                    &argv[0],
+                    &argv[1..],
+                    &crate::e_terminal::LaunchOptions::default(),
+                );
+                argv = std::iter::once(cmd.get_program())
+                    .chain(cmd.get_args())
+                    .map(|a| a.to_string_lossy().into_owned())
+                    .collect();
+            }
+        }
+        argv
+    }
+
+    /// The `"program" "arg"...` part of an e_window anchor.
+    pub fn anchor_command(&self, file: &Path, line: usize, col: usize) -> String {
+        let argv = self.windowed_argv(file, line, col);
+        argv.iter()
+            .map(|a| format!("\"{}\"", a.replace('"', "\\\"")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// `program`'s file name without directory or extension.
+fn program_name(program: &str) -> String {
+    Path::new(program)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Split a `file:line:col` reference; line and column default to 1. Drive
+/// letters in Windows paths are kept with the file.
+pub fn parse_lineref(lineref: &str) -> (PathBuf, usize, usize) {
+    let mut parts = lineref.rsplitn(3, ':');
+    let last = parts.next().unwrap_or_default();
+    let middle = parts.next();
+    let rest = parts.next();
+    match (rest, middle.and_then(|m| m.parse().ok()), last.parse().ok()) {
+        (Some(file), Some(line), Some(col)) if !file.is_empty() => (PathBuf::from(file), line, col),
+        _ => match lineref
+            .rsplit_once(':')
+            .and_then(|(file, line)| Some((file, line.parse().ok()?)))
+        {
+            Some((file, line)) if !file.is_empty() => (PathBuf::from(file), line, 1),
+            _ => (PathBuf::from(lineref), 1, 1),
+        },
+    }
+}
+
+/// Diagnostics of the last filtered run, kept for `cargo e --open <diag#>`.
+#[cfg(feature = "uses_serde")]
+pub mod last_run {
+    use super::{parse_lineref, Editor};
+    use crate::e_cargocommand_ext::CargoProcessResult;
+    use crate::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    /// One numbered diagnostic, with its file resolved when it was recorded.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct SavedDiagnostic {
+        /// Label of the run it came from (`target` or `target@profile`).
+        #[serde(default)]
+        pub target: String,
+        pub level: String,
+        pub number: usize,
+        pub file: PathBuf,
+        pub line: usize,
+        pub col: usize,
+        pub message: String,
+    }
+
+    impl SavedDiagnostic {
+        /// The label cargo-e prints before it, e.g. `E01`.
+        pub fn label(&self) -> String {
+            format!(
+                "{}{:02}",
+                self.level
+                    .chars()
+                    .next()
+                    .unwrap_or('?')
+                    .to_ascii_uppercase(),
+                self.number
+            )
+        }
+    }
+
+    /// File for the package in the current directory, next to the run history.
+    pub fn default_path() -> PathBuf {
+        let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        crate::e_target_cache::target_dir_for(&cwd.join("Cargo.toml"))
+            .join("cargo-e")
+            .join("last_diagnostics.json")
+    }
+
+    /// The numbered diagnostics of `result`, paths resolved the way cargo's spans are:
+    /// from the cwd, else from the target's package directory.
+    pub fn from_result(result: &CargoProcessResult) -> Vec<SavedDiagnostic> {
+        let target = result.label();
+        result
+            .diagnostics
+            .iter()
+            .filter(|d| !d.lineref.is_empty())
+            .filter_map(|d| {
+                let (file, line, col) = parse_lineref(&d.lineref);
+                let file = crate::e_command_builder::resolve_file_path(
+                    &result.manifest_path,
+                    &file.to_string_lossy(),
+                );
+                Some(SavedDiagnostic {
+                    target: target.clone(),
+                    level: d.level.clone(),
+                    number: d.diag_number?,
+                    file: fs::canonicalize(&file).unwrap_or(file),
+                    line,
+                    col,
+                    message: d.message.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Save the diagnostics of a filtered `result`. The first run of an invocation replaces
+    /// the file; later ones (`--run-all`) are added to it, replacing an earlier run of the
+    /// same target.
+    pub fn record(result: &CargoProcessResult) {
+        static RECORDED: std::sync::Mutex<bool> = std::sync::Mutex::new(false);
+        if !result.is_filter {
+            return;
+        }
+        let mut recorded = RECORDED.lock().unwrap_or_else(|e| e.into_inner());
+        let path = default_path();
+        let mut saved = if *recorded {
+            read(&path).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        *recorded = true;
+        let target = result.label();
+        saved.retain(|d| d.target != target);
+        saved.extend(from_result(result));
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, serde_json::to_string_pretty(&saved)?));
+        if let Err(e) = written {
+            log::warn!("Could not save diagnostics {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn read(path: &Path) -> anyhow::Result<Vec<SavedDiagnostic>> {
+        let text = fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!(
+                "no diagnostics saved at {} ({}); run a target with -f first",
+                path.display(),
+                e
+            )
+        })?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Find `spec` among `saved`: `E3`/`e03`/`W12` by level and number, or a
+    /// bare number for an error, optionally after `target:`; without a target the
+    /// first run that has it wins.
+    pub fn find<'a>(saved: &'a [SavedDiagnostic], spec: &str) -> Option<&'a SavedDiagnostic> {
+        let spec = spec.trim();
+        let (target, spec) = match spec.rsplit_once(':') {
+            Some((target, spec)) => (Some(target), spec),
+            None => (None, spec),
+        };
+        let (level, number) = match spec.chars().next()? {
+            c if c.is_ascii_alphabetic() => (c.to_ascii_lowercase(), &spec[1..]),
+            _ => ('e', spec),
+        };
+        let number: usize = number.parse().ok()?;
+        saved.iter().find(|d| {
+            target.is_none_or(|target| d.target == target)
+                && d.number == number
+                && d.level.chars().next().map(|c| c.to_ascii_lowercase()) == Some(level)
+        })
+    }
+
+    /// `cargo e --open <diag#>`: open that diagnostic of the last filtered run.
+    pub fn open(spec: &str, editor: &Editor) -> anyhow::Result<()> {
+        let saved = read(&default_path())?;
+        let Some(diag) = find(&saved, spec) else {
+            let several = saved.iter().any(|d| d.target != saved[0].target);
+            let available: Vec<String> = saved
+                .iter()
+                .map(|d| {
+                    if several {
+                        format!("{}:{}", d.target, d.label())
+                    } else {
+                        d.label()
+                    }
+                })
+                .collect();
+            anyhow::bail!(
+                "no diagnostic {} in the last filtered run (available: [{}])",
+                spec,
+                available.join(", ")
+            );
+        };
+        println!(
+            "{} {}:{}:{} {}",
+            diag.label(),
+            diag.file.display(),
+            diag.line,
+            diag.col,
+            diag.message
+        );
+        editor.open(&diag.file, diag.line, diag.col)?;
+        Ok(())
+    }
+}
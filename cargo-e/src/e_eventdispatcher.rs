@@ -1,5 +1,6 @@
 use regex::Regex;
 use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::fmt;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
@@ -17,6 +18,8 @@ thread_local! {
     });
 
     static PRIOR_RESPONSE: RefCell<Option<CallbackResponse>> = RefCell::new(None);
+
+    static OUTPUT_SINK: RefCell<Option<OutputSink>> = const { RefCell::new(None) };
 }
 
 /// Context struct for thread-local storage.
@@ -47,6 +50,115 @@ impl ThreadLocalContext {
     }
 }
 
+/// Output of a running target, collected for a caller that draws it itself
+/// (the TUI output pane) instead of letting it reach the terminal.
+///
+/// The capture threads install the builder's sink with [`set_output_sink`];
+/// everything they and their dispatcher callbacks print through
+/// [`out_println!`](crate::out_println) then lands here, ANSI escapes removed.
+#[derive(Debug, Clone, Default)]
+pub struct OutputSink(Arc<Mutex<VecDeque<String>>>);
+
+impl OutputSink {
+    /// Lines kept; older ones are dropped.
+    pub const CAPACITY: usize = 10_000;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, text: &str) {
+        let mut lines = self.0.lock().unwrap();
+        for line in crate::e_fmt::strip_ansi(text).split('\n') {
+            if lines.len() == Self::CAPACITY {
+                lines.pop_front();
+            }
+            lines.push_back(line.trim_end_matches('\r').to_string());
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.0.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// At most `height` lines ending `scroll` lines above the newest, for a
+    /// pane that only shows what fits.
+    pub fn window(&self, height: usize, scroll: usize) -> Vec<String> {
+        let lines = self.0.lock().unwrap();
+        let end = lines.len() - scroll.min(lines.len());
+        let start = end.saturating_sub(height);
+        lines.range(start..end).cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+
+    /// Remove and return the lines collected so far, for a reader that
+    /// forwards them as they arrive.
+    pub fn take(&self) -> Vec<String> {
+        self.0.lock().unwrap().drain(..).collect()
+    }
+}
+
+/// Send this thread's [`out_println!`](crate::out_println) output to `sink`, or back to stdout.
+pub fn set_output_sink(sink: Option<OutputSink>) {
+    OUTPUT_SINK.with(|s| *s.borrow_mut() = sink);
+}
+
+/// The sink set on this thread, so threads it spawns can install it too.
+pub fn current_output_sink() -> Option<OutputSink> {
+    OUTPUT_SINK.with(|s| s.borrow().clone())
+}
+
+/// Print `line` to this thread's output sink if one is set, otherwise to stdout or stderr.
+pub fn emit_line(line: String, to_stderr: bool) {
+    let captured = OUTPUT_SINK.with(|s| match s.borrow().as_ref() {
+        Some(sink) => {
+            sink.push(&line);
+            true
+        }
+        None => false,
+    });
+    if !captured {
+        if to_stderr {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+/// `println!` that honors the thread's [`OutputSink`].
+#[macro_export]
+macro_rules! out_println {
+    () => {
+        $crate::e_eventdispatcher::emit_line(String::new(), false)
+    };
+    ($($arg:tt)*) => {
+        $crate::e_eventdispatcher::emit_line(format!($($arg)*), false)
+    };
+}
+
+/// `eprintln!` that honors the thread's [`OutputSink`].
+#[macro_export]
+macro_rules! out_eprintln {
+    () => {
+        $crate::e_eventdispatcher::emit_line(String::new(), true)
+    };
+    ($($arg:tt)*) => {
+        $crate::e_eventdispatcher::emit_line(format!($($arg)*), true)
+    };
+}
+
 /// Our internal diagnostic level for cargo.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
 pub enum CargoDiagnosticLevel {
@@ -56,27 +168,28 @@ pub enum CargoDiagnosticLevel {
     Note,
 }
 
-// /// A line of source code associated with a diagnostic.
-// #[derive(Debug, Clone)]
-// pub struct CargoDiagnosticSpanLine {
-//     pub text: String,
-//     pub highlight_start: usize,
-//     pub highlight_end: usize,
-// }
+/// A line of source code associated with a diagnostic.
+#[derive(Debug, Clone)]
+pub struct CargoDiagnosticSpanLine {
+    pub text: String,
+    pub highlight_start: usize,
+    pub highlight_end: usize,
+}
 
-// A span (i.e. file location) associated with a diagnostic.
-// #[derive(Debug, Clone)]
-// pub struct CargoDiagnosticSpan {
-//     pub file_name: String,
-//     pub line_start: usize,
-//     pub line_end: usize,
-//     pub column_start: usize,
-//     pub column_end: usize,
-//     pub is_primary: bool,
-//     pub text: Vec<CargoDiagnosticSpanLine>,
-//     pub label: Option<String>,
-//     pub suggested_replacement: Option<String>,
-// }
+/// A span (i.e. file location) associated with a diagnostic.
+#[derive(Debug, Clone)]
+pub struct CargoDiagnosticSpan {
+    pub file_name: String,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub column_start: usize,
+    pub column_end: usize,
+    pub is_primary: bool,
+    pub text: Vec<CargoDiagnosticSpanLine>,
+    pub label: Option<String>,
+    pub suggested_replacement: Option<String>,
+    pub suggestion_applicability: Option<String>,
+}
 
 // /// Our internal diagnostic message.
 // #[derive(Debug, Clone)]
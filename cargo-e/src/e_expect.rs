@@ -0,0 +1,435 @@
+//! Smoke-test expectations for `--run-all`.
+//!
+//! A target declares what a good run looks like, either under
+//! `[targets.<name>]` in the configuration or in the leading `//!` comment of
+//! its source file:
+//!
+//! ```text
+//! //! cargo-e: ready = 'listening on'
+//! //! cargo-e: reject-stderr = 'ERROR'
+//! ```
+//!
+//! | key             | meaning                                                        |
+//! |-----------------|----------------------------------------------------------------|
+//! | `expect-exit`   | the target exits on its own with this code                     |
+//! | `expect-stdout` | regex (or list) that must appear on stdout                     |
+//! | `expect-stderr` | regex (or list) that must appear on stderr                     |
+//! | `reject-stdout` | regex (or list) that must not appear on stdout                 |
+//! | `reject-stderr` | regex (or list) that must not appear on stderr                 |
+//! | `ready`         | regex that marks the target as up; it passes and is stopped    |
+//! | `survive`       | seconds the target must keep running without panicking         |
+//!
+//! Values are TOML, so a regex with backslashes reads best as a literal
+//! string (`'\d+ frames'`). Configuration wins over the doc comment key by key.
+//!
+//! [`Monitor`] sees every line the target prints through the builder's
+//! dispatchers; `run_all_examples` stops the target once [`Monitor::stop_reason`]
+//! says so and stores the [`Verdict`] in the run's result.
+use crate::e_target::CargoTarget;
+use regex::Regex;
+use std::fmt;
+use std::process::ExitStatus;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use toml::Value;
+
+/// What a target has to do for its run to pass.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Expectations {
+    /// Exit code the target must exit with by itself.
+    pub exit: Option<i32>,
+    pub stdout: Vec<String>,
+    pub stderr: Vec<String>,
+    pub reject_stdout: Vec<String>,
+    pub reject_stderr: Vec<String>,
+    /// Output that means the target is up; it then passes and is stopped.
+    pub ready: Option<String>,
+    /// Seconds the target must run without exiting or panicking.
+    pub survive: Option<u64>,
+}
+
+impl Expectations {
+    pub fn is_empty(&self) -> bool {
+        *self == Expectations::default()
+    }
+
+    /// Whether checking these needs the target's output captured.
+    pub fn needs_output(&self) -> bool {
+        !(self.stdout.is_empty()
+            && self.stderr.is_empty()
+            && self.reject_stdout.is_empty()
+            && self.reject_stderr.is_empty()
+            && self.ready.is_none()
+            && self.survive.is_none())
+    }
+
+    /// Read the expectation keys of a `[targets.<name>]` table; other keys are ignored.
+    pub fn merge_toml(&mut self, table: &toml::value::Table) {
+        fn patterns(value: &Value) -> Vec<String> {
+            match value {
+                Value::String(s) => vec![s.clone()],
+                Value::Array(items) => items
+                    .iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect(),
+                _ => Vec::new(),
+            }
+        }
+        for (key, value) in table {
+            match key.as_str() {
+                "expect-exit" => self.exit = value.as_integer().map(|code| code as i32),
+                "expect-stdout" => self.stdout = patterns(value),
+                "expect-stderr" => self.stderr = patterns(value),
+                "reject-stdout" => self.reject_stdout = patterns(value),
+                "reject-stderr" => self.reject_stderr = patterns(value),
+                "ready" => self.ready = value.as_str().map(str::to_string),
+                "survive" => self.survive = value.as_integer().and_then(|s| u64::try_from(s).ok()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Parse the `cargo-e: key = value` lines of a leading doc comment.
+    pub fn from_doc(doc: &[String]) -> Self {
+        let mut expect = Expectations::default();
+        for line in doc {
+            let Some(decl) = line.trim().strip_prefix("cargo-e:") else {
+                continue;
+            };
+            match toml::from_str::<toml::value::Table>(decl.trim()) {
+                Ok(table) => expect.merge_toml(&table),
+                Err(e) => crate::out_eprintln!("cargo-e: ignoring `{}`: {}", line.trim(), e),
+            }
+        }
+        expect
+    }
+
+    /// Take every expectation `other` sets.
+    pub fn overlay(&mut self, other: &Expectations) {
+        if other.exit.is_some() {
+            self.exit = other.exit;
+        }
+        for (into, from) in [
+            (&mut self.stdout, &other.stdout),
+            (&mut self.stderr, &other.stderr),
+            (&mut self.reject_stdout, &other.reject_stdout),
+            (&mut self.reject_stderr, &other.reject_stderr),
+        ] {
+            if !from.is_empty() {
+                *into = from.clone();
+            }
+        }
+        if other.ready.is_some() {
+            self.ready = other.ready.clone();
+        }
+        if other.survive.is_some() {
+            self.survive = other.survive;
+        }
+    }
+}
+
+/// The expectations of `target`: its doc comment, overridden by configuration.
+pub fn declared(target: &CargoTarget) -> Expectations {
+    let mut expect = crate::e_findmain::candidate_main_file(target)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|text| Expectations::from_doc(&crate::e_preview::leading_doc(&text)))
+        .unwrap_or_default();
+    if let Some(ov) = crate::e_config::target_override(&target.name) {
+        expect.overlay(&ov.expect);
+    }
+    expect
+}
+
+/// Which stream a line came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Whether a run met its expectations, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verdict {
+    pub passed: bool,
+    pub reason: String,
+}
+
+impl Verdict {
+    fn pass(reason: String) -> Self {
+        Verdict {
+            passed: true,
+            reason,
+        }
+    }
+
+    fn fail(reason: String) -> Self {
+        Verdict {
+            passed: false,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcome = if self.passed { "PASS" } else { "FAIL" };
+        write!(f, "{}: {}", outcome, self.reason)
+    }
+}
+
+struct Pattern {
+    source: String,
+    regex: Regex,
+    seen: bool,
+}
+
+#[derive(Default)]
+struct State {
+    stdout: Vec<Pattern>,
+    stderr: Vec<Pattern>,
+    reject_stdout: Vec<Pattern>,
+    reject_stderr: Vec<Pattern>,
+    ready: Option<Pattern>,
+    /// When cargo handed over to the target.
+    running_at: Option<Instant>,
+    ready_at: Option<Instant>,
+    rejected: Option<String>,
+    panicked: Option<String>,
+}
+
+/// Checks one run's output against its expectations as the lines arrive.
+pub struct Monitor {
+    expect: Expectations,
+    /// cargo prints nothing before the target's own stderr (`--quiet`).
+    quiet: bool,
+    created: Instant,
+    invalid: Option<String>,
+    state: Mutex<State>,
+}
+
+impl Monitor {
+    pub fn new(expect: Expectations, quiet: bool) -> Self {
+        Self::new_at(expect, quiet, Instant::now())
+    }
+
+    /// A monitor whose run started at `created`.
+    pub fn new_at(expect: Expectations, quiet: bool, created: Instant) -> Self {
+        let mut invalid = None;
+        let mut compile = |sources: &[String]| -> Vec<Pattern> {
+            sources
+                .iter()
+                .filter_map(|source| match Regex::new(source) {
+                    Ok(regex) => Some(Pattern {
+                        source: source.clone(),
+                        regex,
+                        seen: false,
+                    }),
+                    Err(e) => {
+                        invalid.get_or_insert(format!("invalid pattern `{}`: {}", source, e));
+                        None
+                    }
+                })
+                .collect()
+        };
+        let state = State {
+            stdout: compile(&expect.stdout),
+            stderr: compile(&expect.stderr),
+            reject_stdout: compile(&expect.reject_stdout),
+            reject_stderr: compile(&expect.reject_stderr),
+            ready: compile(expect.ready.as_slice()).pop(),
+            ..Default::default()
+        };
+        Monitor {
+            expect,
+            quiet,
+            created,
+            invalid,
+            state: Mutex::new(state),
+        }
+    }
+
+    pub fn expectations(&self) -> &Expectations {
+        &self.expect
+    }
+
+    pub fn line(&self, stream: Stream, line: &str) {
+        self.line_at(stream, line, Instant::now());
+    }
+
+    /// Check a line the target printed at `now`.
+    pub fn line_at(&self, stream: Stream, line: &str, now: Instant) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let state = &mut *state;
+        if stream == Stream::Stderr && !self.quiet && state.running_at.is_none() {
+            // Until cargo's `Running` line, stderr is the build.
+            if is_cargo_running(line) {
+                state.running_at = Some(now);
+            }
+            return;
+        }
+        state.running_at.get_or_insert(now);
+        let (wanted, rejected) = match stream {
+            Stream::Stdout => (&mut state.stdout, &state.reject_stdout),
+            Stream::Stderr => (&mut state.stderr, &state.reject_stderr),
+        };
+        for pattern in wanted.iter_mut().filter(|p| p.regex.is_match(line)) {
+            pattern.seen = true;
+        }
+        if state.rejected.is_none() {
+            if let Some(pattern) = rejected.iter().find(|p| p.regex.is_match(line)) {
+                state.rejected = Some(format!(
+                    "{} matched rejected `{}`: {}",
+                    stream_name(stream),
+                    pattern.source,
+                    line.trim()
+                ));
+            }
+        }
+        if let Some(ready) = state.ready.as_mut().filter(|p| !p.seen) {
+            if ready.regex.is_match(line) {
+                ready.seen = true;
+                state.ready_at = Some(now);
+            }
+        }
+        if stream == Stream::Stderr && line.contains("panicked at") {
+            state
+                .panicked
+                .get_or_insert_with(|| line.trim().to_string());
+        }
+    }
+
+    /// How long the target itself has been running at `now`.
+    fn ran(&self, state: &State, now: Instant) -> Duration {
+        now.saturating_duration_since(state.running_at.unwrap_or(self.created))
+    }
+
+    pub fn stop_reason(&self) -> Option<String> {
+        self.stop_reason_at(Instant::now())
+    }
+
+    /// Why the target should be stopped at `now`: it already failed, or it
+    /// became ready and survived as long as it had to.
+    pub fn stop_reason_at(&self, now: Instant) -> Option<String> {
+        let state = self.state.lock().ok()?;
+        if let Some(rejected) = &state.rejected {
+            return Some(rejected.clone());
+        }
+        if let (Some(_), Some(panic)) = (self.expect.survive, &state.panicked) {
+            return Some(panic.clone());
+        }
+        if self.expect.ready.is_none() && self.expect.survive.is_none() {
+            return None;
+        }
+        let ready = self.expect.ready.is_none() || state.ready_at.is_some();
+        let survived = self
+            .expect
+            .survive
+            .is_none_or(|secs| self.ran(&state, now) >= Duration::from_secs(secs));
+        match (ready, survived) {
+            (true, true) if self.expect.ready.is_some() => Some("ready".to_string()),
+            (true, true) => Some(format!("survived {}s", self.expect.survive.unwrap_or(0))),
+            _ => None,
+        }
+    }
+
+    pub fn verdict(&self, exit: Option<ExitStatus>) -> Verdict {
+        self.verdict_at(exit, Instant::now())
+    }
+
+    /// Judge the run once it is over at `now`; `exit` is set when the target
+    /// exited by itself rather than being stopped.
+    pub fn verdict_at(&self, exit: Option<ExitStatus>, now: Instant) -> Verdict {
+        if let Some(invalid) = &self.invalid {
+            return Verdict::fail(invalid.clone());
+        }
+        let Ok(state) = self.state.lock() else {
+            return Verdict::fail("expectation state is unavailable".to_string());
+        };
+        if let Some(rejected) = &state.rejected {
+            return Verdict::fail(rejected.clone());
+        }
+        let ran = self.ran(&state, now);
+        let mut passed = Vec::new();
+        if let Some(secs) = self.expect.survive {
+            if let Some(panic) = &state.panicked {
+                return Verdict::fail(format!("panicked: {}", panic));
+            }
+            if ran < Duration::from_secs(secs) {
+                let how = if exit.is_some() { "exited" } else { "stopped" };
+                return Verdict::fail(format!(
+                    "{} after {:.1}s; expected to survive {}s",
+                    how,
+                    ran.as_secs_f64(),
+                    secs
+                ));
+            }
+            passed.push(format!("survived {}s", secs));
+        }
+        if let Some(ready) = &state.ready {
+            match state.ready_at {
+                Some(at) => passed.push(format!(
+                    "ready after {:.1}s",
+                    self.ran(&state, at).as_secs_f64()
+                )),
+                None => {
+                    return Verdict::fail(format!("never printed ready `{}`", ready.source));
+                }
+            }
+        }
+        if let Some(code) = self.expect.exit {
+            match exit {
+                Some(status) if status.code() == Some(code) => {
+                    passed.push(format!("exited with {}", code))
+                }
+                Some(status) => {
+                    return Verdict::fail(match status.code() {
+                        Some(actual) => format!("exited with {}; expected {}", actual, code),
+                        None => format!("terminated by a signal; expected exit code {}", code),
+                    })
+                }
+                None => {
+                    return Verdict::fail(format!(
+                        "still running when stopped; expected exit code {}",
+                        code
+                    ))
+                }
+            }
+        }
+        for (stream, wanted) in [
+            (Stream::Stdout, &state.stdout),
+            (Stream::Stderr, &state.stderr),
+        ] {
+            if let Some(missing) = wanted.iter().find(|p| !p.seen) {
+                return Verdict::fail(format!(
+                    "{} never matched `{}`",
+                    stream_name(stream),
+                    missing.source
+                ));
+            }
+        }
+        let matched = state.stdout.len() + state.stderr.len();
+        if matched > 0 {
+            passed.push(format!("matched {} output pattern(s)", matched));
+        }
+        if passed.is_empty() {
+            passed.push("no rejected output".to_string());
+        }
+        Verdict::pass(passed.join(", "))
+    }
+}
+
+fn stream_name(stream: Stream) -> &'static str {
+    match stream {
+        Stream::Stdout => "stdout",
+        Stream::Stderr => "stderr",
+    }
+}
+
+/// cargo's `Running `target/...`` line, possibly coloured.
+fn is_cargo_running(line: &str) -> bool {
+    crate::e_fmt::strip_ansi(line)
+        .trim_start()
+        .starts_with("Running ")
+}
@@ -1,7 +1,8 @@
 // src/e_findmain.rs
 
 use crate::{
-    e_target::{CargoTarget, TargetKind},
+    e_editor::Editor,
+    e_target::{CargoTarget, TargetKind, TargetOrigin},
     prelude::*,
 };
 use toml::Value;
@@ -10,6 +11,9 @@ use crate::e_workspace::{get_workspace_member_manifest_paths, is_workspace_manif
 
 /// Given an Example, attempts to locate the main file.
 ///
+/// A non-extended target whose origin records its source file (a single file,
+/// default binary or `cargo metadata` target) resolves to that file.
+///
 /// For **extended samples** (i.e. sample.extended is true), it first checks for a file at:
 /// 1. `<manifest_dir>/src/main.rs`  
 /// 2. `<manifest_dir>/main.rs`  
@@ -24,6 +28,20 @@ use crate::e_workspace::{get_workspace_member_manifest_paths, is_workspace_manif
 pub fn find_main_file(sample: &CargoTarget) -> Option<PathBuf> {
     let manifest_path = Path::new(&sample.manifest_path);
 
+    // A non-extended target discovered from its source file already knows it.
+    if !sample.extended {
+        if let Some(
+            TargetOrigin::SingleFile(path)
+            | TargetOrigin::DefaultBinary(path)
+            | TargetOrigin::CargoMetadata { src_path: path, .. },
+        ) = &sample.origin
+        {
+            if path.exists() {
+                return Some(path.clone());
+            }
+        }
+    }
+
     // Determine the base directory.
     let base = if is_workspace_manifest(manifest_path) {
         // Try to locate a workspace member manifest matching the sample name.
@@ -46,17 +64,17 @@ pub fn find_main_file(sample: &CargoTarget) -> Option<PathBuf> {
 
     // Check conventional locations for extended samples.
     let candidate_src = base.join("src").join("main.rs");
-    println!("DEBUG: candidate_src: {:?}", candidate_src);
+    debug!("candidate_src: {:?}", candidate_src);
     if candidate_src.exists() {
         return Some(candidate_src);
     }
     let candidate_main = base.join("main.rs");
-    println!("DEBUG: candidate_src: {:?}", candidate_main);
+    debug!("candidate_src: {:?}", candidate_main);
     if candidate_main.exists() {
         return Some(candidate_main);
     }
     let candidate_main = base.join(format!("{}.rs", sample.name));
-    println!("DEBUG: candidate_src: {:?}", candidate_main);
+    debug!("candidate_src: {:?}", candidate_main);
     if candidate_main.exists() {
         return Some(candidate_main);
     }
@@ -65,7 +83,7 @@ pub fn find_main_file(sample: &CargoTarget) -> Option<PathBuf> {
         .join("src")
         .join("bin")
         .join(format!("{}.rs", sample.name));
-    println!("DEBUG: candidate_src: {:?}", candidate_src);
+    debug!("candidate_src: {:?}", candidate_src);
     if candidate_src.exists() {
         return Some(candidate_src);
     }
@@ -110,20 +128,12 @@ pub fn find_main_line(file: &Path) -> Option<(usize, usize)> {
     None
 }
 
-/// Computes the arguments for VSCode given a sample target.
-/// Returns a tuple (folder_str, goto_arg).
-/// - `folder_str` is the folder that will be opened in VSCode.
-/// - `goto_arg` is an optional string of the form "\<file\>:\<line\>:\<column\>"
-///   determined by searching for "fn main" in the candidate file.
-///
-/// For extended samples, it checks first for "src/main.rs", then "main.rs".
-/// For non-extended examples, it assumes the file is at "examples/\<name\>.rs" relative to cwd.
-pub fn compute_vscode_args(sample: &CargoTarget) -> (String, Option<String>) {
+/// The file to open for `sample`: [`find_main_file`], else `src/main.rs` next
+/// to the manifest for binaries and extended examples, else
+/// `examples/<name>.rs` under the current directory for built-in examples.
+pub fn candidate_main_file(sample: &CargoTarget) -> Option<PathBuf> {
     let manifest_path = Path::new(&sample.manifest_path);
-    // Debug print
-    println!("DEBUG: manifest_path: {:?}", manifest_path);
-
-    let candidate_file: Option<PathBuf> = find_main_file(sample).or_else(|| {
+    find_main_file(sample).or_else(|| {
         if sample.kind == TargetKind::Binary
             || (sample.kind == TargetKind::Example && sample.extended)
         {
@@ -147,82 +157,15 @@ pub fn compute_vscode_args(sample: &CargoTarget) -> (String, Option<String>) {
         } else {
             None
         }
-    });
-
-    println!("DEBUG: candidate_file: {:?}", candidate_file);
-
-    let (folder, goto_arg) = if let Some(file) = candidate_file {
-        let folder = file.parent().unwrap_or(&file).to_path_buf();
-        let goto_arg = if let Some((line, col)) = find_main_line(&file) {
-            Some(format!(
-                "{}:{}:{}",
-                file.to_str().unwrap_or_default(),
-                line,
-                col
-            ))
-        } else {
-            Some(file.to_str().unwrap_or_default().to_string())
-        };
-        (folder, goto_arg)
-    } else {
-        (
-            manifest_path
-                .parent()
-                .unwrap_or(manifest_path)
-                .to_path_buf(),
-            None,
-        )
-    };
-
-    let folder_str = folder.to_str().unwrap_or_default().to_string();
-    println!("DEBUG: folder_str: {}", folder_str);
-    println!("DEBUG: goto_arg: {:?}", goto_arg);
-
-    (folder_str, goto_arg)
+    })
 }
 
-/// Asynchronously opens VSCode for the given sample target.
-/// It computes the VSCode arguments using `compute_vscode_args` and then launches VSCode.
-pub async fn open_vscode_for_sample(sample: &CargoTarget) {
-    let (folder_str, goto_arg) = compute_vscode_args(sample);
-
-    let output = if cfg!(target_os = "windows") {
-        if let Some(ref goto) = goto_arg {
-            Command::new("cmd")
-                .args(["/C", "code", folder_str.as_str(), "--goto", goto.as_str()])
-                .output()
-        } else {
-            Command::new("cmd")
-                .args(["/C", "code", folder_str.as_str()])
-                .output()
-        }
-    } else {
-        let mut cmd = Command::new("code");
-        cmd.arg(folder_str.as_str());
-        if let Some(goto) = goto_arg {
-            cmd.args(["--goto", goto.as_str()]);
-        }
-        cmd.output()
-    };
-
-    match output {
-        Ok(output) if output.status.success() => {
-            // VSCode opened successfully.
-            println!("DEBUG: VSCode command output: {:?}", output);
-        }
-        Ok(output) => {
-            let msg = format!(
-                "Error opening VSCode:\nstdout: {}\nstderr: {}",
-                String::from_utf8_lossy(&output.stdout),
-                String::from_utf8_lossy(&output.stderr)
-            );
-            error!("{}", msg);
-        }
-        Err(e) => {
-            let msg = format!("Failed to execute VSCode command: {}", e);
-            error!("{}", msg);
-        }
-    }
+/// Opens the target's main file in `editor`, at `fn main` when it is found.
+/// Falls back to the manifest when no source file can be located.
+pub fn open_target_in_editor(sample: &CargoTarget, editor: &Editor) -> io::Result<()> {
+    let file = candidate_main_file(sample).unwrap_or_else(|| sample.manifest_path.clone());
+    let (line, col) = find_main_line(&file).unwrap_or((1, 1));
+    editor.open(&file, line, col)
 }
 
 // /// Opens Vim for the given sample target.
@@ -589,7 +532,7 @@ mod tests {
     }
 
     #[test]
-    fn test_compute_vscode_args_non_extended() -> Result<(), Box<dyn std::error::Error>> {
+    fn test_candidate_main_file_non_extended() -> Result<(), Box<dyn std::error::Error>> {
         // Create a temporary directory and change the current working directory to it.
         let dir = tempdir()?;
         let temp_path = dir.path();
@@ -619,10 +562,10 @@ mod tests {
             extended: false,
         };
 
-        let (folder_str, goto_arg) = compute_vscode_args(&sample);
-        // In this case, we expect folder_str to contain "examples" and goto_arg to refer to sample_non_ext.rs.
-        assert!(folder_str.contains("examples"));
-        assert!(goto_arg.unwrap().contains("sample_non_ext.rs"));
+        let file = candidate_main_file(&sample).expect("Should find the example file");
+        // In this case, we expect the file to be examples/sample_non_ext.rs.
+        assert!(file.ends_with("examples/sample_non_ext.rs"));
+        assert_eq!(find_main_line(&file).map(|pos| pos.0), Some(1));
 
         // Cleanup is not required because the tempdir will be dropped,
         // which deletes all files inside the temporary directory.
@@ -630,7 +573,7 @@ mod tests {
     }
 
     #[test]
-    fn test_compute_vscode_args_extended_src_main() -> Result<(), Box<dyn std::error::Error>> {
+    fn test_candidate_main_file_extended_src_main() -> Result<(), Box<dyn std::error::Error>> {
         // Simulate an extended sample where Cargo.toml is in the sample folder and "src/main.rs" exists.
         let dir = tempdir()?;
         let sample_dir = dir.path().join("extended_sample");
@@ -653,18 +596,15 @@ mod tests {
             extended: true,
         };
 
-        let (folder_str, goto_arg) = compute_vscode_args(&sample);
-        // The folder should be sample_dir/src since that's where main.rs is.
-        assert!(folder_str.ends_with("src"));
-        let goto = goto_arg.unwrap();
-        // The goto argument should contain main.rs.
-        assert!(goto.contains("main.rs"));
+        let file = candidate_main_file(&sample).expect("Should find src/main.rs");
+        // The file should be sample_dir/src/main.rs.
+        assert_eq!(file, main_rs);
         dir.close()?;
         Ok(())
     }
 
     #[test]
-    fn test_compute_vscode_args_extended_main_rs() -> Result<(), Box<dyn std::error::Error>> {
+    fn test_candidate_main_file_extended_main_rs() -> Result<(), Box<dyn std::error::Error>> {
         // Simulate an extended sample where "src/main.rs" does not exist, but "main.rs" exists.
         let dir = tempdir()?;
         let sample_dir = dir.path().join("extended_sample2");
@@ -687,11 +627,9 @@ mod tests {
             extended: true,
         };
 
-        let (folder_str, goto_arg) = compute_vscode_args(&sample);
-        // The folder should be the sample_dir (since main.rs is directly in it).
-        assert!(folder_str.ends_with("extended_sample2"));
-        let goto = goto_arg.unwrap();
-        assert!(goto.contains("main.rs"));
+        let file = candidate_main_file(&sample).expect("Should find main.rs");
+        // main.rs sits directly in the sample_dir.
+        assert_eq!(file, main_rs);
         dir.close()?;
         Ok(())
     }
@@ -0,0 +1,230 @@
+//! Fuzzy matching of targets, in the style of fzf/skim.
+//!
+//! The pattern's characters must appear in order in the text. Matches score
+//! higher when they are consecutive, start a word (after `_`, `-`, `/`, `.`,
+//! a space, or a lower-to-upper case change) or start the text, and lower the
+//! more characters they skip. Matching ignores case unless the pattern has an
+//! uppercase letter. Used by the TUI's `/` search and for partial matches of
+//! an explicit target name on the command line.
+use crate::e_target::CargoTarget;
+
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CAMEL: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 4;
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// A successful match: its score and the matched character indices in the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    pub positions: Vec<usize>,
+}
+
+/// Match `pattern` against `text`; `None` if its characters do not all appear in order.
+/// An empty pattern matches everything with score 0.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let case_sensitive = pattern.chars().any(char::is_uppercase);
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let pattern: Vec<char> = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+    let original: Vec<char> = text.chars().collect();
+    let text: Vec<char> = original.iter().map(|&c| fold(c)).collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    let pattern: Vec<char> = pattern.into_iter().map(fold).collect();
+    let (n, m) = (pattern.len(), text.len());
+    if n > m {
+        return None;
+    }
+
+    // best[i][j]: best score with pattern[i] matched at text[j];
+    // from[i][j]: where pattern[i - 1] was matched on that path.
+    let mut best = vec![vec![None::<i64>; m]; n];
+    let mut from = vec![vec![0usize; m]; n];
+    for i in 0..n {
+        for j in i..m {
+            if text[j] != pattern[i] {
+                continue;
+            }
+            let mut score = SCORE_MATCH + bonus(&original, j);
+            if i == 0 {
+                best[i][j] = Some(score * BONUS_FIRST_CHAR_MULTIPLIER);
+                continue;
+            }
+            let mut prev_best = None;
+            for (k, prev) in best[i - 1].iter().enumerate().take(j).skip(i - 1) {
+                let Some(prev) = *prev else {
+                    continue;
+                };
+                let transition = if k + 1 == j {
+                    BONUS_CONSECUTIVE
+                } else {
+                    SCORE_GAP_START + SCORE_GAP_EXTENSION * (j - k - 2) as i64
+                };
+                if prev_best.is_none_or(|(s, _)| prev + transition > s) {
+                    prev_best = Some((prev + transition, k));
+                }
+            }
+            if let Some((prev, k)) = prev_best {
+                score += prev;
+                best[i][j] = Some(score);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut j, score) = best[n - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, s)| s.map(|s| (j, s)))
+        .max_by_key(|&(j, s)| (s, std::cmp::Reverse(j)))?;
+    let mut positions = vec![0; n];
+    for i in (0..n).rev() {
+        positions[i] = j;
+        j = from[i][j];
+    }
+    Some(FuzzyMatch { score, positions })
+}
+
+fn bonus(text: &[char], j: usize) -> i64 {
+    let Some(&prev) = j.checked_sub(1).and_then(|p| text.get(p)) else {
+        return BONUS_BOUNDARY;
+    };
+    let c = text[j];
+    if matches!(prev, '_' | '-' | '/' | '\\' | '.' | ':' | ' ') && c.is_alphanumeric() {
+        BONUS_BOUNDARY
+    } else if (prev.is_lowercase() && c.is_uppercase())
+        || (!prev.is_ascii_digit() && c.is_ascii_digit())
+    {
+        BONUS_CAMEL
+    } else {
+        0
+    }
+}
+
+/// Which part of a target a query matched best.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetField {
+    Name,
+    DisplayName,
+    Kind,
+    Package,
+}
+
+/// One target in a ranked search, by index into the searched slice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetMatch {
+    pub index: usize,
+    pub score: i64,
+    pub field: TargetField,
+    /// Matched character indices within that field.
+    pub positions: Vec<usize>,
+}
+
+/// The package a target belongs to, taken from its manifest's directory name.
+pub fn package_label(target: &CargoTarget) -> String {
+    target
+        .manifest_path
+        .parent()
+        .and_then(|dir| dir.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Best match of `query` over a target's name, display name, kind label and package.
+pub fn match_target(query: &str, target: &CargoTarget) -> Option<(TargetField, FuzzyMatch)> {
+    best_field(
+        query,
+        [
+            (TargetField::Name, target.name.clone()),
+            (TargetField::DisplayName, target.display_name.clone()),
+            (TargetField::Kind, target.kind.label().to_string()),
+            (TargetField::Package, package_label(target)),
+        ],
+    )
+}
+
+/// Best match of `query` over a target's name and display name only.
+pub fn match_target_name(query: &str, target: &CargoTarget) -> Option<(TargetField, FuzzyMatch)> {
+    best_field(
+        query,
+        [
+            (TargetField::Name, target.name.clone()),
+            (TargetField::DisplayName, target.display_name.clone()),
+        ],
+    )
+}
+
+fn best_field(
+    query: &str,
+    fields: impl IntoIterator<Item = (TargetField, String)>,
+) -> Option<(TargetField, FuzzyMatch)> {
+    let mut best: Option<(TargetField, FuzzyMatch)> = None;
+    for (field, text) in fields {
+        if let Some(m) = fuzzy_match(query, &text) {
+            // Earlier fields win ties.
+            if best.as_ref().is_none_or(|(_, b)| m.score > b.score) {
+                best = Some((field, m));
+            }
+        }
+    }
+    best
+}
+
+/// Targets matching `query`, best first; an empty query keeps every target in order.
+pub fn rank_targets(query: &str, targets: &[CargoTarget]) -> Vec<TargetMatch> {
+    rank_with(query, targets, match_target)
+}
+
+/// Like [`rank_targets`], but matching names only, so a query that happens to match
+/// a package or kind does not select every target in it.
+pub fn rank_targets_by_name(query: &str, targets: &[CargoTarget]) -> Vec<TargetMatch> {
+    rank_with(query, targets, match_target_name)
+}
+
+fn rank_with(
+    query: &str,
+    targets: &[CargoTarget],
+    matcher: fn(&str, &CargoTarget) -> Option<(TargetField, FuzzyMatch)>,
+) -> Vec<TargetMatch> {
+    let mut ranked: Vec<TargetMatch> = targets
+        .iter()
+        .enumerate()
+        .filter_map(|(index, target)| {
+            let (field, m) = matcher(query, target)?;
+            Some(TargetMatch {
+                index,
+                score: m.score,
+                field,
+                positions: m.positions,
+            })
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| {
+                targets[a.index]
+                    .name
+                    .len()
+                    .cmp(&targets[b.index].name.len())
+            })
+            .then_with(|| a.index.cmp(&b.index))
+    });
+    if query.trim().is_empty() {
+        ranked.sort_by_key(|m| m.index);
+    }
+    ranked
+}
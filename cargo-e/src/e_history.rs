@@ -0,0 +1,355 @@
+//! Run history, stored as JSON lines at `target/cargo-e/history/runs.jsonl`.
+//!
+//! Every result recorded by the process manager is appended with the commit it
+//! ran against. `cargo e history` queries the file, and `--baseline <ref>`
+//! compares a run-all against the last results recorded at or before `ref`.
+use crate::e_cargocommand_ext::CargoProcessResult;
+use crate::prelude::*;
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{BufRead, Write};
+
+/// Length of the SHA prefix used to compare commits.
+const SHA_PREFIX: usize = 7;
+
+/// One recorded run of a target.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// RFC 3339 time the record was written.
+    pub timestamp: String,
+    /// Short SHA of HEAD when the target ran, if inside a git repository.
+    pub sha: Option<String>,
+    pub target: String,
+    pub profile: Option<String>,
+    pub build_secs: Option<f64>,
+    pub run_secs: Option<f64>,
+    pub elapsed_secs: Option<f64>,
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+impl HistoryRecord {
+    pub fn from_result(result: &CargoProcessResult, sha: Option<String>) -> Self {
+        let count = |level: &str| {
+            result
+                .diagnostics
+                .iter()
+                .filter(|d| d.level == level)
+                .count()
+        };
+        let secs = |d: Option<std::time::Duration>| d.map(|d| d.as_secs_f64());
+        HistoryRecord {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            sha,
+            target: result.target_name.clone(),
+            profile: result.profile.clone(),
+            build_secs: secs(result.build_elapsed),
+            run_secs: secs(result.runtime_elapsed),
+            elapsed_secs: secs(result.elapsed_time),
+            exit_code: result.exit_status.and_then(|s| s.code()),
+            success: !crate::e_reports::result_failed(result),
+            errors: count("error"),
+            warnings: count("warning"),
+        }
+    }
+
+    /// `target` or `target@profile`, matching [`CargoProcessResult::label`].
+    pub fn label(&self) -> String {
+        match &self.profile {
+            Some(profile) => format!("{}@{}", self.target, profile),
+            None => self.target.clone(),
+        }
+    }
+
+    fn sha_prefix(&self) -> Option<&str> {
+        self.sha.as_deref().map(|s| &s[..s.len().min(SHA_PREFIX)])
+    }
+}
+
+/// History file used for `manifest_path`, next to the target cache.
+pub fn history_path_for(manifest_path: &Path) -> PathBuf {
+    crate::e_target_cache::target_dir_for(manifest_path)
+        .join("cargo-e")
+        .join("history")
+        .join("runs.jsonl")
+}
+
+/// History file for the package in the current directory.
+pub fn default_history_path() -> PathBuf {
+    let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    history_path_for(&cwd.join("Cargo.toml"))
+}
+
+pub fn append(path: &Path, record: &HistoryRecord) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let line = serde_json::to_string(record)?;
+    writeln!(file, "{}", line)
+}
+
+/// Read every record, oldest first. Lines that do not parse are skipped.
+pub fn read(path: &Path) -> io::Result<Vec<HistoryRecord>> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut records = Vec::new();
+    for line in io::BufReader::new(file).lines() {
+        let line = line?;
+        match serde_json::from_str(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => log::debug!("Skipping history line: {}", e),
+        }
+    }
+    Ok(records)
+}
+
+/// Append `result` to the default history file, tagged with the current commit.
+pub fn record(result: &CargoProcessResult) {
+    static SHA: OnceCell<Option<String>> = OnceCell::new();
+    let sha = SHA
+        .get_or_init(|| crate::e_reports::current_short_sha().ok())
+        .clone();
+    let path = default_history_path();
+    if let Err(e) = append(&path, &HistoryRecord::from_result(result, sha)) {
+        log::warn!("Could not write run history {}: {}", path.display(), e);
+    }
+}
+
+/// Build time statistics for one target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildTimes {
+    pub label: String,
+    pub runs: usize,
+    pub mean_secs: f64,
+    pub max_secs: f64,
+}
+
+/// Targets ordered by mean build time, slowest first.
+pub fn slowest(records: &[HistoryRecord], limit: usize) -> Vec<BuildTimes> {
+    let mut times: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for record in records {
+        if let Some(secs) = record.build_secs {
+            times.entry(record.label()).or_default().push(secs);
+        }
+    }
+    let mut out: Vec<BuildTimes> = times
+        .into_iter()
+        .map(|(label, secs)| BuildTimes {
+            label,
+            runs: secs.len(),
+            mean_secs: secs.iter().sum::<f64>() / secs.len() as f64,
+            max_secs: secs.iter().cloned().fold(0.0, f64::max),
+        })
+        .collect();
+    out.sort_by(|a, b| b.mean_secs.total_cmp(&a.mean_secs));
+    out.truncate(limit);
+    out
+}
+
+/// A target that both passed and failed on the same commit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flaky {
+    pub label: String,
+    pub sha: Option<String>,
+    pub passes: usize,
+    pub failures: usize,
+}
+
+/// Targets whose outcome changed between runs of the same commit.
+pub fn flaky(records: &[HistoryRecord]) -> Vec<Flaky> {
+    let mut outcomes: BTreeMap<(String, Option<String>), (usize, usize)> = BTreeMap::new();
+    for record in records {
+        let key = (record.label(), record.sha_prefix().map(str::to_string));
+        let entry = outcomes.entry(key).or_default();
+        if record.success {
+            entry.0 += 1;
+        } else {
+            entry.1 += 1;
+        }
+    }
+    outcomes
+        .into_iter()
+        .filter(|(_, (passes, failures))| *passes > 0 && *failures > 0)
+        .map(|((label, sha), (passes, failures))| Flaky {
+            label,
+            sha,
+            passes,
+            failures,
+        })
+        .collect()
+}
+
+/// A target that passed at the baseline but fails now.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub label: String,
+    pub baseline_sha: Option<String>,
+    pub current: HistoryRecord,
+}
+
+/// The most recent record of each target, in first-seen order.
+pub fn latest_per_target(records: &[HistoryRecord]) -> Vec<HistoryRecord> {
+    let mut order = Vec::new();
+    let mut latest: HashMap<String, &HistoryRecord> = HashMap::new();
+    for record in records {
+        if latest.insert(record.label(), record).is_none() {
+            order.push(record.label());
+        }
+    }
+    order
+        .into_iter()
+        .filter_map(|label| latest.remove(&label).cloned())
+        .collect()
+}
+
+/// Compare `current` against the last `history` record of each target made on a
+/// commit in `baseline` (SHA prefixes, see [`ancestors_of`]).
+pub fn regressions(
+    history: &[HistoryRecord],
+    baseline: &HashSet<String>,
+    current: &[HistoryRecord],
+) -> Vec<Regression> {
+    let mut at_baseline: HashMap<String, &HistoryRecord> = HashMap::new();
+    for record in history {
+        if record
+            .sha_prefix()
+            .is_some_and(|sha| baseline.contains(sha))
+        {
+            at_baseline.insert(record.label(), record);
+        }
+    }
+    latest_per_target(current)
+        .into_iter()
+        .filter(|record| !record.success)
+        .filter_map(|record| {
+            let base = at_baseline.get(&record.label())?;
+            base.success.then(|| Regression {
+                label: record.label(),
+                baseline_sha: base.sha.clone(),
+                current: record,
+            })
+        })
+        .collect()
+}
+
+/// SHA prefixes of `git_ref` and every commit reachable from it.
+pub fn ancestors_of(git_ref: &str) -> anyhow::Result<HashSet<String>> {
+    let repo = git2::Repository::discover(".").context("not inside a git repository")?;
+    let commit = repo
+        .revparse_single(git_ref)
+        .and_then(|obj| obj.peel_to_commit())
+        .with_context(|| format!("unknown git ref `{}`", git_ref))?;
+    let mut walk = repo.revwalk()?;
+    walk.push(commit.id())?;
+    let mut shas = HashSet::new();
+    for oid in walk {
+        let hex = oid?.to_string();
+        shas.insert(hex[..SHA_PREFIX].to_string());
+    }
+    Ok(shas)
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "cargo e history", about = "Query the recorded run history.")]
+pub struct HistoryCli {
+    #[arg(
+        long,
+        help = "History file [default: target/cargo-e/history/runs.jsonl]."
+    )]
+    pub path: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<HistoryCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryCommand {
+    /// Targets with the slowest mean build time (default).
+    Slowest {
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Targets that both passed and failed on the same commit.
+    Flaky,
+    /// Targets that passed at REF but whose latest run failed.
+    FailingSince {
+        #[arg(value_name = "REF")]
+        git_ref: String,
+    },
+}
+
+/// Entry point for `cargo e history ...`; `args[0]` is the `history` word itself.
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    let parsed = HistoryCli::try_parse_from(args).unwrap_or_else(|e| e.exit());
+    let path = parsed.path.unwrap_or_else(default_history_path);
+    let records =
+        read(&path).with_context(|| format!("failed to read history {}", path.display()))?;
+    if records.is_empty() {
+        println!("No run history in {}", path.display());
+        return Ok(());
+    }
+    match parsed
+        .command
+        .unwrap_or(HistoryCommand::Slowest { limit: 10 })
+    {
+        HistoryCommand::Slowest { limit } => {
+            for t in slowest(&records, limit) {
+                println!(
+                    "{:>8.2}s mean {:>8.2}s max {:>4} runs  {}",
+                    t.mean_secs, t.max_secs, t.runs, t.label
+                );
+            }
+        }
+        HistoryCommand::Flaky => {
+            let flaky = flaky(&records);
+            if flaky.is_empty() {
+                println!("No flaky targets.");
+            }
+            for f in flaky {
+                println!(
+                    "{}  {} passed, {} failed at {}",
+                    f.label,
+                    f.passes,
+                    f.failures,
+                    f.sha.as_deref().unwrap_or("(no commit)")
+                );
+            }
+        }
+        HistoryCommand::FailingSince { git_ref } => {
+            let baseline = ancestors_of(&git_ref)?;
+            let found = regressions(&records, &baseline, &latest_per_target(&records));
+            if found.is_empty() {
+                println!("No targets started failing since {}.", git_ref);
+            }
+            for r in found {
+                print_regression(&r);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn print_regression(r: &Regression) {
+    println!(
+        "{}  passed at {}, failed at {} (exit {}, {} errors)",
+        r.label,
+        r.baseline_sha.as_deref().unwrap_or("?"),
+        r.current.sha.as_deref().unwrap_or("?"),
+        r.current
+            .exit_code
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "-".to_string()),
+        r.current.errors
+    );
+}
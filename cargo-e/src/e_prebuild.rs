@@ -1,68 +1,328 @@
 use anyhow::{Context, Result};
-use std::process::Command;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
-use crate::e_target::{CargoTarget, TargetKind};
+use crate::e_cargocommand_ext::CargoDiagnostic;
+use crate::e_target::{CargoTarget, TargetKind, TargetOrigin};
 
-/// Prebuilds all given targets by invoking `cargo build` with the appropriate flags.
-///
-/// This function supports all target kinds:
-/// - **Example:** Runs `cargo build --example <name>`
-/// - **ExtendedExample:** Runs `cargo build --example <name> --manifest-path <manifest_path>`
-/// - **Binary:** Runs `cargo build --bin <name>`
-/// - **ExtendedBinary:** Runs `cargo build --bin <name> --manifest-path <manifest_path>`
-///
-/// # Parameters
-///
-/// - `targets`: A slice of `Example` instances representing the targets to prebuild.
-///
-/// # Returns
+/// Identifies a target across packages: manifest, whether it is an example, and its name.
+type TargetKey = (PathBuf, bool, String);
+
+/// Identifies one `cargo build`: the manifest and the sorted required features.
+type GroupKey = (PathBuf, Vec<String>);
+
+fn key_of(target: &CargoTarget) -> TargetKey {
+    (
+        target.manifest_path.clone(),
+        is_example(target),
+        target.name.clone(),
+    )
+}
+
+fn is_example(target: &CargoTarget) -> bool {
+    matches!(
+        target.kind,
+        TargetKind::Example | TargetKind::ExtendedExample
+    )
+}
+
+/// Whether `target` is built by `cargo build` into a plain executable that can be run directly.
+pub fn is_prebuildable(target: &CargoTarget) -> bool {
+    matches!(
+        target.kind,
+        TargetKind::Example
+            | TargetKind::ExtendedExample
+            | TargetKind::Binary
+            | TargetKind::ExtendedBinary
+    )
+}
+
+/// What building the selected targets up front produced.
+#[derive(Debug, Default, Clone)]
+pub struct Prebuilt {
+    artifacts: HashMap<TargetKey, PathBuf>,
+    diagnostics: HashMap<TargetKey, Vec<CargoDiagnostic>>,
+    /// Diagnostics no selected target accounts for, such as the package's library.
+    pub shared: Vec<CargoDiagnostic>,
+}
+
+impl Prebuilt {
+    /// The executable built for `target`, if its build succeeded.
+    pub fn artifact(&self, target: &CargoTarget) -> Option<&Path> {
+        self.artifacts.get(&key_of(target)).map(PathBuf::as_path)
+    }
+
+    /// Diagnostics attributed to `target` during the build.
+    pub fn diagnostics(&self, target: &CargoTarget) -> &[CargoDiagnostic] {
+        self.diagnostics
+            .get(&key_of(target))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Read cargo's JSON messages for one build of `targets` (all from `manifest_path`),
+    /// echoing each rendered diagnostic as cargo would have printed it.
+    pub fn ingest<R: BufRead>(
+        &mut self,
+        manifest_path: &Path,
+        targets: &[&CargoTarget],
+        reader: R,
+    ) {
+        use cargo_metadata::Message;
+        let manifest = manifest_path.to_path_buf();
+        let mut counts = HashMap::new();
+        for message in Message::parse_stream(reader).flatten() {
+            match message {
+                Message::CompilerArtifact(artifact) => {
+                    let Some(exe) = artifact.executable else {
+                        continue;
+                    };
+                    let selected = targets.iter().find(|t| {
+                        t.name == artifact.target.name
+                            && is_example(t) == artifact.target.is_example()
+                    });
+                    if let Some(target) = selected {
+                        self.artifacts
+                            .insert(key_of(target), PathBuf::from(exe.as_str()));
+                    }
+                }
+                Message::CompilerMessage(msg) => {
+                    if let Some(rendered) = &msg.message.rendered {
+                        crate::out_eprintln!("{}", rendered.trim_end());
+                    }
+                    let Some(mut diag) =
+                        CargoDiagnostic::from_compiler_diagnostic(&msg.message, &manifest, None)
+                    else {
+                        continue;
+                    };
+                    // The primary span's file decides; the crate rustc was compiling is
+                    // the fallback for spans outside every target's own sources.
+                    let owner = primary_file(&diag)
+                        .and_then(|file| owning_target(&file, targets))
+                        .or_else(|| {
+                            targets.iter().copied().find(|t| {
+                                t.name == msg.target.name
+                                    && is_example(t) == msg.target.is_example()
+                            })
+                        });
+                    match owner {
+                        Some(target) => {
+                            let key = key_of(target);
+                            if let Some(level) = diag.diagnostic_level() {
+                                let count = counts.entry((key.clone(), level)).or_insert(0);
+                                *count += 1;
+                                diag.diag_number = Some(*count);
+                            }
+                            self.diagnostics.entry(key).or_default().push(diag);
+                        }
+                        None => self.shared.push(diag),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn primary_file(diag: &CargoDiagnostic) -> Option<PathBuf> {
+    diag.spans
+        .iter()
+        .find(|s| s.is_primary)
+        .or_else(|| diag.spans.first())
+        .map(|s| PathBuf::from(&s.file_name))
+}
+
+/// The target whose sources contain `file`: its own source file, or the whole directory
+/// of a multi-file example or binary (`examples/name/main.rs`, `src/bin/name/main.rs`).
+pub fn owning_target<'a>(file: &Path, targets: &[&'a CargoTarget]) -> Option<&'a CargoTarget> {
+    targets.iter().copied().find(|target| {
+        let src = match &target.origin {
+            Some(TargetOrigin::CargoMetadata { src_path, .. })
+            | Some(TargetOrigin::SingleFile(src_path))
+            | Some(TargetOrigin::MultiFile(src_path))
+            | Some(TargetOrigin::DefaultBinary(src_path))
+            | Some(TargetOrigin::TomlSpecified(src_path)) => src_path,
+            _ => return false,
+        };
+        // Span paths are resolved the same way.
+        let src = &crate::e_command_builder::resolve_file_path(
+            &target.manifest_path,
+            &src.to_string_lossy(),
+        );
+        if file == src.as_path() || (src.is_dir() && file.starts_with(src)) {
+            return true;
+        }
+        let dir = src.parent().filter(|dir| {
+            src.file_name().is_some_and(|name| name == "main.rs")
+                && dir.file_name().is_some_and(|name| name != "src")
+        });
+        dir.is_some_and(|dir| file.starts_with(dir))
+    })
+}
+
+/// Arguments for one `cargo build` of every target in `targets`, which share `manifest_path`.
 ///
-/// Returns `Ok(())` if all targets build successfully; otherwise, returns an error.
-pub fn prebuild_examples(targets: &[CargoTarget]) -> Result<()> {
+/// The build enables the union of the targets' required features, which changes what the
+/// others are compiled with; [`prebuild_targets`] only groups targets that require the
+/// same features. `--keep-going` lets the other targets finish when one fails to compile.
+pub fn build_args(
+    manifest_path: &Path,
+    targets: &[&CargoTarget],
+    release: bool,
+    quiet: bool,
+) -> Vec<String> {
+    let mut args = vec![
+        "build".to_string(),
+        "--message-format=json-diagnostic-rendered-ansi".to_string(),
+        "--keep-going".to_string(),
+        "--manifest-path".to_string(),
+        manifest_path.to_string_lossy().to_string(),
+    ];
+    if release {
+        args.push("--release".into());
+    }
+    if quiet {
+        args.push("--quiet".into());
+    }
+    let mut features: Vec<String> = Vec::new();
     for target in targets {
-        // Determine the build flag and whether to include the manifest path
-        let (build_flag, use_manifest) = match target.kind {
-            TargetKind::Example => ("--example", false),
-            TargetKind::ExtendedExample => ("--example", true),
-            TargetKind::Binary => ("--bin", false),
-            TargetKind::ExtendedBinary => ("--bin", true),
-            TargetKind::ManifestTauriExample => ("", true),
-            TargetKind::ManifestTauri => ("", true),
-            TargetKind::Test => ("--test", true),
-            TargetKind::Manifest => ("", true),
-            TargetKind::Bench => ("", true),
-            TargetKind::ManifestDioxus => ("", true),
-            TargetKind::ManifestDioxusExample => ("", true),
-            TargetKind::ManifestLeptos => ("", true),
-            TargetKind::Unknown => ("", true),
-            _ => ("", true),
+        let flag = if is_example(target) {
+            "--example"
+        } else {
+            "--bin"
         };
+        args.push(flag.into());
+        args.push(target.name.clone());
+        for feature in target.required_features().unwrap_or_default().split(',') {
+            if !feature.is_empty() && !features.iter().any(|f| f == feature) {
+                features.push(feature.to_string());
+            }
+        }
+    }
+    if !features.is_empty() {
+        args.push("--features".into());
+        args.push(features.join(","));
+    }
+    args
+}
 
-        if build_flag.is_empty() {
-            return Ok(());
+/// The targets [`prebuild_targets`] builds, split into one `cargo build` each: by manifest
+/// and by required features. Targets with a configured runner are left to cargo.
+pub fn build_groups(targets: &[CargoTarget]) -> Vec<(PathBuf, Vec<&CargoTarget>)> {
+    let mut groups: Vec<(GroupKey, Vec<&CargoTarget>)> = Vec::new();
+    for target in targets.iter().filter(|t| is_prebuildable(t)) {
+        if crate::e_config::target_override(&target.name).is_some_and(|ov| ov.runner.is_some()) {
+            continue;
+        }
+        let mut features: Vec<String> = target
+            .required_features()
+            .unwrap_or_default()
+            .split(',')
+            .filter(|f| !f.is_empty())
+            .map(str::to_string)
+            .collect();
+        features.sort();
+        features.dedup();
+        let key = (target.manifest_path.clone(), features);
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(target),
+            None => groups.push((key, vec![target])),
         }
-        println!("Prebuilding target [{}]: {}", build_flag, target.name);
+    }
+    groups
+        .into_iter()
+        .map(|((manifest_path, _), group)| (manifest_path, group))
+        .collect()
+}
 
-        let mut command = Command::new("cargo");
-        command.arg("build").arg(build_flag).arg(&target.name);
+/// Builds every example and binary in `targets` up front, with one `cargo build` per manifest
+/// and set of required features, so each executable is what `cargo run` would have built.
+///
+/// Runs of the targets then start their executables directly, so parallel runs do not wait
+/// on cargo's build directory lock or rebuild shared dependencies in turn. Targets with a
+/// configured runner, and kinds cargo does not build into a plain executable, are left out
+/// and run through cargo as before; so are targets that failed to compile.
+///
+/// # Errors
+///
+/// Returns an error if cargo cannot be started.
+pub fn prebuild_targets(targets: &[CargoTarget], cli: &crate::Cli) -> Result<Prebuilt> {
+    let groups = build_groups(targets);
 
-        if use_manifest {
-            command.args(&[
-                "--manifest-path",
-                &target.manifest_path.to_str().unwrap_or_default().to_owned(),
-            ]);
-        }
+    let mut prebuilt = Prebuilt::default();
+    for (manifest_path, group) in &groups {
+        let args = build_args(manifest_path, group, cli.release, cli.quiet);
+        crate::out_println!("cargo {}", args.join(" "));
 
-        let status = command
-            .status()
-            .with_context(|| format!("Cargo build failed for target {}", target.name))?;
+        let _restore = RestoreManifest {
+            path: manifest_path,
+            original: crate::e_manifest::maybe_patch_manifest_for_run(manifest_path)
+                .context("Failed to patch manifest for build")?,
+        };
+        let mut child = Command::new("cargo")
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| {
+                format!(
+                    "Failed to start cargo build for {}",
+                    manifest_path.display()
+                )
+            })?;
 
+        // cargo's progress and summary lines go to stderr.
+        let stderr = child.stderr.take().map(|stderr| {
+            let sink = crate::e_eventdispatcher::current_output_sink();
+            std::thread::spawn(move || {
+                crate::e_eventdispatcher::set_output_sink(sink);
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    crate::out_eprintln!("{}", line);
+                }
+            })
+        });
+        if let Some(stdout) = child.stdout.take() {
+            prebuilt.ingest(manifest_path, group, BufReader::new(stdout));
+        }
+        let status = child.wait().context("Failed to wait for cargo build")?;
+        if let Some(stderr) = stderr {
+            let _ = stderr.join();
+        }
+        let built = group
+            .iter()
+            .filter(|t| prebuilt.artifact(t).is_some())
+            .count();
         if !status.success() {
-            return Err(anyhow::anyhow!(
-                "Prebuild failed for target {}",
-                target.name
-            ));
+            crate::out_eprintln!(
+                "Prebuild of {} built {} of {} target(s); the rest run through cargo.",
+                manifest_path.display(),
+                built,
+                group.len()
+            );
+        }
+    }
+    Ok(prebuilt)
+}
+
+/// Puts back a manifest patched by `maybe_patch_manifest_for_run` when dropped, however
+/// the build ends.
+struct RestoreManifest<'a> {
+    path: &'a Path,
+    original: Option<String>,
+}
+
+impl Drop for RestoreManifest<'_> {
+    fn drop(&mut self) {
+        if let Some(original) = self.original.take() {
+            if let Err(e) = std::fs::write(self.path, original) {
+                crate::out_eprintln!(
+                    "Failed to restore patched manifest {}: {}",
+                    self.path.display(),
+                    e
+                );
+            }
         }
     }
-    Ok(())
 }
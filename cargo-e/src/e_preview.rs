@@ -0,0 +1,189 @@
+//! Source previews of targets, shown next to the TUI's target list.
+//!
+//! [`Preview::load`] reads the file [`find_main_file`] resolves for a target
+//! (for extended targets, the sub-project's `main.rs`) along with its leading
+//! `//!` doc comment and `required-features`. [`highlight`] splits one line
+//! into tokens for lightweight Rust syntax highlighting; it works a line at a
+//! time, so block comments and strings spanning lines are not tracked.
+use std::path::PathBuf;
+
+use crate::e_findmain::find_main_file;
+use crate::e_target::CargoTarget;
+
+/// A target's source file and the facts shown above it.
+#[derive(Debug, Clone)]
+pub struct Preview {
+    pub source: PathBuf,
+    pub manifest_path: PathBuf,
+    /// The leading `//!` doc comment, one entry per line, markers removed.
+    pub doc: Vec<String>,
+    pub required_features: Option<String>,
+    pub lines: Vec<String>,
+    /// 0-based line of `fn main`, if the file has one.
+    pub main_line: Option<usize>,
+}
+
+impl Preview {
+    /// `None` if no source file can be found or read.
+    pub fn load(target: &CargoTarget) -> Option<Preview> {
+        let source = find_main_file(target)?;
+        let text = std::fs::read_to_string(&source).ok()?;
+        Some(Preview::from_source(target, source, &text))
+    }
+
+    pub fn from_source(target: &CargoTarget, source: PathBuf, text: &str) -> Preview {
+        let lines: Vec<String> = text.lines().map(|l| l.replace('\t', "    ")).collect();
+        Preview {
+            source,
+            manifest_path: target.manifest_path.clone(),
+            doc: leading_doc(text),
+            required_features: target.required_features(),
+            main_line: lines.iter().position(|l| l.contains("fn main")),
+            lines,
+        }
+    }
+
+    /// The doc comment's first paragraph on one line, or an empty string.
+    pub fn summary(&self) -> String {
+        self.doc
+            .iter()
+            .map(|l| l.trim())
+            .take_while(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// The `//!` lines at the top of `text`, after any blank lines or `#!` line.
+pub fn leading_doc(text: &str) -> Vec<String> {
+    let mut doc = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("//!") {
+            doc.push(
+                rest.strip_prefix(' ')
+                    .unwrap_or(rest)
+                    .trim_end()
+                    .to_string(),
+            );
+        } else if doc.is_empty() && (trimmed.is_empty() || trimmed.starts_with("#!")) {
+            continue;
+        } else {
+            break;
+        }
+    }
+    doc
+}
+
+/// What a piece of highlighted source is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Plain,
+    Keyword,
+    /// An identifier starting with an uppercase letter.
+    Type,
+    String,
+    Number,
+    Comment,
+    /// A macro invocation, `!` included.
+    Macro,
+    Attribute,
+    Lifetime,
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// Split one line of Rust into tokens; adjacent plain text is merged.
+pub fn highlight(line: &str) -> Vec<(Token, String)> {
+    let mut tokens: Vec<(Token, String)> = Vec::new();
+    let mut push = |token: Token, text: String| match tokens.last_mut() {
+        Some((Token::Plain, last)) if token == Token::Plain => last.push_str(&text),
+        _ => tokens.push((token, text)),
+    };
+    if line.trim_start().starts_with('#') {
+        push(Token::Attribute, line.to_string());
+        return tokens;
+    }
+    let chars: Vec<char> = line.chars().collect();
+    let collect = |from: usize, to: usize| chars[from..to].iter().collect::<String>();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            push(Token::Comment, collect(i, chars.len()));
+            break;
+        } else if c == '"' {
+            i = string_end(&chars, i + 1);
+            push(Token::String, collect(start, i));
+        } else if c == '\'' {
+            let is_char = chars.get(i + 1) == Some(&'\\') || chars.get(i + 2) == Some(&'\'');
+            if is_char {
+                i += 2;
+                while i < chars.len() && chars[i] != '\'' {
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                push(Token::String, collect(start, i));
+            } else {
+                i += 1;
+                while i < chars.len() && is_ident(chars[i]) {
+                    i += 1;
+                }
+                push(Token::Lifetime, collect(start, i));
+            }
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (is_ident(chars[i]) || chars[i] == '.') {
+                // Stop before a method call or range on an integer.
+                if chars[i] == '.' && !chars.get(i + 1).is_some_and(|n| n.is_ascii_digit()) {
+                    break;
+                }
+                i += 1;
+            }
+            push(Token::Number, collect(start, i));
+        } else if is_ident(c) {
+            while i < chars.len() && is_ident(chars[i]) {
+                i += 1;
+            }
+            let word = collect(start, i);
+            if matches!(word.as_str(), "r" | "b" | "br") && chars.get(i) == Some(&'"') {
+                i = string_end(&chars, i + 1);
+                push(Token::String, collect(start, i));
+            } else if chars.get(i) == Some(&'!') && chars.get(i + 1) != Some(&'=') {
+                i += 1;
+                push(Token::Macro, collect(start, i));
+            } else if KEYWORDS.contains(&word.as_str()) {
+                push(Token::Keyword, word);
+            } else if word.starts_with(char::is_uppercase) {
+                push(Token::Type, word);
+            } else {
+                push(Token::Plain, word);
+            }
+        } else {
+            i += 1;
+            push(Token::Plain, c.to_string());
+        }
+    }
+    tokens
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Index just past the `"` closing a string whose body starts at `i`.
+fn string_end(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
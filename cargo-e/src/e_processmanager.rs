@@ -2,13 +2,14 @@
 
 use crate::e_cargocommand_ext::{CargoProcessHandle, CargoProcessResult};
 use crate::e_command_builder::TerminalError;
+use crate::e_resources::{target_usage, tree_usage, ResourceLimits, ResourceUsage, Sampler};
 use crate::e_target::CargoTarget;
 use crate::{Cli, GLOBAL_MANAGER};
 use chrono::Local;
 use std::process::ExitStatus;
-use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
 use std::sync::mpsc::{self, Receiver, Sender};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
 use std::thread::{self, sleep};
 use std::time::{Duration, SystemTime};
 use sysinfo::System;
@@ -34,7 +35,7 @@ use {
 
 impl ProcessObserver for ProcessManager {
     fn on_spawn(&self, pid: u32, handle: Arc<Mutex<CargoProcessHandle>>) {
-        self.processes.insert(pid, handle.clone());
+        self.register(pid, handle);
     }
 }
 
@@ -92,6 +93,11 @@ pub struct ProcessManager {
     processes: dashmap::DashMap<u32, Arc<Mutex<CargoProcessHandle>>>,
     results: dashmap::DashMap<u32, CargoProcessResult>,
     signal_times: SignalTimes, // <-- Add this line
+    /// Set by [`ProcessManager::request_stop`]; `--run-all` starts no new targets.
+    stop_requested: AtomicBool,
+    /// Usage sampled so far for processes whose result is not recorded yet.
+    sampling: dashmap::DashMap<u32, ResourceUsage>,
+    limits: ResourceLimits,
 }
 
 impl Drop for ProcessManager {
@@ -121,8 +127,17 @@ impl ProcessManager {
             processes: dashmap::DashMap::new(),
             results: dashmap::DashMap::new(),
             signal_times: SignalTimes::new(),
+            stop_requested: AtomicBool::new(false),
+            sampling: dashmap::DashMap::new(),
+            limits: ResourceLimits::from_cli(_cli),
         });
         ProcessManager::install_handler(Arc::clone(&manager), rx);
+        if _cli.sample_interval > 0 {
+            ProcessManager::start_sampler(
+                Arc::downgrade(&manager),
+                Duration::from_millis(_cli.sample_interval),
+            );
+        }
         crate::GLOBAL_MANAGER.get_or_init(|| Arc::clone(&manager));
         crate::GLOBAL_EWINDOW_PIDS.get_or_init(|| dashmap::DashMap::new());
         manager
@@ -147,6 +162,16 @@ impl ProcessManager {
     pub fn time_between_signals(&self) -> Option<Duration> {
         self.signal_times.time_between_signals()
     }
+    /// Ask a running `--run-all` to stop its current targets and start no more.
+    pub fn request_stop(&self) {
+        self.stop_requested.store(true, Ordering::SeqCst);
+    }
+    pub fn stop_requested(&self) -> bool {
+        self.stop_requested.load(Ordering::SeqCst)
+    }
+    pub fn clear_stop(&self) {
+        self.stop_requested.store(false, Ordering::SeqCst);
+    }
     pub fn reset_signalled(&self) {
         self.signalled_count.store(0, Ordering::SeqCst);
     }
@@ -279,9 +304,56 @@ impl ProcessManager {
 
     pub fn register(&self, pid: u32, handle: Arc<Mutex<CargoProcessHandle>>) -> u32 {
         self.processes.insert(pid, handle.clone());
+        self.sampling.insert(pid, ResourceUsage::default());
         pid
     }
 
+    /// Every `interval`, add a CPU/RSS sample for each process still being
+    /// sampled, counting its descendants. A `cargo run` counts only once cargo
+    /// has finished building, and then only the target it started, not cargo.
+    /// Stops once the manager is dropped.
+    fn start_sampler(manager: Weak<Self>, interval: Duration) {
+        thread::spawn(move || {
+            let mut sampler = Sampler::new();
+            loop {
+                thread::sleep(interval);
+                let Some(this) = manager.upgrade() else {
+                    break;
+                };
+                let roots: Vec<u32> = this.sampling.iter().map(|entry| *entry.key()).collect();
+                if roots.is_empty() {
+                    continue;
+                }
+                let snapshot = sampler.snapshot(&roots);
+                for pid in roots {
+                    let Some(is_compiler_target) = this.running_target(pid) else {
+                        continue;
+                    };
+                    let usage = if is_compiler_target {
+                        target_usage(pid, &snapshot)
+                    } else {
+                        tree_usage(pid, &snapshot)
+                    };
+                    if let (Some((cpu, rss)), Some(mut entry)) =
+                        (usage, this.sampling.get_mut(&pid))
+                    {
+                        entry.record(cpu, rss);
+                    }
+                }
+            }
+        });
+    }
+
+    /// For a process past its build, whether it is cargo building and running a
+    /// target (rather than the target itself). `None` while it is still building,
+    /// or when its handle is busy.
+    fn running_target(&self, pid: u32) -> Option<bool> {
+        let handle = self.processes.get(&pid).map(|entry| entry.clone())?;
+        let handle = handle.try_lock().ok()?;
+        let stats = handle.stats.try_lock().ok()?;
+        stats.build_finished_time.map(|_| stats.is_comiler_target)
+    }
+
     pub fn take(&self, pid: u32) -> Option<Arc<Mutex<CargoProcessHandle>>> {
         // self.processes.remove(&pid).map(|(_, handle)| handle)
         self.processes.get(&pid).map(|entry| entry.clone())
@@ -292,36 +364,38 @@ impl ProcessManager {
             "[ProcessManager::remove] Removing process with PID: {}",
             pid
         );
-        if let Some(handle_arc) = self.processes.get(&pid) {
-            match handle_arc.try_lock() {
-                Ok(mut h) => {
-                    h.removed = true;
-                    let final_diagnostics = {
-                        let diag_lock = match h.diagnostics.try_lock() {
-                            Ok(lock) => lock.clone(),
-                            Err(_) => Vec::new(),
-                        };
-                        diag_lock
-                    };
-                    h.result.diagnostics = final_diagnostics.clone();
+        if let Err(e) = self.finish(pid) {
+            eprintln!("{}", e);
+        }
+    }
 
-                    if let Some(exit_status) = h.child.try_wait().ok().flatten() {
-                        h.result.exit_status = Some(exit_status);
-                    }
+    /// Mark the process removed and record its result, without printing.
+    /// `Ok(None)` if the PID is not managed.
+    pub fn finish(&self, pid: u32) -> anyhow::Result<Option<CargoProcessResult>> {
+        let Some(handle_arc) = self.processes.get(&pid).map(|entry| entry.clone()) else {
+            return Ok(None);
+        };
+        let mut h = handle_arc
+            .try_lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire lock for PID {}: {}", pid, e))?;
+        h.removed = true;
+        let final_diagnostics = match h.diagnostics.try_lock() {
+            Ok(lock) => lock.clone(),
+            Err(_) => Vec::new(),
+        };
+        h.result.diagnostics = final_diagnostics;
 
-                    h.result.end_time = Some(SystemTime::now());
-                    if let (Some(start), Some(end)) = (h.result.start_time, h.result.end_time) {
-                        h.result.elapsed_time = Some(end.duration_since(start).unwrap_or_default());
-                    }
-                    h.result.pid = pid;
-                    self.record_result(h.result.clone());
-                    drop(h);
-                }
-                Err(e) => {
-                    eprintln!("Failed to acquire lock for PID {}: {}", pid, e);
-                }
-            }
+        if let Some(exit_status) = h.child.try_wait().ok().flatten() {
+            h.result.exit_status = Some(exit_status);
+        }
+
+        h.result.end_time = Some(SystemTime::now());
+        if let (Some(start), Some(end)) = (h.result.start_time, h.result.end_time) {
+            h.result.elapsed_time = Some(end.duration_since(start).unwrap_or_default());
         }
+        h.result.pid = pid;
+        self.record_result(h.result.clone());
+        Ok(Some(h.result.clone()))
     }
 
     pub fn try_wait(&self, pid: u32) -> anyhow::Result<Option<ExitStatus>> {
@@ -1093,6 +1167,8 @@ impl ProcessManager {
                     println!("Process with PID {} not found in the process map.", pid);
                     let result = CargoProcessResult {
                         target_name: String::new(), // Placeholder, should be set properly in actual use
+                        profile: None,
+                        manifest_path: std::path::PathBuf::new(),
                         cmd: String::new(), // Placeholder, should be set properly in actual use
                         args: Vec::new(),   // Placeholder, should be set properly in actual use
                         pid,
@@ -1110,6 +1186,13 @@ impl ProcessManager {
                         runtime_output_size: 0, // Placeholder, should be set properly in actual use
                         is_filter: false,       // Placeholder, should be set properly in actual use
                         is_could_not_compile: false, // Placeholder, should be set properly in actual use
+                        resources: Default::default(),
+                        resource_violation: None,
+                        expectation: None,
+                        snapshot: None,
+                        timed_out: false,
+                        annotations: Vec::new(),
+                        plugin_failure: None,
                     };
                     self.record_result(result.clone());
                     anyhow::anyhow!("Process handle with PID {} not found", pid)
@@ -1285,22 +1368,83 @@ impl ProcessManager {
         Ok(handle.result)
     }
 
-    pub fn record_result(&self, result: CargoProcessResult) {
+    /// Store the result of a run. A pid can be recorded more than once (stopped,
+    /// then finished with its verdict); later recordings update the stored result
+    /// but keep what was sampled and what the plugins set, and the plugin hooks
+    /// and the history see each run only once.
+    pub fn record_result(&self, mut result: CargoProcessResult) {
+        if let Some(previous) = self.results.get(&result.pid).map(|r| r.clone()) {
+            result.target_name = previous.target_name;
+            result.profile = previous.profile;
+            result.resources = previous.resources;
+            result.resource_violation = previous.resource_violation;
+            self.results.insert(result.pid, result);
+            return;
+        }
+        if let Some((_, usage)) = self.sampling.remove(&result.pid) {
+            result.resources = usage;
+            result.resource_violation = self.limits.violation(&usage);
+            if let Some(violation) = &result.resource_violation {
+                crate::out_eprintln!("{}: {}", result.label(), violation);
+            }
+        }
+        #[cfg(feature = "uses_plugins")]
+        let result = crate::plugins::hooks::after_build(result);
+        #[cfg(feature = "uses_serde")]
+        {
+            crate::e_history::record(&result);
+            crate::e_editor::last_run::record(&result);
+        }
         self.results.insert(result.pid, result);
     }
 
-    pub fn generate_report(&self, create_gist: bool) {
-        let results: Vec<_> = self
+    /// Every recorded result, in start order.
+    pub fn results(&self) -> Vec<CargoProcessResult> {
+        let mut results: Vec<_> = self
             .results
             .iter()
             .map(|entry| entry.value().clone())
             .collect();
-        let report = crate::e_reports::generate_markdown_report(&results);
-        if let Err(e) = crate::e_reports::save_report_to_file(&report, "run_report.md") {
+        results.sort_by_key(|r| (r.start_time, r.pid));
+        results
+    }
+
+    pub fn generate_report(&self, create_gist: bool) {
+        let results = self.results();
+        let (format, path) = match crate::GLOBAL_CLI.get() {
+            Some(cli) => (cli.report_format, cli.report_path.clone()),
+            None => (crate::e_cli::ReportFormat::Markdown, None),
+        };
+        let path = path.unwrap_or_else(|| format.default_path().into());
+        #[allow(unused_mut)]
+        let mut report = match crate::e_reports::generate_report(&results, format) {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Failed to generate {} report: {}", format, e);
+                return;
+            }
+        };
+        #[cfg(feature = "uses_plugins")]
+        {
+            let sections = crate::plugins::hooks::on_exit(&results);
+            // Plugin sections are markdown; structured formats stay machine-readable.
+            if format == crate::e_cli::ReportFormat::Markdown {
+                for section in sections {
+                    report.push('\n');
+                    report.push_str(&section);
+                    report.push('\n');
+                }
+            }
+        }
+        if let Err(e) = crate::e_reports::save_report_to_file(&report, &path.to_string_lossy()) {
             eprintln!("Failed to save report: {}", e);
         }
         if create_gist {
-            crate::e_reports::create_gist(&report, "run_report.md").unwrap_or_else(|e| {
+            let file_name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| format.default_path().to_string());
+            crate::e_reports::create_gist(&report, &file_name).unwrap_or_else(|e| {
                 eprintln!("Failed to create Gist: {}", e);
             });
         }
@@ -1513,7 +1657,7 @@ impl ProcessManager {
     pub fn kill_by_pid(&self, pid: u32) -> anyhow::Result<bool> {
         // Check if the process is alive
         if !self.is_alive(pid) {
-            eprintln!("Process with PID {} is not running.", pid);
+            crate::out_eprintln!("Process with PID {} is not running.", pid);
             return Ok(false);
         }
 
@@ -1530,9 +1674,9 @@ impl ProcessManager {
             ];
             let mut killed = false;
             for (i, sig) in signals.iter().enumerate() {
-                eprintln!("Attempt {}: sending {:?} to PID {}", i + 1, sig, pid);
+                crate::out_eprintln!("Attempt {}: sending {:?} to PID {}", i + 1, sig, pid);
                 if let Err(e) = nix_kill(Pid::from_raw(pid as i32), *sig) {
-                    eprintln!("Failed to send {:?} to PID {}: {}", sig, pid, e);
+                    crate::out_eprintln!("Failed to send {:?} to PID {}: {}", sig, pid, e);
                 }
                 std::thread::sleep(std::time::Duration::from_millis(500));
                 if !self.is_alive(pid) {
@@ -1545,7 +1689,7 @@ impl ProcessManager {
 
         #[cfg(windows)]
         {
-            eprintln!("Attempting to kill PID {} on Windows", pid);
+            crate::out_eprintln!("Attempting to kill PID {} on Windows", pid);
             let output = std::process::Command::new("taskkill")
                 .args(["/F", "/T", "/PID", &pid.to_string()])
                 .output();
@@ -1556,7 +1700,7 @@ impl ProcessManager {
                         std::thread::sleep(std::time::Duration::from_millis(500));
                         Ok(!self.is_alive(pid))
                     } else {
-                        eprintln!(
+                        crate::out_eprintln!(
                             "taskkill failed for PID {}: {}",
                             pid,
                             String::from_utf8_lossy(&out.stderr)
@@ -1565,7 +1709,7 @@ impl ProcessManager {
                     }
                 }
                 Err(e) => {
-                    eprintln!("Failed to execute taskkill for PID {}: {}", pid, e);
+                    crate::out_eprintln!("Failed to execute taskkill for PID {}: {}", pid, e);
                     Ok(false)
                 }
             }
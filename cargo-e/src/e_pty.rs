@@ -0,0 +1,244 @@
+//! Running filtered (`-f`) targets under a pseudo-terminal on Unix.
+//!
+//! With `-f` a target's stdout is normally a pipe, so anything that needs a
+//! terminal (ratatui/crossterm examples) fails with `NotConnected`. When
+//! cargo-e itself runs in a terminal, the child instead gets the slave side of
+//! a pty as stdin, stdout and controlling terminal; stderr stays a pipe.
+//!
+//! [`Pty::start`] reads the master side. Cargo's JSON messages are only handed
+//! to the stdout reader, as before. Once the target starts writing, its bytes
+//! are also copied to our stdout untouched, so it renders as usual, and our
+//! terminal is put in raw mode so keystrokes reach it. The reader gets every
+//! line with ANSI escapes and carriage returns removed, so dispatchers,
+//! diagnostics and timing keep working.
+use std::fs::File;
+use std::io::{self, IsTerminal, Read, Write};
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+use nix::sys::termios::{self, OutputFlags, SetArg, Termios};
+
+pub use crate::e_fmt::strip_ansi;
+
+/// Whether a pty would help: both our stdin and stdout are terminals.
+pub fn should_use() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// An open pseudo-terminal pair, sized like our own terminal.
+pub struct Pty {
+    master: File,
+    slave: File,
+}
+
+impl Pty {
+    pub fn open() -> io::Result<Pty> {
+        let size = window_size(libc::STDOUT_FILENO);
+        let pair = nix::pty::openpty(size.as_ref(), None::<&Termios>)?;
+        Ok(Pty {
+            master: File::from(pair.master),
+            slave: File::from(pair.slave),
+        })
+    }
+
+    /// Make the slave `cmd`'s stdin, stdout and controlling terminal.
+    ///
+    /// The child starts a new session, so the pty's line discipline delivers
+    /// Ctrl+C to the target rather than to cargo-e.
+    pub fn attach(&self, cmd: &mut Command) -> io::Result<()> {
+        cmd.stdin(self.slave.try_clone()?)
+            .stdout(self.slave.try_clone()?);
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                if libc::ioctl(libc::STDIN_FILENO, libc::TIOCSCTTY as _, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+
+    /// Start copying the master side; call once the child has been spawned.
+    ///
+    /// The returned reader yields cleaned lines and reaches EOF when the child
+    /// closes the terminal. The spawning `Command` must have released its
+    /// copies of the slave, or that never happens.
+    pub fn start(self) -> ChannelReader {
+        let Pty { master, slave } = self;
+        drop(slave);
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || tee(master, tx));
+        ChannelReader {
+            rx,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+fn tee(mut master: File, tx: Sender<Vec<u8>>) {
+    let mut stdout = io::stdout();
+    let mut buf = [0u8; 4096];
+    let mut line: Vec<u8> = Vec::new();
+    let mut session: Option<Session> = None;
+    loop {
+        let n = match master.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            // EIO once the last process using the slave exits.
+            Err(_) => break,
+        };
+        let chunk = &buf[..n];
+        if session.is_some() {
+            let _ = stdout.write_all(chunk);
+            let _ = stdout.flush();
+        }
+        for (i, &b) in chunk.iter().enumerate() {
+            // Cargo writes whole JSON objects per line; anything else is the target.
+            let echo_from = if session.is_none() && line.is_empty() && b != b'{' {
+                Some(i)
+            } else {
+                None
+            };
+            line.push(b);
+            let echo_from = echo_from.or_else(|| {
+                (session.is_none() && b == b'\n' && is_build_finished(&line)).then_some(i + 1)
+            });
+            if let Some(from) = echo_from {
+                session = Some(Session::begin(&master));
+                let _ = stdout.write_all(&chunk[from..]);
+                let _ = stdout.flush();
+            }
+            if b == b'\n' {
+                if tx.send(clean_line(&line)).is_err() {
+                    return;
+                }
+                line.clear();
+            }
+        }
+    }
+    if !line.is_empty() {
+        let _ = tx.send(clean_line(&line));
+    }
+    // Dropping the session restores our terminal and stops input forwarding.
+    drop(session);
+}
+
+fn is_build_finished(line: &[u8]) -> bool {
+    let line = String::from_utf8_lossy(line);
+    line.contains(r#""reason":"build-finished""#)
+}
+
+/// `line` without ANSI escape sequences or carriage returns, ending in `\n`.
+fn clean_line(line: &[u8]) -> Vec<u8> {
+    let mut text = strip_ansi(&String::from_utf8_lossy(line));
+    text.retain(|c| c != '\r' && c != '\n');
+    text.push('\n');
+    text.into_bytes()
+}
+
+/// Our terminal while a target owns it: raw input forwarded to the pty, and
+/// window size changes passed on. Restored on drop.
+struct Session {
+    saved: Option<Termios>,
+    done: Arc<AtomicBool>,
+}
+
+impl Session {
+    fn begin(master: &File) -> Session {
+        let saved = termios::tcgetattr(io::stdin()).ok();
+        if let Some(saved) = &saved {
+            let mut raw = saved.clone();
+            termios::cfmakeraw(&mut raw);
+            // Keep `\n` -> `\r\n` so cargo-e's own messages stay readable.
+            raw.output_flags
+                .insert(OutputFlags::OPOST | OutputFlags::ONLCR);
+            let _ = termios::tcsetattr(io::stdin(), SetArg::TCSANOW, &raw);
+        }
+        let done = Arc::new(AtomicBool::new(false));
+        if let Ok(master) = master.try_clone() {
+            let done = Arc::clone(&done);
+            thread::spawn(move || forward_input(master, done));
+        }
+        Session { saved, done }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::Relaxed);
+        if let Some(saved) = &self.saved {
+            let _ = termios::tcsetattr(io::stdin(), SetArg::TCSANOW, saved);
+        }
+    }
+}
+
+/// Copy our stdin to the pty until `done`, polling so the thread can stop
+/// without consuming input meant for cargo-e afterwards.
+fn forward_input(mut master: File, done: Arc<AtomicBool>) {
+    use std::os::fd::AsRawFd;
+    let dims = |ws: &libc::winsize| (ws.ws_row, ws.ws_col);
+    let mut size = window_size(libc::STDOUT_FILENO).map(|ws| dims(&ws));
+    let mut buf = [0u8; 1024];
+    while !done.load(Ordering::Relaxed) {
+        if let Some(ws) = window_size(libc::STDOUT_FILENO) {
+            if Some(dims(&ws)) != size {
+                // The kernel sends SIGWINCH to the target.
+                unsafe { libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ as _, &ws) };
+                size = Some(dims(&ws));
+            }
+        }
+        let mut fds = [libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        if unsafe { libc::poll(fds.as_mut_ptr(), 1, 100) } <= 0 {
+            continue;
+        }
+        let n = unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len()) };
+        if n <= 0 || master.write_all(&buf[..n as usize]).is_err() {
+            break;
+        }
+    }
+}
+
+fn window_size(fd: libc::c_int) -> Option<libc::winsize> {
+    let mut ws: libc::winsize = unsafe { std::mem::zeroed() };
+    let ok = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut ws) } == 0;
+    (ok && ws.ws_col > 0 && ws.ws_row > 0).then_some(ws)
+}
+
+/// Lines from the pty as a byte stream for the existing stdout reader.
+pub struct ChannelReader {
+    rx: Receiver<Vec<u8>>,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.buf.len() {
+            match self.rx.recv() {
+                Ok(next) => {
+                    self.buf = next;
+                    self.pos = 0;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
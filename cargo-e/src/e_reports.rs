@@ -1,24 +1,35 @@
-use crate::e_cargocommand_ext::CargoProcessResult;
+use crate::e_cargocommand_ext::{CargoDiagnostic, CargoProcessResult};
+use crate::e_cli::ReportFormat;
 use comfy_table::{Cell, ContentArrangement, Row, Table};
 use git2::{Error, Repository};
 use std::fs::File;
 use std::io::{self, Write};
 use std::process::Command;
+use std::time::SystemTime;
 
-fn current_remote_and_short_sha() -> Result<(String, String, String), Error> {
+/// Short SHA of HEAD in the repository containing the current directory.
+pub(crate) fn current_short_sha() -> Result<String, Error> {
     let repo = Repository::discover(".")?;
+    short_head_sha(&repo)
+}
 
+fn short_head_sha(repo: &Repository) -> Result<String, Error> {
     // Get HEAD OID and shorten to 7 chars
     let oid = repo
         .head()?
         .target()
         .ok_or_else(|| Error::from_str("no HEAD target"))?;
-    let short = repo
+    Ok(repo
         .find_object(oid, None)?
         .short_id()?
         .as_str()
         .ok_or_else(|| Error::from_str("invalid short id"))?
-        .to_string();
+        .to_string())
+}
+
+fn current_remote_and_short_sha() -> Result<(String, String, String), Error> {
+    let repo = Repository::discover(".")?;
+    let short = short_head_sha(&repo)?;
 
     // Look up the "origin" remote URL
     let remote = repo
@@ -135,17 +146,17 @@ pub fn generate_comfy_report(results: &[CargoProcessResult]) -> String {
             .elapsed_time
             .map(|d| format!("{:.2?}", d))
             .unwrap_or_else(|| "-".to_string());
-        let exit_code = result.exit_status.map_or("-".to_string(), |s| {
-            s.code().map_or("-".to_string(), |c| c.to_string())
-        });
-        let success = result
-            .exit_status
-            .map_or("No", |s| if s.success() { "Yes" } else { "No" });
-
-        report.push_str(&format!("## {}. {}\n\n", cnt, result.target_name));
+        let exit_code = match result.exit_status {
+            Some(s) => s.code().map_or("-".to_string(), |c| c.to_string()),
+            None if result.timed_out => "stopped at timeout".to_string(),
+            None => "-".to_string(),
+        };
+        let success = if result_failed(result) { "No" } else { "Yes" };
+
+        report.push_str(&format!("## {}. {}\n\n", cnt, result.label()));
         report.push_str(&format!("{} {}\n", result.cmd, result.args.join(" ")));
         result_table.add_row(Row::from(vec![
-            Cell::new(result.target_name.clone()),
+            Cell::new(result.label()),
             // Cell::new(format!("{} {}", result.cmd, result.args.join(" "))),
         ]));
         result_table.add_row(Row::from(vec![
@@ -159,8 +170,43 @@ pub fn generate_comfy_report(results: &[CargoProcessResult]) -> String {
             Cell::new(exit_code),
         ]));
         result_table.add_row(Row::from(vec![Cell::new("Success"), Cell::new(success)]));
+        result_table.add_row(Row::from(vec![
+            Cell::new("CPU"),
+            Cell::new(result.resources.cpu_summary()),
+        ]));
+        result_table.add_row(Row::from(vec![
+            Cell::new("Memory (RSS)"),
+            Cell::new(result.resources.rss_summary()),
+        ]));
+        if let Some(violation) = &result.resource_violation {
+            result_table.add_row(Row::from(vec![
+                Cell::new("Resource Limit"),
+                Cell::new(violation),
+            ]));
+        }
+        if let Some(verdict) = &result.expectation {
+            result_table.add_row(Row::from(vec![
+                Cell::new("Expectations"),
+                Cell::new(verdict),
+            ]));
+        }
+        if let Some(snapshot) = &result.snapshot {
+            result_table.add_row(Row::from(vec![Cell::new("Snapshot"), Cell::new(snapshot)]));
+        }
+        if let Some(reason) = &result.plugin_failure {
+            result_table.add_row(Row::from(vec![
+                Cell::new("Failed by Plugin"),
+                Cell::new(reason),
+            ]));
+        }
+        for note in &result.annotations {
+            result_table.add_row(Row::from(vec![Cell::new("Note"), Cell::new(note)]));
+        }
         report.push_str(&result_table.to_string());
         report.push_str("\n\n");
+        if let Some(crate::e_snapshot::SnapshotOutcome::Mismatch { diff, .. }) = &result.snapshot {
+            report.push_str(&format!("```diff\n{}```\n\n", diff));
+        }
 
         // Diagnostics Table
         if !result.diagnostics.is_empty() {
@@ -315,7 +361,7 @@ pub fn generate_markdown_report(results: &[CargoProcessResult]) -> String {
             .map_or("No", |s| if s.success() { "Yes" } else { "No" });
 
         table.add_row(Row::from(vec![
-            Cell::new(format!("{}. {}", index + 1, result.target_name)),
+            Cell::new(format!("{}. {}", index + 1, result.label())),
             Cell::new(""),
         ]));
         table.add_row(Row::from(vec![
@@ -421,6 +467,267 @@ pub fn generate_markdown_report(results: &[CargoProcessResult]) -> String {
 //     report
 // }
 
+/// File, line and column of a diagnostic: its primary span if rustc reported one,
+/// otherwise parsed from the `file:line:col` lineref.
+fn diagnostic_location(diag: &CargoDiagnostic) -> Option<(String, usize, usize)> {
+    if let Some(span) = diag
+        .spans
+        .iter()
+        .find(|s| s.is_primary)
+        .or(diag.spans.first())
+    {
+        return Some((span.file_name.clone(), span.line_start, span.column_start));
+    }
+    let mut parts = diag.lineref.trim().rsplitn(3, ':');
+    let col = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    let file = parts.next()?.trim_start_matches("-->").trim().to_string();
+    Some((file, line, col))
+}
+
+fn time_string(t: Option<SystemTime>) -> Option<String> {
+    t.map(|t| chrono::DateTime::<chrono::Local>::from(t).to_rfc3339())
+}
+
+/// Whether a run counts as failed: a non-zero or missing exit status (a run `--run-all`
+/// stopped at its timeout counts as having run; a target with smoke-test expectations
+/// must pass them instead), a compile failure, an error diagnostic,
+/// going over `--max-rss`/`--max-cpu`, output that differs from its `--snapshot`, or an
+/// `after_build` plugin failing it.
+pub fn result_failed(result: &CargoProcessResult) -> bool {
+    let ran_ok = match &result.expectation {
+        Some(verdict) => verdict.passed,
+        None => result.timed_out || result.exit_status.is_some_and(|s| s.success()),
+    };
+    !ran_ok
+        || result.is_could_not_compile
+        || result.diagnostics.iter().any(|d| d.level == "error")
+        || result.resource_violation.is_some()
+        || result.snapshot.as_ref().is_some_and(|s| s.is_failure())
+        || result.plugin_failure.is_some()
+}
+
+/// Serializes every field of each result, including diagnostics with their locations.
+#[cfg(feature = "uses_serde")]
+pub fn generate_json_report(results: &[CargoProcessResult]) -> String {
+    use serde_json::json;
+    let secs = |d: Option<std::time::Duration>| d.map(|d| d.as_secs_f64());
+    let entries: Vec<serde_json::Value> = results
+        .iter()
+        .map(|r| {
+            #[cfg(unix)]
+            let signal = r
+                .exit_status
+                .and_then(|s| std::os::unix::process::ExitStatusExt::signal(&s));
+            #[cfg(not(unix))]
+            let signal: Option<i32> = None;
+            let diagnostics: Vec<serde_json::Value> = r
+                .diagnostics
+                .iter()
+                .map(|d| {
+                    let location = diagnostic_location(d);
+                    json!({
+                        "level": d.level,
+                        "code": d.error_code,
+                        "message": d.message,
+                        "file": location.as_ref().map(|l| &l.0),
+                        "line": location.as_ref().map(|l| l.1),
+                        "column": location.as_ref().map(|l| l.2),
+                        "lineref": d.lineref,
+                        "note": d.note,
+                        "help": d.help,
+                        "suggestion": d.suggestion,
+                    })
+                })
+                .collect();
+            json!({
+                "target_name": r.target_name,
+                "label": r.label(),
+                "profile": r.profile,
+                "cmd": r.cmd,
+                "args": r.args,
+                "pid": r.pid,
+                "success": !result_failed(r),
+                "exit_status": {
+                    "code": r.exit_status.and_then(|s| s.code()),
+                    "success": r.exit_status.map(|s| s.success()),
+                    "signal": signal,
+                },
+                "timed_out": r.timed_out,
+                "terminal_error": r.terminal_error.as_ref().map(|e| format!("{:?}", e)),
+                "start_time": time_string(r.start_time),
+                "build_finished_time": time_string(r.build_finished_time),
+                "end_time": time_string(r.end_time),
+                "build_elapsed_secs": secs(r.build_elapsed),
+                "runtime_elapsed_secs": secs(r.runtime_elapsed),
+                "elapsed_secs": secs(r.elapsed_time),
+                "build_output_size": r.build_output_size,
+                "runtime_output_size": r.runtime_output_size,
+                "is_filter": r.is_filter,
+                "is_could_not_compile": r.is_could_not_compile,
+                "stats": {
+                    "compiler_message_count": r.stats.compiler_message_count,
+                    "compiler_artifact_count": r.stats.compiler_artifact_count,
+                    "build_script_executed_count": r.stats.build_script_executed_count,
+                    "build_finished_count": r.stats.build_finished_count,
+                },
+                "resources": {
+                    "samples": r.resources.samples,
+                    "peak_cpu_percent": (!r.resources.is_empty()).then_some(r.resources.peak_cpu),
+                    "avg_cpu_percent": r.resources.avg_cpu(),
+                    "peak_rss_bytes": (!r.resources.is_empty()).then_some(r.resources.peak_rss),
+                    "avg_rss_bytes": r.resources.avg_rss(),
+                },
+                "resource_violation": r.resource_violation,
+                "expectation": r.expectation.as_ref().map(|v| json!({
+                    "passed": v.passed,
+                    "reason": v.reason,
+                })),
+                "snapshot": r.snapshot.as_ref().map(|s| json!({
+                    "status": s.status(),
+                    "path": s.path(),
+                    "diff": match s {
+                        crate::e_snapshot::SnapshotOutcome::Mismatch { diff, .. } => Some(diff),
+                        _ => None,
+                    },
+                })),
+                "plugin_failure": r.plugin_failure,
+                "annotations": r.annotations,
+                "diagnostics": diagnostics,
+            })
+        })
+        .collect();
+    let report = json!({
+        "cargo_e_version": env!("CARGO_PKG_VERSION"),
+        "generated_at": chrono::Local::now().to_rfc3339(),
+        "results": entries,
+    });
+    serde_json::to_string_pretty(&report).unwrap_or_default()
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters other than tab/newline are not allowed in XML 1.0.
+            c if c.is_control() && c != '\n' && c != '\t' && c != '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// One JUnit testcase per target; failures carry the first error diagnostic.
+pub fn generate_junit_report(results: &[CargoProcessResult]) -> String {
+    let failures = results.iter().filter(|r| result_failed(r)).count();
+    let total_time: f64 = results
+        .iter()
+        .filter_map(|r| r.elapsed_time)
+        .map(|d| d.as_secs_f64())
+        .sum();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"cargo-e\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        results.len(),
+        failures,
+        total_time
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"cargo-e\" tests=\"{}\" failures=\"{}\" errors=\"0\" time=\"{:.3}\" timestamp=\"{}\">\n",
+        results.len(),
+        failures,
+        total_time,
+        chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+    ));
+    for r in results {
+        let time = r.elapsed_time.map(|d| d.as_secs_f64()).unwrap_or(0.0);
+        xml.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"cargo-e.{}\" time=\"{:.3}\"",
+            xml_escape(&r.label()),
+            xml_escape(&r.cmd),
+            time
+        ));
+        if !result_failed(r) {
+            xml.push_str("/>\n");
+            continue;
+        }
+        xml.push_str(">\n");
+        let first_error = r.diagnostics.iter().find(|d| d.level == "error");
+        let (message, kind, body) = match first_error {
+            Some(d) => {
+                let location = diagnostic_location(d)
+                    .map(|(file, line, col)| format!("{}:{}:{}", file, line, col))
+                    .unwrap_or_else(|| d.lineref.clone());
+                (
+                    d.message.clone(),
+                    d.error_code.clone().unwrap_or_else(|| "error".to_string()),
+                    format!("{}\n{}", location, d.message),
+                )
+            }
+            None if r.resource_violation.is_some() => {
+                let message = r.resource_violation.clone().unwrap_or_default();
+                (message.clone(), "resources".to_string(), message)
+            }
+            None if r.expectation.as_ref().is_some_and(|v| !v.passed) => {
+                let message = r
+                    .expectation
+                    .as_ref()
+                    .map_or(String::new(), |v| v.reason.clone());
+                (message.clone(), "expectation".to_string(), message)
+            }
+            None if r.snapshot.as_ref().is_some_and(|s| s.is_failure()) => {
+                let snapshot = r.snapshot.as_ref().expect("checked above");
+                let body = match snapshot {
+                    crate::e_snapshot::SnapshotOutcome::Mismatch { diff, .. } => diff.clone(),
+                    other => other.to_string(),
+                };
+                (snapshot.to_string(), "snapshot".to_string(), body)
+            }
+            None if r.plugin_failure.is_some() => {
+                let message = r.plugin_failure.clone().unwrap_or_default();
+                (message.clone(), "plugin".to_string(), message)
+            }
+            None => {
+                let message = match r.exit_status.and_then(|s| s.code()) {
+                    Some(code) => format!("exited with code {}", code),
+                    None if r.exit_status.is_some() => "terminated by a signal".to_string(),
+                    None => "no exit status recorded".to_string(),
+                };
+                (message.clone(), "exit".to_string(), message)
+            }
+        };
+        xml.push_str(&format!(
+            "      <failure message=\"{}\" type=\"{}\">{}</failure>\n",
+            xml_escape(&message),
+            xml_escape(&kind),
+            xml_escape(&body)
+        ));
+        xml.push_str("    </testcase>\n");
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Renders `results` in `format`.
+pub fn generate_report(results: &[CargoProcessResult], format: ReportFormat) -> io::Result<String> {
+    match format {
+        ReportFormat::Markdown => Ok(generate_markdown_report(results)),
+        #[cfg(feature = "uses_serde")]
+        ReportFormat::Json => Ok(generate_json_report(results)),
+        #[cfg(not(feature = "uses_serde"))]
+        ReportFormat::Json => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "JSON reports need the uses_serde feature",
+        )),
+        ReportFormat::Junit => Ok(generate_junit_report(results)),
+    }
+}
+
 pub fn save_report_to_file(report: &str, file_path: &str) -> io::Result<()> {
     let mut file = File::create(file_path)?;
     file.write_all(report.as_bytes())?;
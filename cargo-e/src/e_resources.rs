@@ -0,0 +1,293 @@
+//! CPU and memory usage of running targets.
+//!
+//! The process manager samples every registered process each `--sample-interval`
+//! milliseconds. For `cargo run` only the target counts: cargo's own process and
+//! the compiler, build scripts and linker it runs are left out, and filtered runs
+//! are not sampled until cargo has finished building.
+//! The peak and average end up in [`CargoProcessResult::resources`], the run
+//! reports show them, and `--max-rss` / `--max-cpu` mark a run that went over
+//! as failed.
+//!
+//! [`CargoProcessResult::resources`]: crate::e_cargocommand_ext::CargoProcessResult::resources
+use std::collections::HashMap;
+
+/// CPU and resident memory seen across the samples of one run.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ResourceUsage {
+    pub samples: usize,
+    /// Highest CPU%, summed over the process tree; 100 is one full core.
+    pub peak_cpu: f32,
+    /// Highest resident set size of the process tree, in bytes.
+    pub peak_rss: u64,
+    cpu_total: f64,
+    rss_total: u128,
+}
+
+impl ResourceUsage {
+    pub fn record(&mut self, cpu: f32, rss: u64) {
+        self.samples += 1;
+        self.peak_cpu = self.peak_cpu.max(cpu);
+        self.peak_rss = self.peak_rss.max(rss);
+        self.cpu_total += cpu as f64;
+        self.rss_total += rss as u128;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples == 0
+    }
+
+    pub fn avg_cpu(&self) -> Option<f32> {
+        (!self.is_empty()).then(|| (self.cpu_total / self.samples as f64) as f32)
+    }
+
+    pub fn avg_rss(&self) -> Option<u64> {
+        (!self.is_empty()).then(|| (self.rss_total / self.samples as u128) as u64)
+    }
+
+    /// `peak 41.2% / avg 12.0%`, or `-` without samples.
+    pub fn cpu_summary(&self) -> String {
+        match self.avg_cpu() {
+            Some(avg) => format!("peak {:.1}% / avg {:.1}%", self.peak_cpu, avg),
+            None => "-".to_string(),
+        }
+    }
+
+    /// `peak 212.40 MB / avg 180.00 MB`, or `-` without samples.
+    pub fn rss_summary(&self) -> String {
+        match self.avg_rss() {
+            Some(avg) => format!(
+                "peak {} / avg {}",
+                format_bytes(self.peak_rss),
+                format_bytes(avg)
+            ),
+            None => "-".to_string(),
+        }
+    }
+}
+
+/// Limits from `--max-rss` and `--max-cpu`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ResourceLimits {
+    /// Peak resident memory, in bytes.
+    pub max_rss: Option<u64>,
+    /// Average CPU%.
+    pub max_cpu: Option<f32>,
+}
+
+impl ResourceLimits {
+    pub fn from_cli(cli: &crate::Cli) -> Self {
+        ResourceLimits {
+            max_rss: cli.max_rss,
+            max_cpu: cli.max_cpu,
+        }
+    }
+
+    /// Why `usage` fails these limits, if it does.
+    pub fn violation(&self, usage: &ResourceUsage) -> Option<String> {
+        if let Some(max) = self.max_rss.filter(|max| usage.peak_rss > *max) {
+            return Some(format!(
+                "peak RSS {} exceeds --max-rss {}",
+                format_bytes(usage.peak_rss),
+                format_bytes(max)
+            ));
+        }
+        match (self.max_cpu, usage.avg_cpu()) {
+            (Some(max), Some(avg)) if avg > max => Some(format!(
+                "average CPU {:.1}% exceeds --max-cpu {:.1}%",
+                avg, max
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a size such as `512M`, `1.5G`, `800k`, `2GiB` or `1048576` into bytes.
+/// Suffixes are binary (`M` is 1024 * 1024).
+pub fn parse_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size {:?}; expected e.g. 512M or 2G", text))?;
+    let shift = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 0,
+        "k" | "kb" | "kib" => 10,
+        "m" | "mb" | "mib" => 20,
+        "g" | "gb" | "gib" => 30,
+        "t" | "tb" | "tib" => 40,
+        _ => return Err(format!("unknown size unit {:?} in {:?}", unit, text)),
+    };
+    Ok((number * (1u64 << shift) as f64) as u64)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    crate::e_fmt::format_bytes(bytes as usize)
+}
+
+/// One process in a snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessSample {
+    pub pid: u32,
+    pub parent: Option<u32>,
+    pub name: String,
+    /// CPU% since the previous snapshot.
+    pub cpu: f32,
+    /// Resident bytes.
+    pub rss: u64,
+}
+
+/// Whether `name` is a program cargo runs while building: the compiler, a build
+/// script, a linker or a compiler wrapper.
+pub fn is_build_tool(name: &str) -> bool {
+    let name = name.strip_suffix(".exe").unwrap_or(name);
+    name.starts_with("rustc")
+        || name.starts_with("build-script-")
+        || matches!(
+            name,
+            "cargo"
+                | "cc"
+                | "c++"
+                | "gcc"
+                | "g++"
+                | "clang"
+                | "clang++"
+                | "ld"
+                | "ld.lld"
+                | "lld"
+                | "lld-link"
+                | "link"
+                | "collect2"
+                | "mold"
+                | "sccache"
+        )
+}
+
+fn children_of(processes: &[ProcessSample]) -> HashMap<u32, Vec<&ProcessSample>> {
+    let mut children: HashMap<u32, Vec<&ProcessSample>> = HashMap::new();
+    for p in processes {
+        if let Some(parent) = p.parent {
+            children.entry(parent).or_default().push(p);
+        }
+    }
+    children
+}
+
+fn sum_subtrees<'a>(
+    mut stack: Vec<&'a ProcessSample>,
+    children: &HashMap<u32, Vec<&'a ProcessSample>>,
+) -> (f32, u64) {
+    let (mut cpu, mut rss) = (0.0, 0);
+    while let Some(p) = stack.pop() {
+        cpu += p.cpu;
+        rss += p.rss;
+        if let Some(kids) = children.get(&p.pid) {
+            stack.extend(kids.iter().copied());
+        }
+    }
+    (cpu, rss)
+}
+
+/// CPU% and resident bytes of `root` and all of its descendants in `processes`.
+pub fn tree_usage(root: u32, processes: &[ProcessSample]) -> Option<(f32, u64)> {
+    let root = processes.iter().find(|p| p.pid == root)?;
+    Some(sum_subtrees(vec![root], &children_of(processes)))
+}
+
+/// CPU% and resident bytes of the target `cargo` (process `root`) is running: its
+/// descendants, without cargo itself or anything it runs to build. `None` while
+/// nothing but the build is running.
+pub fn target_usage(root: u32, processes: &[ProcessSample]) -> Option<(f32, u64)> {
+    let children = children_of(processes);
+    let targets: Vec<&ProcessSample> = children
+        .get(&root)?
+        .iter()
+        .copied()
+        .filter(|p| !is_build_tool(&p.name))
+        .collect();
+    if targets.is_empty() {
+        return None;
+    }
+    Some(sum_subtrees(targets, &children))
+}
+
+/// How many samples pass between full scans of the process table for new children.
+const DISCOVER_EVERY: usize = 10;
+
+/// A `sysinfo` view of the sampled processes, refreshed for CPU and memory only.
+pub struct Sampler {
+    system: sysinfo::System,
+    /// Roots and the descendants found for them at the last full scan.
+    tracked: Vec<sysinfo::Pid>,
+    ticks: usize,
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler {
+    pub fn new() -> Self {
+        Sampler {
+            system: sysinfo::System::new(),
+            tracked: Vec::new(),
+            ticks: 0,
+        }
+    }
+
+    /// Refresh `roots` and their descendants and return a snapshot of them. CPU% is
+    /// measured since the previous call, so a process's first snapshot reports 0.
+    ///
+    /// Only the tracked processes are refreshed; the whole process table is read
+    /// every few calls, or when a root is new, to find the children they started.
+    pub fn snapshot(&mut self, roots: &[u32]) -> Vec<ProcessSample> {
+        use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate};
+        let roots: Vec<Pid> = roots.iter().map(|&pid| Pid::from_u32(pid)).collect();
+        let new_root = roots.iter().any(|pid| !self.tracked.contains(pid));
+        if new_root || self.ticks % DISCOVER_EVERY == 0 {
+            self.system.refresh_processes_specifics(
+                ProcessesToUpdate::All,
+                true,
+                ProcessRefreshKind::nothing(),
+            );
+            self.tracked = roots.clone();
+            let mut i = 0;
+            while i < self.tracked.len() {
+                let parent = self.tracked[i];
+                let kids: Vec<Pid> = self
+                    .system
+                    .processes()
+                    .values()
+                    .filter(|p| p.parent() == Some(parent) && p.thread_kind().is_none())
+                    .map(|p| p.pid())
+                    .filter(|pid| !self.tracked.contains(pid))
+                    .collect();
+                self.tracked.extend(kids);
+                i += 1;
+            }
+        }
+        self.ticks += 1;
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&self.tracked),
+            true,
+            ProcessRefreshKind::nothing().with_cpu().with_memory(),
+        );
+        self.tracked
+            .iter()
+            .filter_map(|pid| self.system.process(*pid))
+            // Linux lists threads as processes too; they share their process's memory.
+            .filter(|p| p.thread_kind().is_none())
+            .map(|p| ProcessSample {
+                pid: p.pid().as_u32(),
+                parent: p.parent().map(|pp| pp.as_u32()),
+                name: p.name().to_string_lossy().into_owned(),
+                cpu: p.cpu_usage(),
+                rss: p.memory(),
+            })
+            .collect()
+    }
+}
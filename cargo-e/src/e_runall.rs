@@ -1,4 +1,3 @@
-use crate::e_cli::RunAll;
 use crate::e_command_builder::CargoCommandBuilder;
 use crate::e_processmanager::ProcessManager;
 use crate::e_target::{CargoTarget, TargetKind};
@@ -49,10 +48,16 @@ pub fn send_ctrl_c(child: &mut std::process::Child) -> Result<()> {
 
 /// Runs all filtered targets with prebuild, child process management, and timeout‐based termination.
 ///
-/// If the CLI flag `pre_build` is enabled, this function first prebuilds all targets by invoking
-/// `cargo build` with the appropriate flags (using `--example` or `--bin` and, for extended targets,
-/// the `--manifest-path` flag). Then it spawns a child process for each target using `cargo run`,
-/// waits for the duration specified by `cli.wait`, kills the child process, and then checks its output.
+/// If the CLI flag `pre_build` is enabled, this function first builds all examples and binaries
+/// with one `cargo build` per manifest (see [`crate::e_prebuild`]) and runs the executables it
+/// produced directly; any other target is started with `cargo run`. Each run lasts for the
+/// duration specified by `cli.wait`, after which the child process is killed and its output checked.
+///
+/// A target that declares smoke-test expectations (see [`crate::e_expect`]) is stopped as soon
+/// as it has passed or failed them, and the verdict is recorded in its result.
+///
+/// With `--snapshot`, each target's runtime stdout is recorded to or checked against its
+/// snapshot file (see [`crate::e_snapshot`]).
 ///
 /// # Parameters
 ///
@@ -70,33 +75,68 @@ pub fn run_all_examples(
     // Adjust RUSTFLAGS if --quiet was provided.
     set_rustflags_if_quiet(cli.quiet);
 
-    // Prebuild targets if requested.
-    if cli.pre_build {
-        crate::e_prebuild::prebuild_examples(filtered_targets)
-            .context("Prebuild of targets failed")?;
+    if cli.snapshot.is_some() {
+        crate::e_snapshot::Redactor::new(&cli.snapshot_redact)
+            .context("Invalid --snapshot-redact pattern")?;
     }
 
+    // A caller drawing its own screen (the TUI) collects all output in a sink;
+    // every run thread and its capture threads write there too.
+    let sink = crate::e_eventdispatcher::current_output_sink();
+
     let mut targets = filtered_targets.to_vec();
+    targets.retain(|t| {
+        let skip = crate::e_config::target_override(&t.name).is_some_and(|ov| ov.skip);
+        if skip {
+            crate::out_println!("Skipping {} (skip = true in config)", t.name);
+        }
+        !skip
+    });
     targets.sort_by(|a, b| a.display_name.cmp(&b.display_name));
 
+    // Build everything once up front if requested; the runs then start the executables.
+    let prebuilt = if cli.pre_build {
+        Some(Arc::new(
+            crate::e_prebuild::prebuild_targets(&targets, cli)
+                .context("Prebuild of targets failed")?,
+        ))
+    } else {
+        None
+    };
+
+    // Each target runs once per configured profile, or once without one.
+    let mut runs: Vec<(CargoTarget, Option<crate::e_config::RunProfile>)> = Vec::new();
+    for target in targets {
+        let profiles = crate::e_config::run_profiles(&target.name);
+        if profiles.is_empty() {
+            runs.push((target, None));
+        } else {
+            runs.extend(profiles.into_iter().map(|p| (target.clone(), Some(p))));
+        }
+    }
+
     let user_requested_quit = Arc::new(AtomicBool::new(false));
 
     let chunk_size = cli.run_at_a_time;
     let mut idx = 0;
-    while idx < targets.len() {
-        let chunk = &targets[idx..std::cmp::min(idx + chunk_size, targets.len())];
+    while idx < runs.len() && !manager.stop_requested() {
+        let chunk = &runs[idx..std::cmp::min(idx + chunk_size, runs.len())];
         let mut handles = vec![];
 
-        for (chunk_idx, target) in chunk.iter().enumerate() {
+        for (chunk_idx, (target, profile)) in chunk.iter().enumerate() {
             let manager = Arc::clone(&manager);
             let cli = cli.clone();
             let target = target.clone();
-            let targets_len = targets.len();
+            let profile = profile.clone();
+            let targets_len = runs.len();
             let idx = idx + chunk_idx;
             let user_requested_quit_thread = Arc::clone(&user_requested_quit);
+            let sink = sink.clone();
+            let prebuilt = prebuilt.clone();
 
             // Spawn a thread for each target in the chunk
             let handle = std::thread::spawn(move || {
+                crate::e_eventdispatcher::set_output_sink(sink.clone());
                 // --- Begin: original per-target logic ---
                 let current_bin = env!("CARGO_PKG_NAME");
                 // Skip running our own binary.
@@ -104,27 +144,73 @@ pub fn run_all_examples(
                     return Ok(()) as Result<()>;
                 }
 
+                let artifact = prebuilt
+                    .as_deref()
+                    .and_then(|p| p.artifact(&target))
+                    .map(std::path::Path::to_path_buf);
+
+                // Smoke-test expectations and snapshots work on the captured output,
+                // so those runs are filtered.
+                let quiet = cli.quiet || cli.json_all_targets;
+                // A prebuilt executable prints no cargo preamble to wait for.
+                let monitor = Some(crate::e_expect::declared(&target))
+                    .filter(|expect| !expect.is_empty())
+                    .map(|expect| {
+                        Arc::new(crate::e_expect::Monitor::new(
+                            expect,
+                            quiet || artifact.is_some(),
+                        ))
+                    });
+                let capture = cli
+                    .snapshot
+                    .map(|_| Arc::new(crate::e_snapshot::Capture::default()));
+                let filter = cli.filter
+                    || capture.is_some()
+                    || monitor
+                        .as_ref()
+                        .is_some_and(|m| m.expectations().needs_output());
+
                 let manifest_path = PathBuf::from(target.manifest_path.clone());
-                let builder = CargoCommandBuilder::new(
+                let mut builder = CargoCommandBuilder::new(
                     &target.name,
                     &manifest_path,
                     &cli.subcommand,
-                    cli.filter,
+                    filter,
                     cli.cached,
                     cli.default_binary_is_runner,
-                    cli.quiet || cli.json_all_targets,
+                    quiet,
                     cli.detached,
                     cli.cwd_wsr,
                 )
                 .with_target(&target)
                 .with_cli(&cli)
                 .with_extra_args(&cli.extra);
+                if let Some(profile) = &profile {
+                    builder = builder.with_profile(profile);
+                }
+                if let (Some(exe), Some(prebuilt)) = (&artifact, &prebuilt) {
+                    builder = builder.with_artifact(exe, prebuilt.diagnostics(&target));
+                }
+                if let Some(sink) = &sink {
+                    builder = builder.with_output_sink(sink.clone());
+                }
+                if let Some(monitor) = &monitor {
+                    builder = builder.with_expectations(Arc::clone(monitor));
+                }
+                if let Some(capture) = &capture {
+                    // A snapshot is the plain output, not what a terminal would be sent.
+                    builder.use_pty = false;
+                    builder = builder.with_snapshot(Arc::clone(capture));
+                }
 
                 builder.print_command();
 
-                let maybe_backup =
+                let maybe_backup = if artifact.is_some() {
+                    None
+                } else {
                     crate::e_manifest::maybe_patch_manifest_for_run(&target.manifest_path)
-                        .context("Failed to patch manifest for run")?;
+                        .context("Failed to patch manifest for run")?
+                };
 
                 // let system = Arc::new(Mutex::new(System::new_all()));
                 // std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
@@ -159,7 +245,7 @@ pub fn run_all_examples(
                         if start_guard.is_none() {
                             *start_guard = Some(Instant::now());
                         }
-                        if !cli.no_status_lines {
+                        if !cli.no_status_lines && sink.is_none() {
                             let status_display = ProcessManager::format_process_status(
                                 pid,
                                 runtime_start,
@@ -172,11 +258,7 @@ pub fn run_all_examples(
                     }
                 })?;
 
-                let timeout = match cli.run_all {
-                    RunAll::Timeout(secs) => Duration::from_secs(secs),
-                    RunAll::Forever => Duration::from_secs(u64::MAX),
-                    RunAll::NotSpecified => Duration::from_secs(cli.wait),
-                };
+                let timeout = crate::e_config::run_all_timeout(&target.name, &cli);
 
                 // Use an Arc<Mutex<Option<Instant>>> so it can be set in the run callback and accessed in the main loop.
                 let start_for_callback = Arc::clone(&start);
@@ -214,16 +296,29 @@ pub fn run_all_examples(
                 //     }
                 // });
 
+                // Set when the target exits by itself rather than being stopped.
+                let mut exited = None;
+                let mut timed_out = false;
                 // Main thread continues to monitor the process
                 loop {
                     if manager.is_alive(pid) {
                         std::thread::sleep(Duration::from_millis(500));
+                        if manager.stop_requested() {
+                            crate::out_println!("Stop requested. Killing child process {}.", pid);
+                            manager.kill_by_pid(pid).ok();
+                            break;
+                        }
                         match manager.try_wait(pid) {
                             Ok(Some(status)) => {
-                                println!("Process {} finished naturally. {:?}", pid, status);
+                                crate::out_println!(
+                                    "Process {} finished naturally. {:?}",
+                                    pid,
+                                    status
+                                );
+                                exited = Some(status);
                                 let hold = cli.detached_hold.unwrap_or(0);
                                 if cli.detached_hold.is_some() && hold > 0 {
-                                    println!("holding for the duration (detached_hold enabled). Sleeping for {} seconds...", hold);
+                                    crate::out_println!("holding for the duration (detached_hold enabled). Sleeping for {} seconds...", hold);
                                     std::thread::sleep(std::time::Duration::from_secs(hold as u64));
                                 }
                                 // manager.e_window_kill(pid);
@@ -232,21 +327,33 @@ pub fn run_all_examples(
                             }
                             _ => {
                                 // Process is still running.
+                                if let Some(reason) = monitor.as_ref().and_then(|m| m.stop_reason())
+                                {
+                                    crate::out_println!(
+                                        "\n{}: {}. Stopping child process {}.",
+                                        target.name,
+                                        reason,
+                                        pid
+                                    );
+                                    manager.kill_by_pid(pid).ok();
+                                    break;
+                                }
                                 // We can check for timeout here as well.
                                 if let Ok(start_guard) = start_for_callback.lock() {
                                     if let Some(start_time) = *start_guard {
                                         if start_time.elapsed() >= timeout {
-                                            println!(
+                                            crate::out_println!(
                                             "\nTimeout reached for target {}. Killing child process {}.",
                                             target.name,pid);
                                             let hold = cli.detached_hold.unwrap_or(0);
                                             if cli.detached_hold.is_some() && hold > 0 {
-                                                println!("holding for the duration (detached_hold enabled). Sleeping for {} seconds...", hold);
+                                                crate::out_println!("holding for the duration (detached_hold enabled). Sleeping for {} seconds...", hold);
                                                 std::thread::sleep(std::time::Duration::from_secs(
                                                     hold as u64,
                                                 ));
                                             }
                                             manager.kill_by_pid(pid).ok();
+                                            timed_out = true;
                                             // manager.remove(pid);
                                             // user_requested_kill_thread.store(true, Ordering::SeqCst);
                                             // pids_to_kill_thread.lock().push(pid);
@@ -258,19 +365,21 @@ pub fn run_all_examples(
                             }
                         }
                         if manager.has_signalled() > 0 {
-                            println!("Detected Ctrl+C. {}", manager.has_signalled());
+                            crate::out_println!("Detected Ctrl+C. {}", manager.has_signalled());
                             manager.remove(pid); // Clean up the process handle
 
                             if manager.has_signalled() > 1 {
                                 if let Some(dur) = manager.time_between_signals() {
                                     if dur < Duration::from_millis(350) {
-                                        println!("User requested quit two times quickly (<350ms).");
+                                        crate::out_println!(
+                                            "User requested quit two times quickly (<350ms)."
+                                        );
                                         user_requested_quit_thread.store(true, Ordering::SeqCst);
                                         break;
                                     }
                                 }
                             }
-                            println!("Dectected Ctrl+C, coninuing to next target.");
+                            crate::out_println!("Dectected Ctrl+C, coninuing to next target.");
                             manager.reset_signalled();
                             break;
                         }
@@ -352,6 +461,60 @@ pub fn run_all_examples(
                 // Wait for the timeout thread to finish
                 // let _ = timeout_thread.join();
 
+                if timed_out {
+                    if let Some(handle) = manager.get(pid) {
+                        if let Ok(mut handle) = handle.lock() {
+                            handle.result.timed_out = true;
+                        }
+                    }
+                }
+
+                if let Some(monitor) = &monitor {
+                    let verdict = monitor.verdict(exited);
+                    crate::out_println!("{}: {}", target.name, verdict);
+                    if let Some(handle) = manager.get(pid) {
+                        if let Ok(mut handle) = handle.lock() {
+                            handle.result.expectation = Some(verdict);
+                        }
+                    }
+                }
+
+                if let (Some(mode), Some(capture)) = (cli.snapshot, &capture) {
+                    // Give the stdout thread a moment to take the target's last lines.
+                    let deadline = Instant::now() + Duration::from_secs(1);
+                    while Instant::now() < deadline
+                        && manager.get(pid).is_some_and(|handle| {
+                            handle
+                                .lock()
+                                .is_ok_and(|handle| !handle.stdout_handle.is_finished())
+                        })
+                    {
+                        std::thread::sleep(Duration::from_millis(50));
+                    }
+                    let label = match &profile {
+                        Some(profile) => format!("{}@{}", target.name, profile.name),
+                        None => target.name.clone(),
+                    };
+                    let outcome = crate::e_snapshot::finish_run(
+                        mode,
+                        &manifest_path,
+                        target.kind,
+                        &label,
+                        &cli.snapshot_redact,
+                        capture,
+                    );
+                    if let Some(handle) = manager.get(pid) {
+                        if let Ok(mut handle) = handle.lock() {
+                            handle.result.snapshot = outcome;
+                        }
+                    }
+                }
+
+                // Record how it went for the report and for callers watching results.
+                if let Err(e) = manager.finish(pid) {
+                    crate::out_eprintln!("{}", e);
+                }
+
                 if let Some(original) = maybe_backup {
                     fs::write(&target.manifest_path, original)
                         .context("Failed to restore patched manifest")?;
@@ -211,7 +211,9 @@ pub fn register_ctrlc_handler(process_manager: Arc<ProcessManager>) -> Result<()
 pub async fn open_ai_summarize_for_target(target: &CargoTarget) {
     // Extract the origin path from the target (e.g. the manifest path).
     let origin_path = match &target.origin {
-        Some(TargetOrigin::SingleFile(path)) | Some(TargetOrigin::DefaultBinary(path)) => path,
+        Some(TargetOrigin::SingleFile(path))
+        | Some(TargetOrigin::DefaultBinary(path))
+        | Some(TargetOrigin::CargoMetadata { src_path: path, .. }) => path,
         _ => return,
     };
 
@@ -334,6 +336,16 @@ pub fn run_example(
     manager: Arc<ProcessManager>,
     cli: &crate::Cli,
     target: &crate::e_target::CargoTarget,
+) -> anyhow::Result<Option<std::process::ExitStatus>> {
+    run_example_with_profile(manager, cli, target, None)
+}
+
+/// Runs the given target with an optional run profile (`target@profile`).
+pub fn run_example_with_profile(
+    manager: Arc<ProcessManager>,
+    cli: &crate::Cli,
+    target: &crate::e_target::CargoTarget,
+    profile: Option<&crate::e_config::RunProfile>,
 ) -> anyhow::Result<Option<std::process::ExitStatus>> {
     crate::e_runall::set_rustflags_if_quiet(cli.quiet);
     // Retrieve the current package name at compile time.
@@ -461,6 +473,9 @@ pub fn run_example(
     .with_target(target)
     .with_required_features(&target.manifest_path, target)
     .with_cli(cli);
+    if let Some(profile) = profile {
+        builder = builder.with_profile(profile);
+    }
 
     // Build the command.
     let mut cmd = builder.clone().build_command();
@@ -671,7 +686,7 @@ pub fn run_example(
                 &diag.lineref
             };
             // Resolve filename to full path
-            let (filename, goto) = if let Some((file, line, col)) = diag
+            let open = if let Some((file, line, col)) = diag
                 .lineref
                 .split_once(':')
                 .and_then(|(f, rest)| rest.split_once(':').and_then(|(l, c)| Some((f, l, c))))
@@ -697,21 +712,22 @@ pub fn run_example(
                 });
                 let stripped_file = full_path.to_string_lossy().replace("\\\\?\\", "");
 
-                (stripped_file.to_string(), format!("{}:{}", line, col))
+                crate::e_editor::Editor::configured().anchor_command(
+                    Path::new(&stripped_file),
+                    line.parse().unwrap_or(1),
+                    col.parse().unwrap_or(1),
+                )
             } else {
-                ("".to_string(), "".to_string())
+                "".to_string()
             };
-            let code_path = which("code").unwrap_or_else(|_| "code".to_string().into());
             format!(
-                "{}: {}\nanchor:{}: {}\\n {}|\"{}\" --goto \"{}:{}\"\n",
+                "{}: {}\nanchor:{}: {}\\n {}|{}\n",
                 index,
                 diag.message.trim(),
                 index,
                 diag.message.trim(),
                 lineref,
-                code_path.display(),
-                filename,
-                goto,
+                open,
             )
         })
         .collect();
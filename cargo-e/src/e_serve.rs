@@ -0,0 +1,491 @@
+//! `cargo e --serve`: a long-lived JSON-RPC 2.0 server for editor extensions.
+//!
+//! Messages are JSON objects, one per line, over stdio (`--serve`) or a Unix
+//! socket (`--serve /path/to.sock`, one connection per client). The server
+//! wraps an [`ExtContext`]: discovery runs once and is reused until a client
+//! asks for `listTargets` with `"refresh": true`.
+//!
+//! Methods:
+//! - `listTargets {refresh?}` returns the targets, as `--json-all-targets` prints them.
+//! - `run {name, kind?}` starts a target with its output captured and returns `{pid}`.
+//! - `kill {pid}` returns whether a running process was killed.
+//! - `subscribeDiagnostics` / `subscribeOutput` turn on those notifications for the caller.
+//! - `openLocation {file, line?, col?}` or `{target}` opens the `--editor` editor.
+//! - `shutdown` kills running targets and stops the server.
+//!
+//! Every client gets `process/started {pid, target, kind}` and `process/exited
+//! {pid, target, success, exitCode, errors, warnings, elapsedSecs}`. Subscribed
+//! clients also get `diagnostic {pid, label, level, message, file, line, col,
+//! ...}` as rustc reports each one, and `output {pid, lines}` about ten times a
+//! second while a target prints.
+use crate::e_cargocommand_ext::CargoDiagnostic;
+use crate::e_command_builder::CargoCommandBuilder;
+use crate::e_editor::{parse_lineref, Editor};
+use crate::e_eventdispatcher::OutputSink;
+use crate::e_findmain::{candidate_main_file, find_main_line};
+use crate::e_processmanager::ProcessManager;
+use crate::e_target::{CargoTarget, TargetKind};
+use crate::ext::ExtContext;
+use crate::prelude::*;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+/// How often running targets are checked for output, diagnostics and exit.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A connected client and the notifications it asked for.
+pub struct Client {
+    out: Mutex<Box<dyn Write + Send>>,
+    diagnostics: AtomicBool,
+    output: AtomicBool,
+}
+
+impl Client {
+    pub fn new(out: Box<dyn Write + Send>) -> Self {
+        Client {
+            out: Mutex::new(out),
+            diagnostics: AtomicBool::new(false),
+            output: AtomicBool::new(false),
+        }
+    }
+
+    /// Write one message; a client that went away is dropped on its next read.
+    pub fn send(&self, message: &Value) {
+        let mut out = self.out.lock().unwrap();
+        let _ = writeln!(out, "{}", message).and_then(|_| out.flush());
+    }
+}
+
+/// Which clients a notification goes to.
+#[derive(Clone, Copy)]
+enum Audience {
+    All,
+    Diagnostics,
+    Output,
+}
+
+/// The connected clients, shared with the threads watching running targets.
+#[derive(Clone, Default)]
+struct Clients(Arc<Mutex<Vec<Arc<Client>>>>);
+
+impl Clients {
+    fn add(&self, client: Arc<Client>) {
+        self.0.lock().unwrap().push(client);
+    }
+
+    fn remove(&self, client: &Arc<Client>) {
+        self.0.lock().unwrap().retain(|c| !Arc::ptr_eq(c, client));
+    }
+
+    fn notify(&self, audience: Audience, method: &str, params: Value) {
+        let message = json!({"jsonrpc": "2.0", "method": method, "params": params});
+        for client in self.0.lock().unwrap().iter() {
+            let wanted = match audience {
+                Audience::All => true,
+                Audience::Diagnostics => client.diagnostics.load(Ordering::SeqCst),
+                Audience::Output => client.output.load(Ordering::SeqCst),
+            };
+            if wanted {
+                client.send(&message);
+            }
+        }
+    }
+}
+
+/// What the connection threads hand to the thread that owns the server.
+enum Incoming {
+    Message(Arc<Client>, String),
+    Closed(Arc<Client>),
+}
+
+/// Request handling over an [`ExtContext`]. It is driven from one thread, as
+/// plugins loaded by the context are not thread-safe.
+pub struct Server {
+    ctx: ExtContext,
+    targets: Option<Vec<CargoTarget>>,
+    clients: Clients,
+    shutdown: bool,
+}
+
+impl Server {
+    pub fn new(ctx: ExtContext) -> Self {
+        Server {
+            ctx,
+            targets: None,
+            clients: Clients::default(),
+            shutdown: false,
+        }
+    }
+
+    /// Handle one line from `client`; returns the response to send, if any.
+    pub fn handle_line(&mut self, client: &Arc<Client>, line: &str) -> Option<Value> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, e.to_string())),
+        };
+        let id = request.get("id").cloned();
+        let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+        let outcome = self.call(client, method, &params);
+        let id = id?;
+        Some(match outcome {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err((code, message)) => error_response(id, code, message),
+        })
+    }
+
+    fn call(
+        &mut self,
+        client: &Arc<Client>,
+        method: &str,
+        params: &Value,
+    ) -> Result<Value, (i64, String)> {
+        match method {
+            "listTargets" => {
+                let refresh = params["refresh"].as_bool().unwrap_or(false);
+                Ok(json!(self.targets(refresh).map_err(server_error)?))
+            }
+            "run" => self.run(params),
+            "kill" => {
+                let pid = params["pid"].as_u64().ok_or_else(|| invalid("pid"))? as u32;
+                Ok(json!(kill(&self.ctx.manager, pid)))
+            }
+            "subscribeDiagnostics" => {
+                client.diagnostics.store(true, Ordering::SeqCst);
+                Ok(json!(true))
+            }
+            "subscribeOutput" => {
+                client.output.store(true, Ordering::SeqCst);
+                Ok(json!(true))
+            }
+            "openLocation" => self.open_location(params),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method {:?}", method))),
+        }
+    }
+
+    /// The discovered targets, collected on first use or when `refresh` is set.
+    fn targets(&mut self, refresh: bool) -> anyhow::Result<&[CargoTarget]> {
+        if refresh || self.targets.is_none() {
+            self.targets = Some(self.ctx.collect_targets()?);
+        }
+        Ok(self.targets.as_deref().unwrap_or_default())
+    }
+
+    fn find_target(&mut self, params: &Value) -> Result<CargoTarget, (i64, String)> {
+        let name = params["name"]
+            .as_str()
+            .or_else(|| params["target"].as_str())
+            .ok_or_else(|| invalid("name"))?;
+        let kind: Option<TargetKind> = match params.get("kind") {
+            Some(kind) if !kind.is_null() => {
+                Some(serde_json::from_value(kind.clone()).map_err(|_| invalid("kind"))?)
+            }
+            _ => None,
+        };
+        self.targets(false)
+            .map_err(server_error)?
+            .iter()
+            .find(|t| t.name == name && kind.is_none_or(|k| t.kind == k))
+            .cloned()
+            .ok_or_else(|| (INVALID_PARAMS, format!("no target named {:?}", name)))
+    }
+
+    fn run(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let target = self.find_target(params)?;
+        if target.kind == TargetKind::Plugin {
+            return Err((
+                SERVER_ERROR,
+                "plugin targets cannot be run by --serve".into(),
+            ));
+        }
+        let manager = self.ctx.manager.clone();
+        let sink = OutputSink::new();
+        let builder = CargoCommandBuilder::new(
+            &target.name,
+            &target.manifest_path,
+            &self.ctx.cli.subcommand,
+            true,
+            self.ctx.cli.cached,
+            self.ctx.cli.default_binary_is_runner,
+            true,
+            false,
+            self.ctx.cli.cwd_wsr,
+        )
+        .with_target(&target)
+        .with_cli(&self.ctx.cli)
+        .with_output_sink(sink.clone());
+        let pid = Arc::new(builder)
+            .run(|pid, handle| {
+                manager.register(pid, handle);
+            })
+            .map_err(server_error)?;
+        self.clients.notify(
+            Audience::All,
+            "process/started",
+            json!({"pid": pid, "target": target.name, "kind": target.kind}),
+        );
+        let clients = self.clients.clone();
+        std::thread::spawn(move || watch(&manager, &clients, &target, pid, &sink));
+        Ok(json!({"pid": pid}))
+    }
+
+    fn open_location(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let (file, line, col) = match params["file"].as_str() {
+            Some(file) => (
+                PathBuf::from(file),
+                params["line"].as_u64().unwrap_or(1) as usize,
+                params["col"].as_u64().unwrap_or(1) as usize,
+            ),
+            None => {
+                let target = self.find_target(params).map_err(|_| invalid("file"))?;
+                let file = candidate_main_file(&target).unwrap_or(target.manifest_path);
+                let (line, col) = find_main_line(&file).unwrap_or((1, 1));
+                (file, line, col)
+            }
+        };
+        let argv = Editor::configured().windowed_argv(&file, line, col);
+        Command::new(&argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| (SERVER_ERROR, format!("failed to start {}: {}", argv[0], e)))?;
+        Ok(json!(true))
+    }
+}
+
+/// Forward `pid`'s output and diagnostics until it exits, then report the result.
+fn watch(
+    manager: &ProcessManager,
+    clients: &Clients,
+    target: &CargoTarget,
+    pid: u32,
+    sink: &OutputSink,
+) {
+    let diagnostics = manager
+        .get(pid)
+        .and_then(|h| h.lock().ok().map(|h| h.diagnostics.clone()));
+    let mut sent = 0;
+    let mut exited = false;
+    loop {
+        // One more pass after the exit, for what the capture threads flushed last.
+        let last_pass = exited;
+        exited = exited || matches!(manager.try_wait(pid), Ok(Some(_)));
+        let lines = sink.take();
+        if !lines.is_empty() {
+            clients.notify(
+                Audience::Output,
+                "output",
+                json!({"pid": pid, "lines": lines}),
+            );
+        }
+        if let Some(diagnostics) = &diagnostics {
+            let new: Vec<Value> = match diagnostics.lock() {
+                Ok(d) => d
+                    .iter()
+                    .skip(sent)
+                    .map(|d| diagnostic_json(pid, d))
+                    .collect(),
+                Err(_) => Vec::new(),
+            };
+            sent += new.len();
+            for diagnostic in new {
+                clients.notify(Audience::Diagnostics, "diagnostic", diagnostic);
+            }
+        }
+        if last_pass {
+            break;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    let result = manager.finish(pid).ok().flatten();
+    let count = |level: &str| {
+        result.as_ref().map_or(0, |r| {
+            r.diagnostics.iter().filter(|d| d.level == level).count()
+        })
+    };
+    let exit_code = result
+        .as_ref()
+        .and_then(|r| r.exit_status)
+        .and_then(|s| s.code());
+    clients.notify(
+        Audience::All,
+        "process/exited",
+        json!({
+            "pid": pid,
+            "target": target.name,
+            "success": exit_code == Some(0),
+            "exitCode": exit_code,
+            "errors": count("error"),
+            "warnings": count("warning"),
+            "elapsedSecs": result
+                .as_ref()
+                .and_then(|r| r.elapsed_time)
+                .map(|d| d.as_secs_f64()),
+        }),
+    );
+}
+
+/// Kill `pid` if it is one of ours and still running.
+fn kill(manager: &ProcessManager, pid: u32) -> bool {
+    let Some(handle) = manager.get(pid) else {
+        return false;
+    };
+    let mut handle = handle.lock().unwrap();
+    !handle.removed && handle.kill().is_ok()
+}
+
+/// A diagnostic as sent in `diagnostic` notifications, its file made absolute.
+pub fn diagnostic_json(pid: u32, diagnostic: &CargoDiagnostic) -> Value {
+    let location = (!diagnostic.lineref.is_empty()).then(|| {
+        let (file, line, col) = parse_lineref(&diagnostic.lineref);
+        (fs::canonicalize(&file).unwrap_or(file), line, col)
+    });
+    let label = diagnostic.diag_number.map(|n| {
+        format!(
+            "{}{:02}",
+            diagnostic
+                .level
+                .chars()
+                .next()
+                .unwrap_or('?')
+                .to_ascii_uppercase(),
+            n
+        )
+    });
+    json!({
+        "pid": pid,
+        "label": label,
+        "level": diagnostic.level,
+        "message": diagnostic.message,
+        "code": diagnostic.error_code,
+        "file": location.as_ref().map(|(file, _, _)| file),
+        "line": location.as_ref().map(|(_, line, _)| line),
+        "col": location.as_ref().map(|(_, _, col)| col),
+        "help": diagnostic.help,
+        "note": diagnostic.note,
+        "suggestion": diagnostic.suggestion,
+    })
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+fn invalid(param: &str) -> (i64, String) {
+    (INVALID_PARAMS, format!("missing or invalid {:?}", param))
+}
+
+fn server_error(e: impl std::fmt::Display) -> (i64, String) {
+    (SERVER_ERROR, e.to_string())
+}
+
+/// Run the server until `shutdown` or, on stdio, until stdin closes.
+/// `transport` is `stdio` or the path of a Unix socket to listen on.
+pub fn serve(ctx: ExtContext, transport: &str) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut server = Server::new(ctx);
+    if transport == "stdio" {
+        let client = Arc::new(Client::new(protocol_stdout()));
+        server.clients.add(client.clone());
+        std::thread::spawn(move || read_client(io::stdin().lock(), client, tx));
+    } else {
+        listen(Path::new(transport), server.clients.clone(), tx)?;
+    }
+    for incoming in rx {
+        match incoming {
+            Incoming::Message(client, line) => {
+                if let Some(response) = server.handle_line(&client, &line) {
+                    client.send(&response);
+                }
+            }
+            Incoming::Closed(client) => {
+                server.clients.remove(&client);
+                server.shutdown |= transport == "stdio";
+            }
+        }
+        if server.shutdown {
+            break;
+        }
+    }
+    server.ctx.manager.cleanup();
+    Ok(())
+}
+
+/// Pass each non-empty line from `reader` to the server, then report the close.
+fn read_client(reader: impl BufRead, client: Arc<Client>, tx: mpsc::Sender<Incoming>) {
+    for line in reader.lines() {
+        let Ok(line) = line else { break };
+        if !line.trim().is_empty() && tx.send(Incoming::Message(client.clone(), line)).is_err() {
+            return;
+        }
+    }
+    let _ = tx.send(Incoming::Closed(client));
+}
+
+/// Accept connections on a Unix socket at `path`, each one a client.
+#[cfg(unix)]
+fn listen(path: &Path, clients: Clients, tx: mpsc::Sender<Incoming>) -> anyhow::Result<()> {
+    use std::io::BufReader;
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixListener;
+    // A socket left behind by a previous server would make bind fail; anything
+    // else at the path is the user's and is left alone.
+    if let Ok(meta) = fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            anyhow::bail!(
+                "--serve {}: path exists and is not a socket",
+                path.display()
+            );
+        }
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    eprintln!("cargo-e: serving JSON-RPC on {}", path.display());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let Ok(writer) = stream.try_clone() else {
+                continue;
+            };
+            let client = Arc::new(Client::new(Box::new(writer)));
+            clients.add(client.clone());
+            let tx = tx.clone();
+            std::thread::spawn(move || read_client(BufReader::new(stream), client, tx));
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen(path: &Path, _clients: Clients, _tx: mpsc::Sender<Incoming>) -> anyhow::Result<()> {
+    anyhow::bail!(
+        "--serve {}: Unix sockets are not available here; use --serve stdio",
+        path.display()
+    )
+}
+
+/// Where stdio responses go. On Unix, stdout is moved to a private descriptor
+/// and fd 1 pointed at stderr, so a stray `println!` cannot corrupt the stream.
+fn protocol_stdout() -> Box<dyn Write + Send> {
+    #[cfg(unix)]
+    {
+        use std::os::fd::FromRawFd;
+        let fd = unsafe { libc::dup(libc::STDOUT_FILENO) };
+        if fd >= 0 && unsafe { libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) } >= 0 {
+            return Box::new(unsafe { fs::File::from_raw_fd(fd) });
+        }
+    }
+    Box::new(io::stdout())
+}
@@ -0,0 +1,401 @@
+//! Golden-output snapshots (`--snapshot record|check`).
+//!
+//! A snapshot is a target's runtime stdout: the lines it printed after cargo
+//! finished building (`CargoStats::build_finished_time`), so compiler output
+//! never ends up in it. The text is normalized by a [`Redactor`] first, so
+//! timestamps, paths and PIDs do not make a deterministic example look like it
+//! drifted. `record` writes `snapshots/<kind>/<target>.out` (`examples/`,
+//! `bins/`, ...) next to the target's manifest; `check` compares against it and
+//! prints a unified diff.
+use crate::e_cli::SnapshotMode;
+use crate::e_target::TargetKind;
+use regex::Regex;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Patterns every snapshot is normalized with, before `--snapshot-redact`.
+pub const DEFAULT_REDACTIONS: &[(&str, &str)] = &[
+    (
+        r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?",
+        "[TIMESTAMP]",
+    ),
+    (r"\b\d{2}:\d{2}:\d{2}(?:\.\d+)?\b", "[TIME]"),
+    (r"(?i)\b(pid|process id)([\s:=#]+)\d+", "$1$2[PID]"),
+];
+
+/// Beyond this many line pairs, a changed region is shown as a whole rather than aligned.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Runtime stdout collected while a target runs.
+#[derive(Debug, Default)]
+pub struct Capture {
+    lines: Mutex<Vec<String>>,
+}
+
+impl Capture {
+    pub fn push(&self, line: &str) {
+        if let Ok(mut lines) = self.lines.lock() {
+            // A pseudo-terminal ends lines with \r\n.
+            lines.push(line.trim_end_matches('\r').to_string());
+        }
+    }
+
+    /// Everything captured, one line each with a trailing newline.
+    pub fn text(&self) -> String {
+        let lines = self.lines.lock().map(|l| l.clone()).unwrap_or_default();
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+}
+
+/// Rewrites the parts of the output that change from run to run.
+#[derive(Debug)]
+pub struct Redactor {
+    /// Literal paths, longest first, so a nested directory wins over its parent.
+    paths: Vec<(Regex, String)>,
+    rules: Vec<(Regex, String)>,
+}
+
+impl Redactor {
+    /// The default patterns, then `extra` (`REGEX` or `REGEX=>REPLACEMENT`).
+    pub fn new(extra: &[String]) -> Result<Self, regex::Error> {
+        let mut rules = Vec::new();
+        for (pattern, replacement) in DEFAULT_REDACTIONS {
+            rules.push((Regex::new(pattern)?, replacement.to_string()));
+        }
+        for rule in extra {
+            let (pattern, replacement) = rule.split_once("=>").unwrap_or((rule, "[REDACTED]"));
+            rules.push((Regex::new(pattern)?, replacement.to_string()));
+        }
+        Ok(Redactor {
+            paths: Vec::new(),
+            rules,
+        })
+    }
+
+    /// Also replace `path` (the project root, the working directory) with `name`.
+    /// Paths are replaced before any pattern runs.
+    pub fn with_path(mut self, path: &Path, name: &str) -> Self {
+        let text = regex::escape(&path.to_string_lossy());
+        if text.len() > 1 {
+            let regex = Regex::new(&text).expect("escaped path is a valid regex");
+            let at = self
+                .paths
+                .iter()
+                .position(|(other, _)| other.as_str().len() < text.len())
+                .unwrap_or(self.paths.len());
+            self.paths.insert(at, (regex, name.to_string()));
+        }
+        self
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        self.paths
+            .iter()
+            .chain(&self.rules)
+            .fold(text.to_string(), |text, (regex, replacement)| {
+                regex.replace_all(&text, replacement.as_str()).into_owned()
+            })
+    }
+}
+
+/// `snapshots/<kind>/<label>.out` in the directory of `manifest_path`, so an example
+/// and a binary of the same name keep separate snapshots.
+pub fn snapshot_path(manifest_path: &Path, kind: TargetKind, label: &str) -> PathBuf {
+    let kind_dir = match kind.section_name() {
+        "example" => "examples",
+        "bin" => "bins",
+        "test" => "tests",
+        "bench" => "benches",
+        _ => "targets",
+    };
+    manifest_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("snapshots")
+        .join(kind_dir)
+        .join(format!("{}.out", label))
+}
+
+/// What `--snapshot` did with one run's output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotOutcome {
+    Recorded(PathBuf),
+    Matched(PathBuf),
+    /// The output differs; `diff` is a unified diff from the snapshot to this run.
+    Mismatch {
+        path: PathBuf,
+        diff: String,
+    },
+    /// `check` found no snapshot to compare with.
+    Missing(PathBuf),
+    /// `check` could not read the snapshot.
+    Unreadable {
+        path: PathBuf,
+        error: String,
+    },
+}
+
+impl SnapshotOutcome {
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            SnapshotOutcome::Mismatch { .. }
+                | SnapshotOutcome::Missing(_)
+                | SnapshotOutcome::Unreadable { .. }
+        )
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            SnapshotOutcome::Recorded(path)
+            | SnapshotOutcome::Matched(path)
+            | SnapshotOutcome::Mismatch { path, .. }
+            | SnapshotOutcome::Missing(path)
+            | SnapshotOutcome::Unreadable { path, .. } => path,
+        }
+    }
+
+    /// `recorded`, `matched`, `mismatch`, `missing` or `unreadable`.
+    pub fn status(&self) -> &'static str {
+        match self {
+            SnapshotOutcome::Recorded(_) => "recorded",
+            SnapshotOutcome::Matched(_) => "matched",
+            SnapshotOutcome::Mismatch { .. } => "mismatch",
+            SnapshotOutcome::Missing(_) => "missing",
+            SnapshotOutcome::Unreadable { .. } => "unreadable",
+        }
+    }
+}
+
+impl fmt::Display for SnapshotOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path().display();
+        match self {
+            SnapshotOutcome::Recorded(_) => write!(f, "recorded {}", path),
+            SnapshotOutcome::Matched(_) => write!(f, "matches {}", path),
+            SnapshotOutcome::Mismatch { .. } => write!(f, "differs from {}", path),
+            SnapshotOutcome::Missing(_) => {
+                write!(f, "no snapshot at {}; run --snapshot record", path)
+            }
+            SnapshotOutcome::Unreadable { error, .. } => {
+                write!(f, "could not read {}: {}", path, error)
+            }
+        }
+    }
+}
+
+/// Record `output` at `path`, or compare it with what is there.
+pub fn settle(mode: SnapshotMode, path: &Path, output: &str) -> io::Result<SnapshotOutcome> {
+    match mode {
+        SnapshotMode::Record => {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(path, output)?;
+            Ok(SnapshotOutcome::Recorded(path.to_path_buf()))
+        }
+        SnapshotMode::Check => {
+            let expected = match fs::read_to_string(path) {
+                Ok(expected) => expected,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    return Ok(SnapshotOutcome::Missing(path.to_path_buf()))
+                }
+                Err(e) => return Err(e),
+            };
+            // Line endings depend on the platform that recorded the file.
+            let expected = expected.replace("\r\n", "\n");
+            if expected == output {
+                Ok(SnapshotOutcome::Matched(path.to_path_buf()))
+            } else {
+                let old = path.display().to_string();
+                Ok(SnapshotOutcome::Mismatch {
+                    path: path.to_path_buf(),
+                    diff: unified_diff(&expected, output, &old, "this run", 3),
+                })
+            }
+        }
+    }
+}
+
+/// The lines of `text`, each paired with whether it lacks a trailing newline.
+fn split_lines(text: &str) -> Vec<(&str, bool)> {
+    let mut lines: Vec<(&str, bool)> = text.lines().map(|line| (line, false)).collect();
+    if !text.ends_with('\n') {
+        if let Some(last) = lines.last_mut() {
+            last.1 = true;
+        }
+    }
+    lines
+}
+
+/// A unified diff from `old` to `new` with `context` lines around each change.
+/// A last line without a trailing newline differs from the same line with one
+/// and is followed by `\ No newline at end of file`, as in diff(1).
+pub fn unified_diff(
+    old: &str,
+    new: &str,
+    old_name: &str,
+    new_name: &str,
+    context: usize,
+) -> String {
+    let a = split_lines(old);
+    let b = split_lines(new);
+
+    // Lines shared at both ends need no alignment.
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (mid_a, mid_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    // Each edit is (tag, old line index, new line index).
+    let mut edits: Vec<(char, usize, usize)> = (0..prefix).map(|i| (' ', i, i)).collect();
+    if mid_a.len().saturating_mul(mid_b.len()) > MAX_DIFF_CELLS {
+        // Too big to align: everything in between is replaced.
+        edits.extend((0..mid_a.len()).map(|i| ('-', prefix + i, prefix)));
+        edits.extend((0..mid_b.len()).map(|j| ('+', prefix + mid_a.len(), prefix + j)));
+    } else {
+        // Longest common subsequence table, filled from the end.
+        let mut lcs = vec![vec![0usize; mid_b.len() + 1]; mid_a.len() + 1];
+        for i in (0..mid_a.len()).rev() {
+            for j in (0..mid_b.len()).rev() {
+                lcs[i][j] = if mid_a[i] == mid_b[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < mid_a.len() || j < mid_b.len() {
+            if i < mid_a.len() && j < mid_b.len() && mid_a[i] == mid_b[j] {
+                edits.push((' ', prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if i < mid_a.len() && (j == mid_b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                // Removals before additions, as diff(1) does.
+                edits.push(('-', prefix + i, prefix + j));
+                i += 1;
+            } else {
+                edits.push(('+', prefix + i, prefix + j));
+                j += 1;
+            }
+        }
+    }
+    let (old_end, new_end) = (a.len() - suffix, b.len() - suffix);
+    edits.extend((0..suffix).map(|k| (' ', old_end + k, new_end + k)));
+
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+    let changed: Vec<usize> = (0..edits.len()).filter(|&k| edits[k].0 != ' ').collect();
+    let mut k = 0;
+    while k < changed.len() {
+        // Grow the hunk while the next change is within two contexts of this one.
+        let start = changed[k].saturating_sub(context);
+        let mut end = changed[k];
+        while k + 1 < changed.len() && changed[k + 1] <= end + 2 * context + 1 {
+            k += 1;
+            end = changed[k];
+        }
+        let end = (end + context + 1).min(edits.len());
+        let hunk = &edits[start..end];
+        let old_len = hunk.iter().filter(|e| e.0 != '+').count();
+        let new_len = hunk.iter().filter(|e| e.0 != '-').count();
+        let old_start = hunk[0].1 + usize::from(old_len > 0);
+        let new_start = hunk[0].2 + usize::from(new_len > 0);
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old_len, new_start, new_len
+        ));
+        for &(tag, i, j) in hunk {
+            let (line, no_newline) = if tag == '+' { b[j] } else { a[i] };
+            out.push_str(&format!("{}{}\n", tag, line));
+            if no_newline {
+                out.push_str("\\ No newline at end of file\n");
+            }
+        }
+        k += 1;
+    }
+    out
+}
+
+/// `diff` with removed lines red, added lines green and hunk headers cyan.
+pub fn colorize_diff(diff: &str) -> String {
+    use nu_ansi_term::{Color, Style};
+    diff.lines()
+        .map(|line| {
+            let style = if line.starts_with("---") || line.starts_with("+++") {
+                Style::new().bold()
+            } else if line.starts_with("@@") {
+                Color::Cyan.normal()
+            } else if line.starts_with('-') {
+                Color::Red.normal()
+            } else if line.starts_with('+') {
+                Color::Green.normal()
+            } else {
+                Style::new()
+            };
+            format!("{}\n", style.paint(line))
+        })
+        .collect()
+}
+
+/// Settle the snapshot of one finished run and print what happened; the
+/// snapshot is [`snapshot_path`] for `kind` and `label`. In `check` mode a
+/// snapshot that cannot be read is [`SnapshotOutcome::Unreadable`]; otherwise
+/// `None` if it could not be written.
+pub fn finish_run(
+    mode: SnapshotMode,
+    manifest_path: &Path,
+    kind: TargetKind,
+    label: &str,
+    redact: &[String],
+    capture: &Capture,
+) -> Option<SnapshotOutcome> {
+    let mut redactor = match Redactor::new(redact) {
+        Ok(redactor) => redactor,
+        Err(e) => {
+            crate::out_eprintln!("{}: invalid --snapshot-redact pattern: {}", label, e);
+            return None;
+        }
+    };
+    if let Some(root) = manifest_path.parent() {
+        redactor = redactor.with_path(root, "[ROOT]");
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        redactor = redactor.with_path(&cwd, "[CWD]");
+    }
+    redactor = redactor.with_path(&std::env::temp_dir(), "[TMP]");
+    let path = snapshot_path(manifest_path, kind, label);
+    match settle(mode, &path, &redactor.apply(&capture.text())) {
+        Ok(outcome) => {
+            crate::out_println!("{}: snapshot {}", label, outcome);
+            if let SnapshotOutcome::Mismatch { diff, .. } = &outcome {
+                use std::io::IsTerminal;
+                let color = std::io::stdout().is_terminal()
+                    && crate::e_eventdispatcher::current_output_sink().is_none();
+                if color {
+                    crate::out_println!("{}", colorize_diff(diff));
+                } else {
+                    crate::out_println!("{}", diff);
+                }
+            }
+            Some(outcome)
+        }
+        Err(e) => {
+            crate::out_eprintln!("{}: snapshot {}: {}", label, path.display(), e);
+            match mode {
+                SnapshotMode::Check => Some(SnapshotOutcome::Unreadable {
+                    path,
+                    error: e.to_string(),
+                }),
+                SnapshotMode::Record => None,
+            }
+        }
+    }
+}
@@ -10,12 +10,19 @@ use std::{
 use toml::Value;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "uses_serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TargetOrigin {
     DefaultBinary(PathBuf),
     SingleFile(PathBuf),
     MultiFile(PathBuf),
     SubProject(PathBuf),
     TomlSpecified(PathBuf),
+    /// A target reported by `cargo metadata`, carrying the fields cargo resolved for it.
+    CargoMetadata {
+        src_path: PathBuf,
+        edition: String,
+        required_features: Vec<String>,
+    },
     Named(OsString),
     /// A target provided by a plugin, storing plugin file and reported source path
     Plugin {
@@ -25,6 +32,7 @@ pub enum TargetOrigin {
 }
 
 #[derive(Debug, Clone, PartialEq, Hash, Eq, Copy, PartialOrd, Ord)]
+#[cfg_attr(feature = "uses_serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TargetKind {
     Unknown,
     UnknownExample,
@@ -93,6 +101,7 @@ impl TargetKind {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "uses_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CargoTarget {
     pub name: String,
     pub display_name: String,
@@ -112,6 +121,20 @@ impl CargoTarget {
         }
         label
     }
+    /// The target's `required-features`, comma-separated; `None` when it has none.
+    pub fn required_features(&self) -> Option<String> {
+        if let Some(TargetOrigin::CargoMetadata {
+            required_features, ..
+        }) = &self.origin
+        {
+            return Some(required_features.join(",")).filter(|f| !f.is_empty());
+        }
+        crate::e_manifest::get_required_features_from_manifest(
+            &self.manifest_path,
+            &self.kind,
+            &self.name,
+        )
+    }
     /// Constructs a CargoTarget from a source file.
     ///
     /// Reads the file at `file_path` and determines the target kind based on:
@@ -802,7 +825,9 @@ impl CargoTarget {
         // }
         // Operate only if the target has a file to inspect.
         let file_path = match &refined.origin {
-            Some(TargetOrigin::SingleFile(path)) | Some(TargetOrigin::DefaultBinary(path)) => path,
+            Some(TargetOrigin::SingleFile(path))
+            | Some(TargetOrigin::DefaultBinary(path))
+            | Some(TargetOrigin::CargoMetadata { src_path: path, .. }) => path,
             _ => return refined,
         };
 
@@ -1074,7 +1099,12 @@ pub fn dedup_targets(targets: Vec<CargoTarget>) -> Vec<CargoTarget> {
 
     let toml_specified_names: HashSet<String> = targets
         .iter()
-        .filter(|t| matches!(t.origin, Some(TargetOrigin::TomlSpecified(_))))
+        .filter(|t| {
+            matches!(
+                t.origin,
+                Some(TargetOrigin::TomlSpecified(_)) | Some(TargetOrigin::CargoMetadata { .. })
+            )
+        })
         .map(|t| t.name.clone())
         .collect();
 
@@ -0,0 +1,179 @@
+//! On-disk cache of discovered targets, stored at `target/cargo-e/targets.json`.
+//!
+//! Each entry is keyed by the set of manifests that were collected and carries a
+//! fingerprint of the files that influence discovery: every `Cargo.toml`, the
+//! `Cargo.lock`, `src/main.rs`, and everything under `examples/`, `src/bin/`,
+//! `tests/` and `benches/`.
+//! A fingerprint mismatch (or a different cargo-e version) forces a fresh collection.
+use crate::e_target::CargoTarget;
+use crate::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+#[cfg(feature = "uses_serde")]
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::UNIX_EPOCH;
+
+static REFRESH: AtomicBool = AtomicBool::new(false);
+
+/// When set, cached targets are ignored and the cache is rewritten from a fresh collection.
+pub fn set_refresh(refresh: bool) {
+    REFRESH.store(refresh, Ordering::SeqCst);
+}
+
+/// Returns the directory cargo writes build output to for `manifest_path`:
+/// `$CARGO_TARGET_DIR`, or `target/` next to the nearest `Cargo.lock` (the workspace root).
+pub fn target_dir_for(manifest_path: &Path) -> PathBuf {
+    if let Some(dir) = env::var_os("CARGO_TARGET_DIR") {
+        return PathBuf::from(dir);
+    }
+    workspace_root_for(manifest_path).join("target")
+}
+
+/// Path of the cache file used for `manifest_path`.
+pub fn cache_path_for(manifest_path: &Path) -> PathBuf {
+    target_dir_for(manifest_path)
+        .join("cargo-e")
+        .join("targets.json")
+}
+
+fn workspace_root_for(manifest_path: &Path) -> PathBuf {
+    let manifest = fs::canonicalize(manifest_path).unwrap_or_else(|_| manifest_path.to_path_buf());
+    let start = manifest.parent().unwrap_or(Path::new(".")).to_path_buf();
+    start
+        .ancestors()
+        .find(|dir| dir.join("Cargo.lock").is_file())
+        .map(Path::to_path_buf)
+        .unwrap_or(start)
+}
+
+fn hash_path(hasher: &mut DefaultHasher, path: &Path) {
+    path.hash(hasher);
+    if let Ok(meta) = fs::metadata(path) {
+        meta.len().hash(hasher);
+        if let Ok(Ok(duration)) = meta.modified().map(|m| m.duration_since(UNIX_EPOCH)) {
+            duration.as_nanos().hash(hasher);
+        }
+    }
+}
+
+/// Computes a fingerprint over the mtimes and sizes of every file that affects discovery.
+pub fn fingerprint(manifests: &[PathBuf]) -> String {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    if let Some(first) = manifests.first() {
+        hash_path(&mut hasher, &workspace_root_for(first).join("Cargo.lock"));
+    }
+    for manifest in manifests {
+        hash_path(&mut hasher, manifest);
+        let Some(dir) = manifest.parent() else {
+            continue;
+        };
+        hash_path(&mut hasher, &dir.join("src").join("main.rs"));
+        for sub in [
+            dir.join("examples"),
+            dir.join("src").join("bin"),
+            dir.join("tests"),
+            dir.join("benches"),
+        ] {
+            let mut entries: Vec<PathBuf> = walkdir::WalkDir::new(&sub)
+                .into_iter()
+                .filter_entry(|e| e.file_name() != "target")
+                .filter_map(|e| e.ok())
+                .map(|e| e.into_path())
+                .collect();
+            entries.sort();
+            for entry in entries {
+                hash_path(&mut hasher, &entry);
+            }
+        }
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(feature = "uses_serde")]
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct CacheFile {
+    entries: HashMap<String, CacheEntry>,
+}
+
+#[cfg(feature = "uses_serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CacheEntry {
+    fingerprint: String,
+    targets: Vec<CargoTarget>,
+}
+
+#[cfg(feature = "uses_serde")]
+fn cache_key(manifests: &[PathBuf], use_workspace: bool) -> String {
+    let mut key = format!("workspace={}", use_workspace);
+    for manifest in manifests {
+        let manifest = fs::canonicalize(manifest).unwrap_or_else(|_| manifest.clone());
+        key.push('|');
+        key.push_str(&manifest.to_string_lossy());
+    }
+    key
+}
+
+/// Returns the cached targets for `manifests` when the cache is fresh; otherwise runs
+/// `collect` and stores its result. Cache read/write failures only cost a recollection.
+pub fn cached_collect<F>(
+    manifests: &[PathBuf],
+    use_workspace: bool,
+    collect: F,
+) -> Result<Vec<CargoTarget>, Box<dyn Error>>
+where
+    F: FnOnce() -> Result<Vec<CargoTarget>, Box<dyn Error>>,
+{
+    #[cfg(feature = "uses_serde")]
+    if let Some(first) = manifests.first() {
+        let cache_path = cache_path_for(first);
+        let key = cache_key(manifests, use_workspace);
+        let mut cache: CacheFile = fs::read_to_string(&cache_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+
+        if !REFRESH.load(Ordering::SeqCst) {
+            if let Some(entry) = cache.entries.get(&key) {
+                if entry.fingerprint == fingerprint(manifests) {
+                    debug!("target cache hit: {}", cache_path.display());
+                    return Ok(entry.targets.clone());
+                }
+            }
+        }
+
+        let targets = collect()?;
+        // Fingerprint after collecting, since probing may have created the Cargo.lock.
+        cache.entries.insert(
+            key,
+            CacheEntry {
+                fingerprint: fingerprint(manifests),
+                targets: targets.clone(),
+            },
+        );
+        if let Err(e) = write_cache(&cache_path, &cache) {
+            debug!(
+                "failed to write target cache {}: {}",
+                cache_path.display(),
+                e
+            );
+        }
+        return Ok(targets);
+    }
+    #[cfg(not(feature = "uses_serde"))]
+    let _ = use_workspace;
+    collect()
+}
+
+#[cfg(feature = "uses_serde")]
+fn write_cache(cache_path: &Path, cache: &CacheFile) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = cache_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Write to a temporary file first so concurrent readers never see a partial cache.
+    let tmp = cache_path.with_extension(format!("json.{}", std::process::id()));
+    fs::write(&tmp, serde_json::to_string(cache)?)?;
+    fs::rename(&tmp, cache_path)?;
+    Ok(())
+}
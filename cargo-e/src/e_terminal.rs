@@ -0,0 +1,249 @@
+//! Opening commands in a new terminal window, or in a tmux/zellij pane.
+//!
+//! Used for `--detached` on Linux and macOS. Every launcher runs the target
+//! through the same small `sh` wrapper, so `--detached-delay` and
+//! `--detached-hold` mean the same thing everywhere:
+//!
+//! - delay: seconds to wait before starting the target.
+//! - hold: the window stays open this many seconds from the start; a target
+//!   still running then is killed, one that finished earlier leaves its output
+//!   on screen until the time is up.
+//!
+//! Launchers that return immediately (multiplexers, Terminal.app) are wrapped
+//! so the returned command still lasts as long as the target and exits with
+//! its status; run-all concurrency (`-J`) keeps working, one pane per target.
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Runs `"$@"` after `delay` seconds, killing it after `hold` seconds if set,
+/// and writes the exit status to `status_file` if one is given (and its own pid
+/// next to it, so the waiting side can tell when the pane is gone).
+const WRAPPER: &str = r#"delay=$1; hold=$2; status_file=$3; shift 3
+[ -n "$status_file" ] && echo $$ > "$status_file.pid"
+[ "$delay" -gt 0 ] && sleep "$delay"
+[ "$hold" -gt 0 ] && ( sleep "$hold"; pkill -P $$ ) &
+"$@"; status=$?
+[ "$hold" -gt 0 ] && wait
+[ -n "$status_file" ] && echo "$status" > "$status_file"
+exit "$status""#;
+
+/// Runs the launcher in `"$@"`, then waits for the wrapper to write `$1`.
+///
+/// Gives up when the wrapper is no longer running (the pane or session was closed),
+/// or when it has not started within 30 seconds. Stopping this script, as the
+/// run-all timeout does, stops the target in the pane too.
+const WAIT_FOR_STATUS: &str = r#"status_file=$1; shift
+pid_file=$status_file.pid
+trap 'pane=$(cat "$pid_file" 2>/dev/null)
+[ -n "$pane" ] && { kill -TERM -"$pane" 2>/dev/null || { pkill -TERM -P "$pane"; kill -TERM "$pane"; }; }
+rm -f "$status_file" "$pid_file"; exit 143' HUP INT TERM
+"$@" || exit $?
+waited=0
+while [ ! -s "$status_file" ]; do
+  if [ -s "$pid_file" ]; then
+    kill -0 "$(cat "$pid_file")" 2>/dev/null || break
+  elif [ "$waited" -ge 150 ]; then
+    break
+  fi
+  sleep 0.2; waited=$((waited + 1))
+done
+if [ ! -s "$status_file" ]; then
+  echo "cargo-e: the pane closed before the target finished" >&2
+  rm -f "$status_file" "$pid_file"; exit 1
+fi
+status=$(cat "$status_file"); rm -f "$status_file" "$pid_file"
+exit "$status""#;
+
+/// Terminal emulators probed in order, with the arguments that precede the
+/// program to run. Each of these stays in the foreground until the program exits.
+pub const KNOWN_TERMINALS: [(&str, &[&str]); 7] = [
+    ("alacritty", &["-e"]),
+    ("kitty", &[]),
+    ("wezterm", &["start", "--always-new-process", "--"]),
+    ("foot", &[]),
+    ("gnome-terminal", &["--wait", "--"]),
+    ("konsole", &["--nofork", "-e"]),
+    ("xterm", &["-e"]),
+];
+
+/// Where a detached target is opened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Launcher {
+    /// A new pane in the current tmux window.
+    Tmux,
+    /// A new pane in the current zellij session.
+    Zellij,
+    /// macOS Terminal.app, driven through `osascript`.
+    TerminalApp,
+    /// A terminal emulator and the arguments that precede the program to run.
+    Terminal {
+        program: String,
+        exec_args: Vec<String>,
+    },
+}
+
+/// Delay, hold and working directory for one launch.
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    pub delay: Option<u32>,
+    pub hold: Option<u32>,
+    pub cwd: Option<PathBuf>,
+    /// Pane or window title, where the launcher supports one.
+    pub title: Option<String>,
+}
+
+impl Launcher {
+    /// A launcher by name: `tmux`, `zellij`, `terminal-app`, a known terminal,
+    /// or any other program, which is assumed to take `-e <program>`.
+    pub fn from_name(name: &str) -> Launcher {
+        let base = Path::new(name)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(name);
+        match base {
+            "tmux" => Launcher::Tmux,
+            "zellij" => Launcher::Zellij,
+            "terminal-app" | "Terminal.app" => Launcher::TerminalApp,
+            _ => {
+                let exec_args = KNOWN_TERMINALS
+                    .iter()
+                    .find(|(known, _)| *known == base)
+                    .map(|(_, args)| args.iter().map(|a| a.to_string()).collect())
+                    .unwrap_or_else(|| vec!["-e".to_string()]);
+                Launcher::Terminal {
+                    program: name.to_string(),
+                    exec_args,
+                }
+            }
+        }
+    }
+
+    /// Pick a launcher: the configured `preference`, then the multiplexer we are
+    /// running inside, then `$TERMINAL`, then the first known terminal on PATH.
+    pub fn detect(preference: Option<&str>) -> Option<Launcher> {
+        Self::detect_with(
+            preference,
+            |key| std::env::var(key).ok(),
+            |program| which::which(program).is_ok(),
+        )
+    }
+
+    /// [`Launcher::detect`] with the environment and PATH lookup supplied by the caller.
+    pub fn detect_with(
+        preference: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+        on_path: impl Fn(&str) -> bool,
+    ) -> Option<Launcher> {
+        let set = |key: &str| env(key).filter(|v| !v.is_empty());
+        if let Some(name) = preference.filter(|p| !p.is_empty()) {
+            return Some(Launcher::from_name(name));
+        }
+        if set("TMUX").is_some() {
+            return Some(Launcher::Tmux);
+        }
+        if set("ZELLIJ").is_some() {
+            return Some(Launcher::Zellij);
+        }
+        if let Some(terminal) = set("TERMINAL") {
+            return Some(Launcher::from_name(&terminal));
+        }
+        if let Some((name, _)) = KNOWN_TERMINALS.iter().find(|(name, _)| on_path(name)) {
+            return Some(Launcher::from_name(name));
+        }
+        cfg!(target_os = "macos").then_some(Launcher::TerminalApp)
+    }
+
+    /// A command that runs `program args` through this launcher.
+    pub fn command(&self, program: &str, args: &[String], opts: &LaunchOptions) -> Command {
+        let status_file = match self {
+            Launcher::Terminal { .. } => None,
+            _ => Some(status_file_path()),
+        };
+        let mut wrapped: Vec<String> = vec![
+            "sh".into(),
+            "-c".into(),
+            WRAPPER.into(),
+            "cargo-e-detached".into(),
+            opts.delay.unwrap_or(0).to_string(),
+            opts.hold.unwrap_or(0).to_string(),
+            status_file
+                .as_ref()
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            program.to_string(),
+        ];
+        wrapped.extend(args.iter().cloned());
+
+        let mut launch = match self {
+            Launcher::Terminal { program, exec_args } => {
+                let mut cmd = Command::new(program);
+                cmd.args(exec_args).args(&wrapped);
+                return with_cwd(cmd, opts);
+            }
+            Launcher::Tmux => {
+                let mut launch = vec!["tmux".to_string(), "split-window".into(), "-d".into()];
+                if let Some(cwd) = &opts.cwd {
+                    launch.push("-c".into());
+                    launch.push(cwd.to_string_lossy().into_owned());
+                }
+                launch.extend(wrapped);
+                // Keep panes evenly sized as targets come and go.
+                launch.extend([";".into(), "select-layout".into(), "tiled".into()]);
+                launch
+            }
+            Launcher::Zellij => {
+                let mut launch = vec!["zellij".to_string(), "run".into()];
+                if let Some(cwd) = &opts.cwd {
+                    launch.push("--cwd".into());
+                    launch.push(cwd.to_string_lossy().into_owned());
+                }
+                if let Some(title) = &opts.title {
+                    launch.push("--name".into());
+                    launch.push(title.clone());
+                }
+                launch.push("--".into());
+                launch.extend(wrapped);
+                launch
+            }
+            Launcher::TerminalApp => {
+                let mut line: Vec<String> = Vec::new();
+                if let Some(cwd) = &opts.cwd {
+                    line.push(format!("cd {} &&", shell_quote(&cwd.to_string_lossy())));
+                }
+                line.extend(wrapped.iter().map(|a| shell_quote(a)));
+                let script = format!(
+                    "tell application \"Terminal\" to do script \"{}\"",
+                    line.join(" ").replace('\\', "\\\\").replace('"', "\\\"")
+                );
+                vec!["osascript".to_string(), "-e".into(), script]
+            }
+        };
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", WAIT_FOR_STATUS, "cargo-e-launch"]);
+        cmd.arg(status_file.unwrap_or_default());
+        cmd.args(launch.drain(..));
+        with_cwd(cmd, opts)
+    }
+}
+
+fn with_cwd(mut cmd: Command, opts: &LaunchOptions) -> Command {
+    if let Some(cwd) = &opts.cwd {
+        cmd.current_dir(cwd);
+    }
+    cmd
+}
+
+fn status_file_path() -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    std::env::temp_dir().join(format!(
+        "cargo-e-{}-{}.status",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::SeqCst)
+    ))
+}
+
+/// Single-quote `s` for `sh`.
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
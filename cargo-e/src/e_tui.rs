@@ -1,7 +1,10 @@
 #[cfg(feature = "tui")]
 pub mod tui_interactive {
     use crate::e_command_builder::CargoCommandBuilder;
+    use crate::e_eventdispatcher::OutputSink;
+    use crate::e_fuzzy::{fuzzy_match, rank_targets, TargetField, TargetMatch};
     use crate::e_manifest::maybe_patch_manifest_for_run;
+    use crate::e_preview::{highlight, Preview, Token};
     use crate::e_processmanager::ProcessManager;
     use crate::e_prompts::prompt_line;
     use crate::e_target::CargoTarget;
@@ -20,12 +23,16 @@ pub mod tui_interactive {
     use ratatui::{
         backend::CrosstermBackend,
         layout::{Constraint, Direction, Layout, Rect},
-        style::{Color, Style},
+        style::{Color, Modifier, Style},
         text::{Line, Span},
-        widgets::{Block, Borders, List, ListItem, ListState},
+        widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
         Terminal,
     };
-    use std::{collections::HashSet, thread, time::Duration};
+    use std::{
+        collections::{HashMap, HashSet},
+        thread,
+        time::{Duration, SystemTime},
+    };
     use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
 
     /// Flushes the input event queue, ignoring any stray Enter key events.
@@ -120,20 +127,51 @@ pub mod tui_interactive {
 
         let mut list_state = ListState::default();
         list_state.select(Some(0));
+        // `/` search: the query, whether keys are being typed into it, and the
+        // matching targets, best first.
+        let mut query = String::new();
+        let mut searching = false;
+        let mut view: Vec<TargetMatch> = rank_targets("", &exs);
         let mut exit_hover = false;
         let mut run_history_map = crate::e_parser::read_run_history(&history_path);
+        // Index of the chosen run profile per target name; 0 means no profile.
+        let mut selected_profiles: HashMap<String, usize> = HashMap::new();
+        // The target started from the list, shown next to it with its output.
+        let mut pane: Option<OutputPane> = None;
+        // Targets marked with space/`a` (indices into `exs`), and the last batch
+        // run of marked targets; its output shows when no single target does.
+        let mut marked: HashSet<usize> = HashSet::new();
+        let mut batch: Option<BatchRun> = None;
+        // `v` toggles a preview of the highlighted target's source; it is
+        // reloaded when the highlight moves. `preview_top` is its first shown line.
+        let mut show_preview = false;
+        let mut preview: Option<(usize, Option<Preview>)> = None;
+        let mut preview_top = 0;
         'main_loop: loop {
+            let outcomes = batch
+                .as_ref()
+                .map(|b| b.outcomes(&manager))
+                .unwrap_or_default();
+            if show_preview {
+                let current = selected_index(&list_state, &view);
+                if current != preview.as_ref().map(|(i, _)| *i) {
+                    preview = current.map(|i| (i, Preview::load(&exs[i])));
+                    preview_top = preview
+                        .as_ref()
+                        .and_then(|(_, p)| p.as_ref()?.main_line)
+                        .map_or(0, |line| line.saturating_sub(2));
+                }
+                if let Some((_, Some(p))) = &preview {
+                    preview_top = preview_top.min(p.lines.len().saturating_sub(1));
+                }
+            }
             terminal.draw(|f| {
                 let size = f.area();
                 let area = Rect::new(0, 0, size.width, size.height);
-                let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(2)
-                    .constraints([Constraint::Min(0)].as_ref())
-                    .split(area);
-                let list_area = chunks[0];
+                let (list_area, preview_area, pane_areas) =
+                    screen_layout(area, show_preview, pane.is_some() || batch.is_some());
 
-                let left_text = format!("Select target ({} found)", exs.len());
+                let left_text = title_text(exs.len(), &query, searching, view.len(), marked.len());
                 let separator = " ┃ ";
                 let right_text = "q to EXIT";
                 let title_line = if exit_hover {
@@ -159,18 +197,31 @@ pub mod tui_interactive {
                     .unwrap_or(0);
                 let mut line_number = 0;
                 let block = Block::default().borders(Borders::ALL).title(title_line);
-                let items: Vec<ListItem> = exs
+                let items: Vec<ListItem> = view
                     .iter()
-                    .map(|ex| {
+                    .map(|m| {
+                        let ex = &exs[m.index];
                         let kind_str = format!("{:?}", ex.kind);
-                        let mut display = format!(
-                            "{:>width$}: [{:>max_kind_width$}] {}",
+                        let prefix = format!(
+                            "{}{:>width$}: [{:>max_kind_width$}] ",
+                            if marked.contains(&m.index) {
+                                "* "
+                            } else {
+                                "  "
+                            },
                             line_number,
                             kind_str,
-                            &ex.display_name,
                             width = pad_width,
                             max_kind_width = max_kind_width
                         );
+                        let positions = if m.field == TargetField::DisplayName {
+                            m.positions.clone()
+                        } else {
+                            fuzzy_match(&query, &ex.display_name)
+                                .map(|found| found.positions)
+                                .unwrap_or_default()
+                        };
+                        let mut display = String::new();
                         if let Some(count) = run_history_map.get(&ex.name) {
                             display.push_str(&format!(
                                 "({} run{})",
@@ -178,8 +229,42 @@ pub mod tui_interactive {
                                 if *count == 1 { "" } else { "s" }
                             ));
                         }
+                        let profiles = crate::e_config::run_profiles(&ex.name);
+                        if !profiles.is_empty() {
+                            let names: Vec<String> = profiles
+                                .iter()
+                                .enumerate()
+                                .map(|(i, p)| {
+                                    if selected_profiles.get(&ex.name) == Some(&(i + 1)) {
+                                        format!("*@{}", p.name)
+                                    } else {
+                                        format!("@{}", p.name)
+                                    }
+                                })
+                                .collect();
+                            display.push_str(&format!(" [p: {}]", names.join(" ")));
+                        }
                         line_number = line_number + 1;
-                        let mut item = ListItem::new(display);
+                        let mut spans = vec![Span::raw(prefix)];
+                        spans.extend(highlighted(&ex.display_name, &positions));
+                        spans.push(Span::raw(display));
+                        if let Some(batch) = batch.as_ref().filter(|b| b.names.contains(&ex.name)) {
+                            spans.push(match outcomes.get(&ex.name) {
+                                Some((true, elapsed)) => Span::styled(
+                                    format!(" pass {}", crate::e_fmt::format_duration(*elapsed)),
+                                    Style::default().fg(Color::Green),
+                                ),
+                                Some((false, elapsed)) => Span::styled(
+                                    format!(" FAIL {}", crate::e_fmt::format_duration(*elapsed)),
+                                    Style::default().fg(Color::Red),
+                                ),
+                                None if batch.is_running() => {
+                                    Span::styled(" …", Style::default().fg(Color::Yellow))
+                                }
+                                None => Span::raw(" skipped"),
+                            });
+                        }
+                        let mut item = ListItem::new(Line::from(spans));
                         if run_history_map.get(&ex.name).is_some() {
                             item = item.style(Style::default().fg(Color::Blue));
                         }
@@ -191,8 +276,31 @@ pub mod tui_interactive {
                     .highlight_style(Style::default().fg(Color::Yellow))
                     .highlight_symbol(">> ");
                 f.render_stateful_widget(list, list_area, &mut list_state);
+                if let (Some(preview_area), Some((index, loaded))) = (preview_area, &preview) {
+                    render_preview(f, preview_area, &exs[*index], loaded.as_ref(), preview_top);
+                }
+                if let Some((output_area, status_area)) = pane_areas {
+                    if let Some(pane) = &pane {
+                        pane.render(f, output_area, status_area, &manager);
+                    } else if let Some(batch) = &batch {
+                        batch.render(f, output_area, status_area, &outcomes);
+                    }
+                }
             })?;
 
+            if let Some(p) = pane.as_mut() {
+                if p.poll(&manager) == Some(true) {
+                    record_run(&history_path, &p.target.name)?;
+                    run_history_map = crate::e_parser::read_run_history(&history_path);
+                }
+            }
+            if let Some(b) = batch.as_mut() {
+                b.poll();
+            }
+            // Wake up regularly so the output pane streams while no key is pressed.
+            if !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
             {
                 match event::read()? {
                     Event::Key(key) => {
@@ -207,6 +315,33 @@ pub mod tui_interactive {
                                 manager.kill_all();
                                 // Optionally, exit the TUI or perform additional cleanup.
                             }
+                            if searching {
+                                let typed = match key.code {
+                                    KeyCode::Char(c)
+                                        if !key
+                                            .modifiers
+                                            .contains(event::KeyModifiers::CONTROL) =>
+                                    {
+                                        query.push(c);
+                                        true
+                                    }
+                                    KeyCode::Backspace => {
+                                        query.pop();
+                                        true
+                                    }
+                                    KeyCode::Enter => {
+                                        // Keep the filter; Enter below runs the highlighted result.
+                                        searching = false;
+                                        false
+                                    }
+                                    _ => false,
+                                };
+                                if typed {
+                                    view = rank_targets(&query, &exs);
+                                    list_state.select((!view.is_empty()).then_some(0));
+                                    continue;
+                                }
+                            }
                             // Check if we might be starting an escape sequence for an arrow key.
                             if key.code == KeyCode::Esc {
                                 // Try to collect the rest of the sequence.
@@ -221,7 +356,7 @@ pub mod tui_interactive {
                                         }
                                         KeyCode::Down => {
                                             let new_index = match list_state.selected() {
-                                                Some(i) if i >= exs.len() - 1 => i,
+                                                Some(i) if i + 1 >= view.len() => i,
                                                 Some(i) => i + 1,
                                                 None => 0,
                                             };
@@ -238,13 +373,89 @@ pub mod tui_interactive {
                                     // We've handled the arrow, so skip further processing.
                                     continue;
                                 } else {
-                                    // No follow-up sequence—treat it as a standalone Esc if needed.
-                                    // For example, you might decide not to exit on Esc now.
-                                    // println!("Standalone Esc detected (ignoring).");
+                                    // A standalone Esc clears the search filter.
+                                    if searching || !query.is_empty() {
+                                        searching = false;
+                                        query.clear();
+                                        view = rank_targets(&query, &exs);
+                                        list_state.select(Some(0));
+                                    }
                                     continue;
                                 }
                             }
                             match key.code {
+                                KeyCode::Char('/') => {
+                                    searching = true;
+                                }
+                                KeyCode::Char('v') => {
+                                    show_preview = !show_preview;
+                                    preview = None;
+                                }
+                                KeyCode::Char('J') => {
+                                    preview_top += 1;
+                                }
+                                KeyCode::Char('K') => {
+                                    preview_top = preview_top.saturating_sub(1);
+                                }
+                                KeyCode::Char(' ') => {
+                                    if let Some(selected) = selected_index(&list_state, &view) {
+                                        if !marked.remove(&selected) {
+                                            marked.insert(selected);
+                                        }
+                                    }
+                                }
+                                KeyCode::Char('a') => {
+                                    // Mark every visible target, or unmark them if all are marked.
+                                    if view.iter().all(|m| marked.contains(&m.index)) {
+                                        for m in &view {
+                                            marked.remove(&m.index);
+                                        }
+                                    } else {
+                                        marked.extend(view.iter().map(|m| m.index));
+                                    }
+                                }
+                                KeyCode::Char('k') => {
+                                    if let Some(p) = pane.as_mut() {
+                                        p.kill(&manager);
+                                    } else if let Some(b) = batch.as_ref() {
+                                        b.stop(&manager);
+                                    }
+                                }
+                                KeyCode::Char('r') => {
+                                    if let Some(p) = pane.as_mut() {
+                                        p.restart(&manager, cli)?;
+                                        terminal.clear()?;
+                                    }
+                                }
+                                KeyCode::Char('c')
+                                    if !key.modifiers.contains(event::KeyModifiers::CONTROL) =>
+                                {
+                                    if let Some(p) = &pane {
+                                        if !p.is_running() {
+                                            pane = None;
+                                        }
+                                    } else if batch.as_ref().is_some_and(|b| !b.is_running()) {
+                                        batch = None;
+                                    }
+                                }
+                                KeyCode::Char('[') | KeyCode::Char(']') => {
+                                    let page = terminal
+                                        .size()
+                                        .map(|r| (r.height / 2) as usize)
+                                        .unwrap_or(10);
+                                    let shown = match (pane.as_mut(), batch.as_mut()) {
+                                        (Some(p), _) => Some((&mut p.scroll, p.sink.len())),
+                                        (None, Some(b)) => Some((&mut b.scroll, b.sink.len())),
+                                        (None, None) => None,
+                                    };
+                                    if let Some((scroll, len)) = shown {
+                                        *scroll = if key.code == KeyCode::Char('[') {
+                                            (*scroll + page).min(len)
+                                        } else {
+                                            scroll.saturating_sub(page)
+                                        };
+                                    }
+                                }
                                 KeyCode::Char('q') => {
                                     // Exit the TUI mode when 'q' is pressed.
                                     println!("Exiting TUI mode...");
@@ -252,7 +463,7 @@ pub mod tui_interactive {
                                 }
                                 KeyCode::Down => {
                                     let i = match list_state.selected() {
-                                        Some(i) if i >= exs.len() - 1 => i,
+                                        Some(i) if i + 1 >= view.len() => i,
                                         Some(i) => i + 1,
                                         None => 0,
                                     };
@@ -275,7 +486,8 @@ pub mod tui_interactive {
                                         .unwrap_or(5)
                                         as usize;
                                     let current = list_state.selected().unwrap_or(0);
-                                    let new = std::cmp::min(current + page, exs.len() - 1);
+                                    let new =
+                                        std::cmp::min(current + page, view.len().saturating_sub(1));
                                     list_state.select(Some(new));
                                 }
                                 KeyCode::PageUp => {
@@ -289,10 +501,12 @@ pub mod tui_interactive {
                                     list_state.select(Some(new));
                                 }
                                 KeyCode::Char('b') => {
-                                    if let Some(selected) = list_state.selected() {
-                                        let sample = &examples[selected];
+                                    if let Some(selected) = selected_index(&list_state, &view) {
+                                        let sample = &exs[selected];
                                         // Run bacon in detached mode. Extra arguments can be added if needed.
-                                        if let Err(e) = e_bacon::run_bacon(sample, &Vec::new()) {
+                                        if let Err(e) =
+                                            e_bacon::run_bacon(sample, &Vec::new(), true)
+                                        {
                                             eprintln!("Error running bacon: {}", e);
                                         } else {
                                             println!("Bacon launched for sample: {}", sample.name);
@@ -301,34 +515,31 @@ pub mod tui_interactive {
                                     }
                                 }
                                 KeyCode::Char('e') => {
-                                    if let Some(selected) = list_state.selected() {
+                                    if let Some(selected) = selected_index(&list_state, &view) {
                                         // Disable raw mode for debug printing.
                                         crossterm::terminal::disable_raw_mode()?;
                                         crossterm::execute!(
                                             std::io::stdout(),
                                             crossterm::terminal::LeaveAlternateScreen
                                         )?;
-                                        // When 'e' is pressed, attempt to open the sample in VSCode.
+                                        // When 'e' is pressed, open the sample in the configured editor.
                                         let sample = &exs[selected];
+                                        let editor = crate::e_editor::Editor::configured();
                                         println!(
-                                            "Opening VSCode for path: {}",
-                                            sample
-                                                .manifest_path
-                                                .to_str()
-                                                .unwrap_or_default()
-                                                .to_owned()
+                                            "Opening {} in the editor",
+                                            sample.manifest_path.display()
                                         );
-                                        // Here we block on the asynchronous open_vscode call.
-                                        // futures::executor::block_on(open_vscode(Path::new(&sample.manifest_path)));
-                                        futures::executor::block_on(
-                                            e_findmain::open_vscode_for_sample(sample),
-                                        );
-                                        std::thread::sleep(std::time::Duration::from_secs(5));
+                                        if let Err(e) =
+                                            e_findmain::open_target_in_editor(sample, &editor)
+                                        {
+                                            eprintln!("Failed to open editor: {}", e);
+                                            std::thread::sleep(std::time::Duration::from_secs(3));
+                                        }
                                         reinit_terminal(&mut terminal)?;
                                     }
                                 }
                                 KeyCode::Char('i') => {
-                                    if let Some(selected) = list_state.selected() {
+                                    if let Some(selected) = selected_index(&list_state, &view) {
                                         // Disable raw mode for debug printing.
                                         crossterm::terminal::disable_raw_mode()?;
                                         crossterm::execute!(
@@ -362,20 +573,62 @@ pub mod tui_interactive {
                                 //         reinit_terminal(&mut terminal)?;
                                 //     }
                                 // }
+                                KeyCode::Char('p') => {
+                                    // Cycle through the run profiles of the selected target.
+                                    if let Some(selected) = selected_index(&list_state, &view) {
+                                        let name = exs[selected].name.clone();
+                                        let count = crate::e_config::run_profiles(&name).len();
+                                        if count > 0 {
+                                            let next = selected_profiles
+                                                .get(&name)
+                                                .map_or(1, |i| (i + 1) % (count + 1));
+                                            selected_profiles.insert(name, next);
+                                        }
+                                    }
+                                }
+                                KeyCode::Enter if !marked.is_empty() => {
+                                    if batch.as_ref().is_some_and(|b| b.is_running()) {
+                                        continue;
+                                    }
+                                    if let Some(mut previous) = pane.take() {
+                                        previous.kill(&manager);
+                                    }
+                                    let mut indices: Vec<usize> = marked.drain().collect();
+                                    indices.sort_unstable();
+                                    let targets =
+                                        indices.into_iter().map(|i| exs[i].clone()).collect();
+                                    batch = Some(BatchRun::launch(&manager, cli, targets));
+                                    terminal.clear()?;
+                                }
                                 KeyCode::Enter => {
-                                    if let Some(selected) = list_state.selected() {
-                                        run_piece(
-                                            manager.clone(),
-                                            &exs,
-                                            selected,
-                                            &history_path,
-                                            &mut run_history,
-                                            &mut terminal,
-                                            cli,
-                                        )?;
-                                        run_history_map =
-                                            crate::e_parser::read_run_history(&history_path);
-                                        reinit_terminal(&mut terminal)?;
+                                    if batch.as_ref().is_some_and(|b| b.is_running()) {
+                                        continue;
+                                    }
+                                    if let Some(selected) = selected_index(&list_state, &view) {
+                                        let profile =
+                                            selected_profile(&selected_profiles, &exs[selected]);
+                                        if cli.detached {
+                                            run_piece(
+                                                manager.clone(),
+                                                Piece {
+                                                    target: &exs[selected],
+                                                    position: (selected + 1, exs.len()),
+                                                    profile: profile.as_ref(),
+                                                },
+                                                &history_path,
+                                                &mut run_history,
+                                                &mut terminal,
+                                                cli,
+                                            )?;
+                                            run_history_map =
+                                                crate::e_parser::read_run_history(&history_path);
+                                            reinit_terminal(&mut terminal)?;
+                                        } else {
+                                            start_in_pane(
+                                                &mut pane, &manager, cli, &exs, selected, profile,
+                                            )?;
+                                            terminal.clear()?;
+                                        }
                                     }
                                 }
                                 _ => {
@@ -387,15 +640,21 @@ pub mod tui_interactive {
                     Event::Mouse(mouse_event) => {
                         let size = terminal.size()?;
                         let area = Rect::new(0, 0, size.width, size.height);
-                        let chunks = Layout::default()
-                            .direction(Direction::Vertical)
-                            .margin(2)
-                            .constraints([Constraint::Min(0)].as_ref())
-                            .split(area);
-                        let list_area = chunks[0];
+                        let (list_area, preview_area, pane_areas) =
+                            screen_layout(area, show_preview, pane.is_some() || batch.is_some());
+                        let inside = |r: Rect| {
+                            mouse_event.column >= r.x
+                                && mouse_event.column < r.x + r.width
+                                && mouse_event.row >= r.y
+                                && mouse_event.row < r.y + r.height
+                        };
+                        let over_pane =
+                            pane_areas.is_some_and(|(output_area, _)| inside(output_area));
+                        let over_preview = preview_area.is_some_and(inside);
                         let title_row = list_area.y;
                         let title_start = list_area.x + 2;
-                        let left_text = format!("Select target ({} found)", exs.len());
+                        let left_text =
+                            title_text(exs.len(), &query, searching, view.len(), marked.len());
                         let separator = " ┃ ";
                         let right_text = "q to EXIT";
                         let offset = (left_text.len() + separator.len()) as u16;
@@ -403,9 +662,29 @@ pub mod tui_interactive {
                         let right_region_end = right_region_start + (right_text.len() as u16);
 
                         match mouse_event.kind {
+                            MouseEventKind::ScrollDown if over_preview => {
+                                preview_top += 3;
+                            }
+                            MouseEventKind::ScrollUp if over_preview => {
+                                preview_top = preview_top.saturating_sub(3);
+                            }
+                            MouseEventKind::ScrollDown if over_pane => {
+                                if let Some(p) = pane.as_mut() {
+                                    p.scroll = p.scroll.saturating_sub(3);
+                                } else if let Some(b) = batch.as_mut() {
+                                    b.scroll = b.scroll.saturating_sub(3);
+                                }
+                            }
+                            MouseEventKind::ScrollUp if over_pane => {
+                                if let Some(p) = pane.as_mut() {
+                                    p.scroll = (p.scroll + 3).min(p.sink.len());
+                                } else if let Some(b) = batch.as_mut() {
+                                    b.scroll = (b.scroll + 3).min(b.sink.len());
+                                }
+                            }
                             MouseEventKind::ScrollDown => {
                                 let current = list_state.selected().unwrap_or(0);
-                                let new = std::cmp::min(current + 1, exs.len() - 1);
+                                let new = std::cmp::min(current + 1, view.len().saturating_sub(1));
                                 list_state.select(Some(new));
                             }
                             MouseEventKind::ScrollUp => {
@@ -428,7 +707,7 @@ pub mod tui_interactive {
                                         && mouse_event.row < inner_y + inner_height
                                     {
                                         let index = (mouse_event.row - inner_y) as usize;
-                                        if index < exs.len() {
+                                        if index < view.len() {
                                             list_state.select(Some(index));
                                         }
                                     }
@@ -449,20 +728,35 @@ pub mod tui_interactive {
                                     && mouse_event.row >= inner_y
                                     && mouse_event.row < inner_y + inner_height
                                 {
-                                    let index = (mouse_event.row - inner_y) as usize;
-                                    if index < exs.len() {
-                                        list_state.select(Some(index));
-                                        run_piece(
-                                            manager.clone(),
-                                            &exs.clone(),
-                                            index,
-                                            &history_path,
-                                            &mut run_history,
-                                            &mut terminal,
-                                            cli,
-                                        )?;
-                                        run_history_map =
-                                            crate::e_parser::read_run_history(&history_path);
+                                    let row = (mouse_event.row - inner_y) as usize;
+                                    let busy = batch.as_ref().is_some_and(|b| b.is_running());
+                                    if let Some(index) =
+                                        view.get(row).map(|m| m.index).filter(|_| !busy)
+                                    {
+                                        list_state.select(Some(row));
+                                        let profile =
+                                            selected_profile(&selected_profiles, &exs[index]);
+                                        if cli.detached {
+                                            run_piece(
+                                                manager.clone(),
+                                                Piece {
+                                                    target: &exs[index],
+                                                    position: (index + 1, exs.len()),
+                                                    profile: profile.as_ref(),
+                                                },
+                                                &history_path,
+                                                &mut run_history,
+                                                &mut terminal,
+                                                cli,
+                                            )?;
+                                            run_history_map =
+                                                crate::e_parser::read_run_history(&history_path);
+                                        } else {
+                                            start_in_pane(
+                                                &mut pane, &manager, cli, &exs, index, profile,
+                                            )?;
+                                            terminal.clear()?;
+                                        }
                                     }
                                 }
                             }
@@ -473,6 +767,17 @@ pub mod tui_interactive {
                 }
             }
         }
+        if let Some(p) = pane.as_mut() {
+            p.kill(&manager);
+        }
+        if let Some(mut b) = batch {
+            if b.is_running() {
+                b.stop(&manager);
+                while !b.poll() {
+                    thread::sleep(Duration::from_millis(100));
+                }
+            }
+        }
 
         disable_raw_mode()?;
         let mut stdout = io::stdout();
@@ -487,6 +792,564 @@ pub mod tui_interactive {
         Ok(())
     }
 
+    /// Split the screen into the target list and a right-hand column holding
+    /// the source preview and/or the output pane with its status bar.
+    fn screen_layout(
+        area: Rect,
+        with_preview: bool,
+        with_pane: bool,
+    ) -> (Rect, Option<Rect>, Option<(Rect, Rect)>) {
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(2)
+            .constraints([Constraint::Min(0)].as_ref())
+            .split(area)[0];
+        if !with_preview && !with_pane {
+            return (outer, None, None);
+        }
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(outer);
+        let (preview, pane_column) = match (with_preview, with_pane) {
+            (true, true) => {
+                let halves = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                    .split(columns[1]);
+                (Some(halves[0]), Some(halves[1]))
+            }
+            (true, false) => (Some(columns[1]), None),
+            _ => (None, Some(columns[1])),
+        };
+        let pane = pane_column.map(|column| {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(1)].as_ref())
+                .split(column);
+            (rows[0], rows[1])
+        });
+        (columns[0], preview, pane)
+    }
+
+    /// The preview pane: a header from the file's `//!` summary, its
+    /// `required-features` and manifest, then the highlighted source from line
+    /// `top` (initially just above `fn main`).
+    fn render_preview(
+        f: &mut ratatui::Frame,
+        area: Rect,
+        target: &CargoTarget,
+        preview: Option<&Preview>,
+        top: usize,
+    ) {
+        let title = Line::from(vec![
+            Span::styled(
+                format!(" {} ", target.display_name),
+                Style::default().fg(Color::Green),
+            ),
+            Span::raw("v close · J K scroll "),
+        ]);
+        let block = Block::default().borders(Borders::ALL).title(title);
+        let dim = Style::default().fg(Color::DarkGray);
+        let Some(preview) = preview else {
+            let text = vec![
+                Line::raw(format!("No source file found for {}.", target.name)),
+                Line::styled(target.manifest_path.display().to_string(), dim),
+            ];
+            f.render_widget(Paragraph::new(text).block(block), area);
+            return;
+        };
+        let mut text = Vec::new();
+        let summary = preview.summary();
+        if !summary.is_empty() {
+            text.push(Line::styled(
+                summary,
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+        }
+        if let Some(features) = &preview.required_features {
+            text.push(Line::styled(
+                format!("required-features: {}", features),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        text.push(Line::styled(
+            format!("manifest: {}", preview.manifest_path.display()),
+            dim,
+        ));
+        text.push(Line::styled(
+            format!("source:   {}", preview.source.display()),
+            dim,
+        ));
+        let number_width = preview.lines.len().to_string().len();
+        let top = top.min(preview.lines.len().saturating_sub(1));
+        for (n, line) in preview.lines.iter().enumerate().skip(top) {
+            let mut spans = vec![Span::styled(
+                format!("{:>width$} ", n + 1, width = number_width),
+                dim,
+            )];
+            spans.extend(
+                highlight(line)
+                    .into_iter()
+                    .map(|(token, part)| Span::styled(part, token_style(token))),
+            );
+            text.push(Line::from(spans));
+            if text.len() >= area.height as usize {
+                break;
+            }
+        }
+        f.render_widget(Paragraph::new(text).block(block), area);
+    }
+
+    fn token_style(token: Token) -> Style {
+        let style = Style::default();
+        match token {
+            Token::Plain => style,
+            Token::Keyword => style.fg(Color::Magenta),
+            Token::Type => style.fg(Color::Cyan),
+            Token::String => style.fg(Color::Green),
+            Token::Number => style.fg(Color::Yellow),
+            Token::Comment => style.fg(Color::DarkGray),
+            Token::Macro => style.fg(Color::Blue),
+            Token::Attribute => style.fg(Color::DarkGray),
+            Token::Lifetime => style.fg(Color::LightCyan),
+        }
+    }
+
+    /// Run `targets[index]` in the output pane, stopping whatever ran there before.
+    fn start_in_pane(
+        pane: &mut Option<OutputPane>,
+        manager: &Arc<ProcessManager>,
+        cli: &Cli,
+        targets: &[CargoTarget],
+        index: usize,
+        profile: Option<crate::e_config::RunProfile>,
+    ) -> anyhow::Result<()> {
+        if let Some(previous) = pane.as_mut() {
+            previous.kill(manager);
+        }
+        *pane = Some(OutputPane::launch(
+            manager,
+            cli,
+            &targets[index],
+            (index + 1, targets.len()),
+            profile,
+        )?);
+        Ok(())
+    }
+
+    /// Append a successful run to the TUI's `run_history.txt`.
+    fn record_run(history_path: &Path, name: &str) -> io::Result<()> {
+        use std::io::Write;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(history_path)?;
+        writeln!(file, "{}", name)
+    }
+
+    /// A target started from the TUI, its output streamed into the right-hand
+    /// pane through the capture threads' [`OutputSink`].
+    struct OutputPane {
+        target: CargoTarget,
+        /// `(n, of)` for [`ProcessManager::format_process_status`].
+        position: (usize, usize),
+        profile: Option<crate::e_config::RunProfile>,
+        sink: OutputSink,
+        pid: u32,
+        start_time: SystemTime,
+        manifest_backup: Option<String>,
+        /// Summary once the process has exited and its result was collected.
+        exit: Option<String>,
+        /// Lines scrolled back from the bottom; 0 follows new output.
+        scroll: usize,
+    }
+
+    impl OutputPane {
+        fn launch(
+            manager: &Arc<ProcessManager>,
+            cli: &Cli,
+            target: &CargoTarget,
+            position: (usize, usize),
+            profile: Option<crate::e_config::RunProfile>,
+        ) -> anyhow::Result<Self> {
+            let manifest_backup = maybe_patch_manifest_for_run(&target.manifest_path)?;
+            let sink = OutputSink::new();
+            // Output is always captured here; the pane is the only place it can go.
+            let mut builder = CargoCommandBuilder::new(
+                &target.name,
+                &target.manifest_path,
+                &cli.subcommand,
+                true,
+                cli.cached,
+                cli.default_binary_is_runner,
+                cli.quiet || cli.json_all_targets,
+                false,
+                cli.cwd_wsr,
+            )
+            .with_target(target)
+            .with_cli(cli)
+            .with_output_sink(sink.clone());
+            if let Some(profile) = &profile {
+                builder = builder.with_profile(profile);
+            }
+            let pid = Arc::new(builder).run(|pid, handle| {
+                manager.register(pid, handle);
+            })?;
+            let start_time = manager
+                .get(pid)
+                .and_then(|h| h.lock().ok().map(|h| h.start_time))
+                .unwrap_or_else(SystemTime::now);
+            Ok(OutputPane {
+                target: target.clone(),
+                position,
+                profile,
+                sink,
+                pid,
+                start_time,
+                manifest_backup,
+                exit: None,
+                scroll: 0,
+            })
+        }
+
+        fn is_running(&self) -> bool {
+            self.exit.is_none()
+        }
+
+        /// Collect the result once the process has exited. Returns whether it
+        /// succeeded, the first time only.
+        fn poll(&mut self, manager: &ProcessManager) -> Option<bool> {
+            if !self.is_running() || !matches!(manager.try_wait(self.pid), Ok(Some(_))) {
+                return None;
+            }
+            if let Some(original) = self.manifest_backup.take() {
+                let _ = fs::write(&self.target.manifest_path, original);
+            }
+            // `finish` records the result without printing over the TUI.
+            let (summary, success) = match manager.finish(self.pid) {
+                Ok(Some(result)) => {
+                    let code = result.exit_status.and_then(|s| s.code());
+                    let errors = result
+                        .diagnostics
+                        .iter()
+                        .filter(|d| d.level == "error")
+                        .count();
+                    let summary = format!(
+                        "[exited {}] {} in {}, {} error{}",
+                        code.map_or("-".to_string(), |c| c.to_string()),
+                        result.label(),
+                        crate::e_fmt::format_duration(result.elapsed_time.unwrap_or_default()),
+                        errors,
+                        if errors == 1 { "" } else { "s" }
+                    );
+                    (summary, code == Some(0))
+                }
+                Ok(None) => ("[exited]".to_string(), false),
+                Err(e) => (format!("[exited] {}", e), false),
+            };
+            self.sink.push(&summary);
+            self.exit = Some(summary);
+            Some(success)
+        }
+
+        /// Kill the process if it is running and wait briefly for it to exit.
+        fn kill(&mut self, manager: &ProcessManager) {
+            if !self.is_running() {
+                return;
+            }
+            if let Some(handle) = manager.get(self.pid) {
+                if let Ok(mut handle) = handle.lock() {
+                    let _ = handle.kill();
+                }
+            }
+            self.sink.push(&format!("[killed pid {}]", self.pid));
+            for _ in 0..50 {
+                if self.poll(manager).is_some() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+
+        /// Stop the process if needed and start the same target again.
+        fn restart(&mut self, manager: &Arc<ProcessManager>, cli: &Cli) -> anyhow::Result<()> {
+            self.kill(manager);
+            *self = OutputPane::launch(
+                manager,
+                cli,
+                &self.target,
+                self.position,
+                self.profile.clone(),
+            )?;
+            Ok(())
+        }
+
+        /// Phase, PID and elapsed time, or the exit summary.
+        fn status_line(&self, manager: &ProcessManager) -> String {
+            if let Some(exit) = &self.exit {
+                return exit.clone();
+            }
+            let built = manager
+                .get(self.pid)
+                .and_then(|h| {
+                    h.try_lock()
+                        .ok()
+                        .map(|h| h.stats.lock().unwrap().build_finished_time.is_some())
+                })
+                .unwrap_or(false);
+            let phase = if built { "running" } else { "building" };
+            let status = crate::e_fmt::strip_ansi(&ProcessManager::format_process_status(
+                self.pid,
+                Some(self.start_time),
+                &self.target,
+                self.position,
+            ));
+            let status = status.split_whitespace().collect::<Vec<_>>().join(" ");
+            if status.is_empty() {
+                format!("[{}] PID: {}", phase, self.pid)
+            } else {
+                format!("[{}] {}", phase, status)
+            }
+        }
+
+        fn render(
+            &self,
+            f: &mut ratatui::Frame,
+            area: Rect,
+            status_area: Rect,
+            manager: &ProcessManager,
+        ) {
+            let height = area.height.saturating_sub(2) as usize;
+            let text: Vec<Line> = self
+                .sink
+                .window(height, self.scroll)
+                .into_iter()
+                .map(Line::raw)
+                .collect();
+            let title = Line::from(vec![
+                Span::styled(
+                    format!(" {} ", self.target.display_name),
+                    Style::default().fg(Color::Green),
+                ),
+                Span::raw("k kill · r restart · [ ] scroll · c close "),
+            ]);
+            let pane =
+                Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(title));
+            f.render_widget(pane, area);
+            let color = match &self.exit {
+                None => Color::Yellow,
+                Some(s) if s.starts_with("[exited 0]") => Color::Green,
+                Some(_) => Color::Red,
+            };
+            f.render_widget(
+                Paragraph::new(self.status_line(manager)).style(Style::default().fg(color)),
+                status_area,
+            );
+        }
+    }
+
+    /// Marked targets run together through [`crate::e_runall::run_all_examples`]
+    /// on a worker thread, honoring `-J` and the `--run-all` timeout. Its output
+    /// goes to the right-hand pane; results are read back from the manager.
+    struct BatchRun {
+        /// Names of the targets in the batch.
+        names: HashSet<String>,
+        started: SystemTime,
+        sink: OutputSink,
+        worker: Option<thread::JoinHandle<anyhow::Result<bool>>>,
+        /// Lines scrolled back from the bottom; 0 follows new output.
+        scroll: usize,
+    }
+
+    impl BatchRun {
+        fn launch(manager: &Arc<ProcessManager>, cli: &Cli, targets: Vec<CargoTarget>) -> Self {
+            let sink = OutputSink::new();
+            let mut batch_cli = cli.clone();
+            // Output must be captured to reach the pane; a prebuild's cargo
+            // output would not be.
+            if !batch_cli.detached {
+                batch_cli.filter = true;
+            }
+            batch_cli.pre_build = false;
+            manager.clear_stop();
+            let names = targets.iter().map(|t| t.name.clone()).collect();
+            let worker = {
+                let manager = Arc::clone(manager);
+                let sink = sink.clone();
+                thread::spawn(move || {
+                    crate::e_eventdispatcher::set_output_sink(Some(sink));
+                    crate::e_runall::run_all_examples(manager, &batch_cli, &targets)
+                })
+            };
+            BatchRun {
+                names,
+                started: SystemTime::now(),
+                sink,
+                worker: Some(worker),
+                scroll: 0,
+            }
+        }
+
+        fn is_running(&self) -> bool {
+            self.worker.is_some()
+        }
+
+        /// Join the worker once it is done. True the first time only.
+        fn poll(&mut self) -> bool {
+            if !self.worker.as_ref().is_some_and(|w| w.is_finished()) {
+                return false;
+            }
+            let summary = match self.worker.take().map(|w| w.join()) {
+                Some(Ok(Ok(_))) => "[batch finished]".to_string(),
+                Some(Ok(Err(e))) => format!("[batch failed] {}", e),
+                _ => "[batch failed] worker panicked".to_string(),
+            };
+            self.sink.push(&summary);
+            true
+        }
+
+        /// Kill the running targets and start no more.
+        fn stop(&self, manager: &ProcessManager) {
+            if self.is_running() {
+                manager.request_stop();
+                self.sink.push("[stopping batch]");
+            }
+        }
+
+        /// Pass/fail and total time per target that finished in this batch.
+        fn outcomes(&self, manager: &ProcessManager) -> HashMap<String, (bool, Duration)> {
+            let mut outcomes: HashMap<String, (bool, Duration)> = HashMap::new();
+            for result in manager.results() {
+                if !self.names.contains(&result.target_name)
+                    || result.start_time.is_none_or(|t| t < self.started)
+                {
+                    continue;
+                }
+                let entry = outcomes
+                    .entry(result.target_name.clone())
+                    .or_insert((true, Duration::ZERO));
+                entry.0 &= !crate::e_reports::result_failed(&result);
+                entry.1 += result.elapsed_time.unwrap_or_default();
+            }
+            outcomes
+        }
+
+        fn render(
+            &self,
+            f: &mut ratatui::Frame,
+            area: Rect,
+            status_area: Rect,
+            outcomes: &HashMap<String, (bool, Duration)>,
+        ) {
+            let height = area.height.saturating_sub(2) as usize;
+            let text: Vec<Line> = self
+                .sink
+                .window(height, self.scroll)
+                .into_iter()
+                .map(Line::raw)
+                .collect();
+            let title = Line::from(vec![
+                Span::styled(
+                    format!(" batch of {} ", self.names.len()),
+                    Style::default().fg(Color::Green),
+                ),
+                Span::raw("k stop · [ ] scroll · c close "),
+            ]);
+            let pane =
+                Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(title));
+            f.render_widget(pane, area);
+            let failed = outcomes.values().filter(|(passed, _)| !passed).count();
+            let status = format!(
+                "[{}] {}/{} done, {} failed",
+                if self.is_running() {
+                    "running"
+                } else {
+                    "finished"
+                },
+                outcomes.len(),
+                self.names.len(),
+                failed
+            );
+            let color = if failed > 0 {
+                Color::Red
+            } else if self.is_running() {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            f.render_widget(
+                Paragraph::new(status).style(Style::default().fg(color)),
+                status_area,
+            );
+        }
+    }
+
+    /// Left part of the list title. The mouse handler uses its length to find
+    /// the EXIT hot spot, so both must build it the same way.
+    fn title_text(
+        total: usize,
+        query: &str,
+        searching: bool,
+        matched: usize,
+        marked: usize,
+    ) -> String {
+        let mut text = if query.is_empty() && !searching {
+            format!("Select target ({} found)", total)
+        } else {
+            format!(
+                "Select target /{}{} ({}/{} match)",
+                query,
+                if searching { "_" } else { "" },
+                matched,
+                total
+            )
+        };
+        if marked > 0 {
+            text.push_str(&format!(" [{} marked, Enter runs them]", marked));
+        }
+        text
+    }
+
+    /// Index into the target list of the highlighted row of the filtered view.
+    fn selected_index(list_state: &ListState, view: &[TargetMatch]) -> Option<usize> {
+        list_state
+            .selected()
+            .and_then(|row| view.get(row))
+            .map(|m| m.index)
+    }
+
+    /// `text` as spans, with the characters at `positions` highlighted.
+    fn highlighted(text: &str, positions: &[usize]) -> Vec<Span<'static>> {
+        let style = Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD);
+        let mut spans = Vec::new();
+        let mut run = String::new();
+        let mut run_matched = false;
+        for (i, c) in text.chars().enumerate() {
+            let matched = positions.contains(&i);
+            if matched != run_matched && !run.is_empty() {
+                let part = std::mem::take(&mut run);
+                spans.push(if run_matched {
+                    Span::styled(part, style)
+                } else {
+                    Span::raw(part)
+                });
+            }
+            run_matched = matched;
+            run.push(c);
+        }
+        if !run.is_empty() {
+            spans.push(if run_matched {
+                Span::styled(run, style)
+            } else {
+                Span::raw(run)
+            });
+        }
+        spans
+    }
+
     /// Reinitializes the terminal: enables raw mode, enters the alternate screen,
     /// enables mouse capture, clears the screen, and creates a new Terminal instance.
     /// This function updates the provided terminal reference.
@@ -506,19 +1369,43 @@ pub mod tui_interactive {
         Ok(())
     }
 
+    /// Returns the run profile chosen with `p` for `target`, if any.
+    fn selected_profile(
+        selected_profiles: &HashMap<String, usize>,
+        target: &CargoTarget,
+    ) -> Option<crate::e_config::RunProfile> {
+        let index = *selected_profiles.get(&target.name)?;
+        index.checked_sub(1).and_then(|i| {
+            crate::e_config::run_profiles(&target.name)
+                .into_iter()
+                .nth(i)
+        })
+    }
+
+    /// A target for [`run_piece`] and the run profile chosen for it, if any.
+    pub struct Piece<'a> {
+        pub target: &'a CargoTarget,
+        /// `(n, of)` for [`ProcessManager::format_process_status`].
+        pub position: (usize, usize),
+        pub profile: Option<&'a crate::e_config::RunProfile>,
+    }
+
     /// Runs the given example (or binary) target. It leaves TUI mode, spawns a cargo process,
     /// installs a Ctrl+C handler to kill the process, waits for it to finish, updates history,
     /// flushes stray input, and then reinitializes the terminal.
     pub fn run_piece(
         manager: Arc<crate::e_processmanager::ProcessManager>,
-        examples: &[CargoTarget],
-        index: usize,
+        piece: Piece<'_>,
         history_path: &Path,
         _run_history: &mut HashSet<String>,
         terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
         cli: &Cli,
     ) -> Result<(), Box<dyn Error>> {
-        let target = &examples[index];
+        let Piece {
+            target,
+            position,
+            profile,
+        } = piece;
         // Leave TUI mode before running the target.
         disable_raw_mode()?;
         execute!(
@@ -529,7 +1416,7 @@ pub mod tui_interactive {
         terminal.show_cursor()?;
 
         let manifest_path = PathBuf::from(target.manifest_path.clone());
-        let builder = CargoCommandBuilder::new(
+        let mut builder = CargoCommandBuilder::new(
             &target.name,
             &manifest_path,
             &cli.subcommand,
@@ -542,6 +1429,9 @@ pub mod tui_interactive {
         )
         .with_target(target)
         .with_cli(cli);
+        if let Some(profile) = profile {
+            builder = builder.with_profile(profile);
+        }
         let cmd = builder.build_command();
 
         // Set current directory appropriately.
@@ -689,12 +1579,8 @@ pub mod tui_interactive {
                 );
                 drop(system_guard);
                 // Refresh CPU usage to get actual value.
-                let status_display = ProcessManager::format_process_status(
-                    pid,
-                    Some(start_time),
-                    &target,
-                    (index + 1, examples.len()),
-                );
+                let status_display =
+                    ProcessManager::format_process_status(pid, Some(start_time), &target, position);
                 ProcessManager::update_status_line(&status_display, true).ok();
             }
         }
@@ -721,13 +1607,7 @@ pub mod tui_interactive {
         if !detached {
             // Only update run history if update_history is true and exit code is zero.
             if update_history && status_code == 0 {
-                use std::io::Write;
-                let mut file = fs::OpenOptions::new()
-                    .create(true)
-                    .append(true)
-                    .open(history_path)?;
-                // Append a newline-separated entry.
-                writeln!(file, "{}", target.name)?;
+                record_run(history_path, &target.name)?;
             }
             // let message = if cli.print_exit_code {
             //     format!("Exitcode {:?}. Press any key to continue...", status_code)
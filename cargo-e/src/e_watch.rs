@@ -0,0 +1,295 @@
+//! `cargo e --watch <target>`: rebuild and restart a target when its sources change.
+//!
+//! Watches the target's source file, its package's `src/` and its manifest.
+//! Bursts of file events are debounced and paths ignored by git are skipped.
+//! Each restart kills the running process through the [`ProcessManager`] and
+//! relaunches it with a fresh [`CargoCommandBuilder`], so filtered diagnostics
+//! and "listening on" detection work the same for every run.
+use crate::e_command_builder::CargoCommandBuilder;
+use crate::e_config::RunProfile;
+use crate::e_processmanager::ProcessManager;
+use crate::e_target::{CargoTarget, TargetOrigin};
+use crate::prelude::*;
+use crate::Cli;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+
+/// Quiet period that ends a burst of file events.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// How often the loop checks whether the running process exited.
+const POLL: Duration = Duration::from_millis(200);
+
+/// What to watch for one target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchSet {
+    pub manifest: PathBuf,
+    /// The package's `src/` directory.
+    pub src_dir: PathBuf,
+    /// The target's own source: a file, or the directory of a multi-file example.
+    pub source: Option<PathBuf>,
+}
+
+impl WatchSet {
+    pub fn for_target(target: &CargoTarget) -> Self {
+        // Watcher events carry absolute paths.
+        let manifest = fs::canonicalize(&target.manifest_path)
+            .unwrap_or_else(|_| target.manifest_path.clone());
+        let package_dir = manifest.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let source = match &target.origin {
+            Some(TargetOrigin::SingleFile(p))
+            | Some(TargetOrigin::DefaultBinary(p))
+            | Some(TargetOrigin::TomlSpecified(p)) => Some(p.clone()),
+            Some(TargetOrigin::MultiFile(p)) => Some(if p.is_dir() {
+                p.clone()
+            } else {
+                parent_or_self(p)
+            }),
+            Some(TargetOrigin::CargoMetadata { src_path, .. }) => Some(src_path.clone()),
+            Some(TargetOrigin::Plugin { reported, .. }) => Some(reported.clone()),
+            _ => None,
+        };
+        WatchSet {
+            manifest,
+            src_dir: package_dir.join("src"),
+            source: source.map(|p| {
+                let p = if p.is_relative() {
+                    package_dir.join(p)
+                } else {
+                    p
+                };
+                fs::canonicalize(&p).unwrap_or(p)
+            }),
+        }
+    }
+
+    /// Directories to register with the watcher. Files are watched through their
+    /// parent directory so editors that replace files on save are still seen.
+    fn roots(&self) -> Vec<(PathBuf, RecursiveMode)> {
+        let mut roots = vec![
+            (self.src_dir.clone(), RecursiveMode::Recursive),
+            (parent_or_self(&self.manifest), RecursiveMode::NonRecursive),
+        ];
+        if let Some(source) = &self.source {
+            if source.is_dir() {
+                roots.push((source.clone(), RecursiveMode::Recursive));
+            } else {
+                roots.push((parent_or_self(source), RecursiveMode::NonRecursive));
+            }
+        }
+        roots.retain(|(dir, _)| dir.is_dir());
+        roots.dedup();
+        roots
+    }
+
+    /// Whether a change to `path` should restart the target.
+    pub fn is_relevant(&self, path: &Path) -> bool {
+        path == self.manifest
+            || path.starts_with(&self.src_dir)
+            || self
+                .source
+                .as_ref()
+                .is_some_and(|s| path == s || (s.is_dir() && path.starts_with(s)))
+    }
+}
+
+fn parent_or_self(path: &Path) -> PathBuf {
+    path.parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+/// Skips paths that git ignores (build output, editor swap files listed in `.gitignore`).
+struct IgnoreFilter(Option<git2::Repository>);
+
+impl IgnoreFilter {
+    fn new(dir: &Path) -> Self {
+        IgnoreFilter(git2::Repository::discover(dir).ok())
+    }
+
+    fn is_ignored(&self, path: &Path) -> bool {
+        let Some(repo) = &self.0 else {
+            return path.components().any(|c| c.as_os_str() == "target");
+        };
+        let Some(workdir) = repo.workdir() else {
+            return false;
+        };
+        let workdir = fs::canonicalize(workdir).unwrap_or_else(|_| workdir.to_path_buf());
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        match path.strip_prefix(&workdir) {
+            Ok(relative) => repo.is_path_ignored(relative).unwrap_or(false),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Run `target`, then restart it on every relevant change until interrupted.
+pub fn watch_target(
+    manager: Arc<ProcessManager>,
+    cli: &Cli,
+    target: &CargoTarget,
+    profile: Option<&RunProfile>,
+) -> anyhow::Result<()> {
+    let set = WatchSet::for_target(target);
+    let ignore = IgnoreFilter::new(&parent_or_self(&set.manifest));
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res| {
+        let _ = tx.send(res);
+    })?;
+    for (dir, mode) in set.roots() {
+        watcher.watch(&dir, mode)?;
+        println!("[watch] watching {}", dir.display());
+    }
+
+    let mut running = Some(Running::launch(&manager, cli, target, profile)?);
+    loop {
+        match rx.recv_timeout(POLL) {
+            Ok(Ok(event)) => {
+                let mut changed = relevant_paths(&set, &ignore, event);
+                if changed.is_empty() {
+                    continue;
+                }
+                // Debounce: keep collecting until the events stop for a moment.
+                loop {
+                    match rx.recv_timeout(DEBOUNCE) {
+                        Ok(Ok(event)) => changed.extend(relevant_paths(&set, &ignore, event)),
+                        Ok(Err(e)) => log::warn!("watch error: {}", e),
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                    }
+                }
+                changed.sort();
+                changed.dedup();
+                for path in &changed {
+                    println!("[watch] changed: {}", path.display());
+                }
+                if let Some(run) = running.take() {
+                    run.stop(&manager);
+                }
+                println!("[watch] restarting {}", target.name);
+                running = Some(Running::launch(&manager, cli, target, profile)?);
+            }
+            Ok(Err(e)) => log::warn!("watch error: {}", e),
+            Err(RecvTimeoutError::Timeout) => {
+                if let Some(run) = running.as_mut() {
+                    if run.poll(&manager) {
+                        running = None;
+                        println!("[watch] waiting for changes to {}", target.name);
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
+fn relevant_paths(set: &WatchSet, ignore: &IgnoreFilter, event: notify::Event) -> Vec<PathBuf> {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return Vec::new();
+    }
+    event
+        .paths
+        .into_iter()
+        .filter(|p| set.is_relevant(p) && !ignore.is_ignored(p))
+        .collect()
+}
+
+/// One launch of the watched target.
+///
+/// The loop polls with [`ProcessManager::try_wait`] rather than blocking in
+/// [`ProcessManager::wait`], which holds the handle lock and would keep
+/// [`ProcessManager::kill_try_by_pid`] from reaching the process.
+struct Running {
+    pid: u32,
+    announced_listening: bool,
+}
+
+impl Running {
+    fn launch(
+        manager: &Arc<ProcessManager>,
+        cli: &Cli,
+        target: &CargoTarget,
+        profile: Option<&RunProfile>,
+    ) -> anyhow::Result<Self> {
+        let mut builder = CargoCommandBuilder::new(
+            &target.name,
+            &target.manifest_path,
+            &cli.subcommand,
+            cli.filter,
+            cli.cached,
+            cli.default_binary_is_runner,
+            cli.quiet || cli.json_all_targets,
+            cli.detached,
+            cli.cwd_wsr,
+        )
+        .with_target(target)
+        .with_required_features(&target.manifest_path, target)
+        .with_cli(cli);
+        if let Some(profile) = profile {
+            builder = builder.with_profile(profile);
+        }
+        let pid = Arc::new(builder).run(|pid, handle| {
+            manager.register(pid, handle);
+        })?;
+        Ok(Running {
+            pid,
+            announced_listening: false,
+        })
+    }
+
+    /// Report "listening on" and completion; returns true once the process has exited.
+    fn poll(&mut self, manager: &ProcessManager) -> bool {
+        if !self.announced_listening && self.is_listening(manager) {
+            self.announced_listening = true;
+            println!("[watch] pid {} is listening", self.pid);
+        }
+        match manager.try_wait(self.pid) {
+            Ok(Some(_)) => {
+                self.finish(manager);
+                true
+            }
+            Ok(None) => false,
+            Err(e) => {
+                log::debug!("[watch] {}", e);
+                false
+            }
+        }
+    }
+
+    fn is_listening(&self, manager: &ProcessManager) -> bool {
+        let Some(handle) = manager.get(self.pid) else {
+            return false;
+        };
+        let Ok(handle) = handle.try_lock() else {
+            return false;
+        };
+        let listening = handle
+            .stats
+            .lock()
+            .map(|s| s.listening_time.is_some())
+            .unwrap_or(false);
+        listening
+    }
+
+    /// Kill the process if it is still running and print the diagnostics it produced.
+    fn stop(self, manager: &ProcessManager) {
+        if !matches!(manager.try_wait(self.pid), Ok(Some(_))) {
+            if let Err(e) = manager.kill_try_by_pid(self.pid) {
+                eprintln!("[watch] failed to stop pid {}: {}", self.pid, e);
+            }
+        }
+        self.finish(manager);
+    }
+
+    /// Collect the result of the exited process; diagnostics stay on screen.
+    fn finish(&self, manager: &ProcessManager) {
+        match manager.wait(self.pid, None) {
+            Ok(result) => {
+                result.print_compact();
+                result.print_short();
+            }
+            Err(e) => eprintln!("[watch] {}", e),
+        }
+    }
+}
@@ -49,6 +49,7 @@ impl ExtContext {
         let threads = std::thread::available_parallelism()
             .map(|n| n.get())
             .unwrap_or(4);
+        crate::e_target_cache::set_refresh(self.cli.no_cache);
         let mut all = collect_all_targets(
             self.cli.manifest_path.clone(),
             self.cli.workspace,
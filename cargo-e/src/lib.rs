@@ -33,20 +33,36 @@ pub mod e_autosense;
 pub mod e_cargocommand_ext;
 pub mod e_collect;
 pub mod e_command_builder;
+pub mod e_config;
 pub mod e_diagnostics_dispatchers;
 pub mod e_discovery;
+pub mod e_editor;
 pub mod e_eventdispatcher;
+pub mod e_expect;
 pub mod e_features;
 pub mod e_fmt;
+pub mod e_fuzzy;
+#[cfg(feature = "uses_serde")]
+pub mod e_history;
 pub mod e_installer;
 pub mod e_prebuild;
+pub mod e_preview;
 pub mod e_processmanager;
 pub mod e_prompts;
+#[cfg(unix)]
+pub mod e_pty;
 pub mod e_reports;
+pub mod e_resources;
 pub mod e_runall;
 pub mod e_runner;
+#[cfg(feature = "uses_serde")]
+pub mod e_serve;
+pub mod e_snapshot;
 pub mod e_target;
+pub mod e_target_cache;
+pub mod e_terminal;
 pub mod e_tui;
+pub mod e_watch;
 pub mod e_workspace;
 use once_cell::sync::OnceCell;
 
@@ -55,6 +71,8 @@ pub static GLOBAL_TTS: OnceCell<std::sync::Mutex<tts::Tts>> = OnceCell::new();
 pub static GLOBAL_MANAGER: OnceCell<std::sync::Arc<e_processmanager::ProcessManager>> =
     OnceCell::new();
 pub static GLOBAL_CLI: OnceCell<Cli> = OnceCell::new();
+/// Configuration resolved from config files and the command line.
+pub static GLOBAL_CONFIG: OnceCell<e_config::ResolvedConfig> = OnceCell::new();
 /// A global set to track PIDs of ewindow processes.
 pub static GLOBAL_EWINDOW_PIDS: OnceCell<dashmap::DashMap<u32, u32>> = OnceCell::new();
 // Plugin system modules
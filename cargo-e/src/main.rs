@@ -26,12 +26,10 @@ use cargo_e::e_target::CargoTarget;
 use cargo_e::e_target::TargetKind;
 use cargo_e::prelude::*;
 use cargo_e::Cli;
-use clap::Parser;
 #[cfg(feature = "tui")]
 use crossterm::terminal::size;
 #[cfg(feature = "check-version-program-start")]
 use e_crate_version_checker::prelude::*;
-use futures::executor::block_on;
 use once_cell::sync::Lazy;
 #[cfg(feature = "uses_serde")]
 use serde_json::json;
@@ -166,7 +164,31 @@ pub fn main() -> anyhow::Result<()> {
 
     let (run_at_a_time, filtered_args) = custom_cli(&mut args);
 
-    let mut cli = Cli::parse_from(filtered_args);
+    let filtered_args: Vec<String> = filtered_args.into_iter().cloned().collect();
+    #[cfg(feature = "uses_plugins")]
+    if filtered_args.get(1).map(String::as_str) == Some("plugins") {
+        let (cli, config) =
+            cargo_e::e_config::parse_cli_with_config(vec![filtered_args[0].clone()]);
+        cargo_e::GLOBAL_CONFIG
+            .set(config)
+            .expect("Failed to set global config");
+        return cargo_e::plugins::manage::run(&cli, &filtered_args[1..]);
+    }
+    if filtered_args.get(1).map(String::as_str) == Some("bacon") {
+        return cargo_e::e_bacon::run(&filtered_args[1..]);
+    }
+    #[cfg(feature = "uses_serde")]
+    if filtered_args.get(1).map(String::as_str) == Some("history") {
+        return cargo_e::e_history::run(&filtered_args[1..]);
+    }
+    let (mut cli, config) = cargo_e::e_config::parse_cli_with_config(filtered_args);
+    if cli.print_config {
+        config.print();
+        exit(0);
+    }
+    cargo_e::GLOBAL_CONFIG
+        .set(config)
+        .expect("Failed to set global config");
     let log_path = cli.log.clone();
     setup_logging(log_path)?;
     if let Some(n) = run_at_a_time {
@@ -180,6 +202,23 @@ pub fn main() -> anyhow::Result<()> {
         .set(cli.clone())
         .expect("Failed to set global CLI");
 
+    if let Some(diag) = &cli.open {
+        #[cfg(feature = "uses_serde")]
+        return cargo_e::e_editor::last_run::open(diag, &cargo_e::e_editor::Editor::configured());
+        #[cfg(not(feature = "uses_serde"))]
+        anyhow::bail!("--open {} needs the uses_serde feature", diag);
+    }
+    if let Some(transport) = &cli.serve {
+        #[cfg(feature = "uses_serde")]
+        {
+            let manager = ProcessManager::new(&cli);
+            let ctx = cargo_e::ext::ExtContext::new(cli.clone(), manager)?;
+            return cargo_e::e_serve::serve(ctx, transport);
+        }
+        #[cfg(not(feature = "uses_serde"))]
+        anyhow::bail!("--serve {} needs the uses_serde feature", transport);
+    }
+
     let subcommand_provided_explicitly =
         args.iter().any(|arg| arg == "-s" || arg == "--subcommand");
 
@@ -430,11 +469,14 @@ pub fn main() -> anyhow::Result<()> {
         }
     }
     let manager = ProcessManager::new(&cli);
+    #[cfg(feature = "uses_plugins")]
+    cargo_e::plugins::hooks::install(&cli, manager.clone());
     // Control the maximum number of Cargo processes running concurrently.
     let num_threads = std::thread::available_parallelism()
         .map(|n| n.get())
         .unwrap_or(4);
     // Collect built-in Cargo targets
+    cargo_e::e_target_cache::set_refresh(cli.no_cache);
     #[allow(unused_mut)]
     let mut examples = cargo_e::e_collect::collect_all_targets(
         cli.manifest_path.clone(),
@@ -482,39 +524,44 @@ pub fn main() -> anyhow::Result<()> {
     {
         #[allow(unused_imports)]
         use cargo_e::e_target::{CargoTarget, TargetKind, TargetOrigin};
-        use cargo_e::plugins::plugin_api::{load_plugins, Target as PluginTarget};
         use std::path::PathBuf;
         let cwd = std::env::current_dir()?;
-        log::trace!("Invoking load_plugins() to discover plugins");
-        for plugin in load_plugins(&cli, manager.clone())? {
-            if plugin.matches(&cwd) {
-                let plugin_path = plugin.source().map(PathBuf::from).unwrap_or(cwd.clone());
-                for mut pt in plugin.collect_targets(&cwd)? {
-                    // If plugin provided a full CargoTarget, use it directly.
-                    if let Some(ct) = pt.cargo_target.take() {
-                        examples.push(ct);
-                    } else {
-                        let reported = pt
-                            .metadata
-                            .as_ref()
-                            .map(PathBuf::from)
-                            .unwrap_or_else(|| cwd.clone());
-                        examples.push(CargoTarget {
-                            name: pt.name.clone(),
-                            display_name: pt.name.clone(),
-                            manifest_path: cwd.clone(),
-                            kind: TargetKind::Plugin,
-                            extended: false,
-                            toml_specified: false,
-                            origin: Some(TargetOrigin::Plugin {
-                                plugin_path: plugin_path.clone(),
-                                reported,
-                            }),
-                        });
+        log::trace!("Collecting targets from the loaded plugins");
+        // The plugins loaded for the hooks; they live on the hook thread.
+        let collected = cargo_e::plugins::hooks::with_plugins(move |plugins| {
+            let mut collected = Vec::new();
+            for plugin in plugins {
+                if plugin.matches(&cwd) {
+                    let plugin_path = plugin.source().map(PathBuf::from).unwrap_or(cwd.clone());
+                    for mut pt in plugin.collect_targets(&cwd)? {
+                        // If plugin provided a full CargoTarget, use it directly.
+                        if let Some(ct) = pt.cargo_target.take() {
+                            collected.push(ct);
+                        } else {
+                            let reported = pt
+                                .metadata
+                                .as_ref()
+                                .map(PathBuf::from)
+                                .unwrap_or_else(|| cwd.clone());
+                            collected.push(CargoTarget {
+                                name: pt.name.clone(),
+                                display_name: pt.name.clone(),
+                                manifest_path: cwd.clone(),
+                                kind: TargetKind::Plugin,
+                                extended: false,
+                                toml_specified: false,
+                                origin: Some(TargetOrigin::Plugin {
+                                    plugin_path: plugin_path.clone(),
+                                    reported,
+                                }),
+                            });
+                        }
                     }
                 }
             }
-        }
+            anyhow::Ok(collected)
+        });
+        examples.extend(collected.transpose()?.unwrap_or_default());
     }
     use std::collections::HashSet;
 
@@ -588,10 +635,40 @@ pub fn main() -> anyhow::Result<()> {
         println!("{}", serde_json::to_string_pretty(&json_targets).unwrap());
         std::process::exit(0);
     }
-    if let Some(explicit) = cli.explicit_example.clone() {
-        if let Some(explicit_example) = cli.explicit_example.clone() {
+    if cli.watch && cli.explicit_example.is_none() {
+        eprintln!("error: --watch needs a target, e.g. `cargo e --watch my_example`");
+        exit(2);
+    }
+    if let Some(spec) = cli.explicit_example.clone() {
+        // `target@profile` selects a run profile from the config files.
+        let (explicit, profile_name) = cargo_e::e_config::split_profile(&spec);
+        let explicit = explicit.to_string();
+        let resolve_profile = |target_name: &str| {
+            profile_name.map(|name| {
+                cargo_e::e_config::run_profile(target_name, name).unwrap_or_else(|| {
+                    let available: Vec<String> = cargo_e::e_config::run_profiles(target_name)
+                        .iter()
+                        .map(|p| p.name.clone())
+                        .collect();
+                    eprintln!(
+                        "error: no profile '{}' for target '{}' (available: [{}])",
+                        name,
+                        target_name,
+                        available.join(", ")
+                    );
+                    std::process::exit(1);
+                })
+            })
+        };
+        // A name no target has is resolved against its fuzzy match below.
+        let profile = if examples.iter().any(|t| t.name == explicit) {
+            resolve_profile(&explicit)
+        } else {
+            None
+        };
+        {
             let mut explicit_lock = EXPLICIT.lock().unwrap();
-            *explicit_lock = explicit_example;
+            *explicit_lock = explicit.clone();
         }
         {
             let mut extra_lock = EXTRA_ARGS.lock().unwrap();
@@ -608,7 +685,7 @@ pub fn main() -> anyhow::Result<()> {
             if target.kind == TargetKind::Plugin {
                 #[cfg(feature = "uses_plugins")]
                 {
-                    use cargo_e::plugins::plugin_api::{load_plugins, Target as PluginTarget};
+                    use cargo_e::plugins::plugin_api::Target as PluginTarget;
                     // Find corresponding plugin and run in-process
                     let cwd = std::env::current_dir()?;
                     if let Some(origin) = &target.origin {
@@ -622,18 +699,36 @@ pub fn main() -> anyhow::Result<()> {
                                 metadata: Some(reported.to_string_lossy().to_string()),
                                 cargo_target: None,
                             };
-                            for plugin in load_plugins(&cli, manager.clone())? {
-                                if plugin.source().map(|s| PathBuf::from(s))
-                                    == Some(plugin_path.clone())
-                                {
-                                    // Delegate execution to run_with_manager
-                                    let result =
-                                        plugin.run_with_manager(manager.clone(), &cli, target)?;
-                                    if let Some(status) = result {
-                                        println!("Plugin exited with code: {:?}", status.code());
+                            let (plugin_path, run_cli, run_manager, run_target) = (
+                                plugin_path.clone(),
+                                cli.clone(),
+                                manager.clone(),
+                                target.clone(),
+                            );
+                            let ran = cargo_e::plugins::hooks::with_plugins(move |plugins| {
+                                for plugin in plugins {
+                                    if plugin.source().map(|s| PathBuf::from(s))
+                                        == Some(plugin_path.clone())
+                                    {
+                                        // Delegate execution to run_with_manager
+                                        let result = plugin.run_with_manager(
+                                            run_manager.clone(),
+                                            &run_cli,
+                                            &run_target,
+                                        )?;
+                                        if let Some(status) = result {
+                                            println!(
+                                                "Plugin exited with code: {:?}",
+                                                status.code()
+                                            );
+                                        }
+                                        return anyhow::Ok(true);
                                     }
-                                    return Ok(());
                                 }
+                                anyhow::Ok(false)
+                            });
+                            if ran.transpose()?.unwrap_or(false) {
+                                return Ok(());
                             }
                         }
                     }
@@ -643,10 +738,7 @@ pub fn main() -> anyhow::Result<()> {
                 if cli.tui {
                     do_tui_and_exit(manager, &cli, &unique_examples);
                 }
-                let ret = cargo_e::e_runner::run_example(manager.clone(), &cli, target)?;
-                manager.clone().generate_report(cli.gist);
-                manager.clone().cleanup();
-                std::process::exit(ret.map(|status| status.code().unwrap_or(1)).unwrap_or(1));
+                return run_target(manager.clone(), &cli, target, profile.as_ref());
             }
         }
         // If not found among examples, search for a binary with that name.
@@ -658,23 +750,25 @@ pub fn main() -> anyhow::Result<()> {
             if cli.tui {
                 do_tui_and_exit(manager, &cli, &unique_examples);
             }
-            let ret = cargo_e::e_runner::run_example(manager.clone(), &cli, target)?;
-            manager.clone().generate_report(cli.gist);
-            manager.clone().cleanup();
-            std::process::exit(ret.map(|status| status.code().unwrap_or(1)).unwrap_or(1));
+            return run_target(manager.clone(), &cli, target, profile.as_ref());
         } else {
             eprintln!(
                 "error: 0 named '{}' found in examples or binaries.",
                 explicit
             );
 
-            // no exact match found: perform a partial search over the unique examples.
-            let query = explicit.to_lowercase();
-            let fuzzy_matches: Vec<CargoTarget> = unique_examples
-                .iter()
-                .filter(|t| t.name.to_lowercase().contains(&query))
-                .cloned()
-                .collect();
+            // no exact match found: perform a fuzzy search over the unique examples, best first.
+            // With `name@profile`, only targets that have the profile are candidates.
+            let fuzzy_matches: Vec<CargoTarget> =
+                cargo_e::e_fuzzy::rank_targets_by_name(&explicit, &unique_examples)
+                    .into_iter()
+                    .map(|m| unique_examples[m.index].clone())
+                    .filter(|t| {
+                        profile_name.is_none_or(|name| {
+                            cargo_e::e_config::run_profile(&t.name, name).is_some()
+                        })
+                    })
+                    .collect();
             if fuzzy_matches.is_empty() {
                 std::process::exit(1);
             } else {
@@ -688,17 +782,14 @@ pub fn main() -> anyhow::Result<()> {
                         "Subcommand provided explicitly with 1 target.\nRunning {}...",
                         fuzzy_matches[0].name
                     );
-                    cargo_e::e_runner::run_example(manager.clone(), &cli, &fuzzy_matches[0])?;
-                    return Ok(());
+                    let profile = resolve_profile(&fuzzy_matches[0].name);
+                    return run_target(manager.clone(), &cli, &fuzzy_matches[0], profile.as_ref());
                 }
 
-                if cli.run_all != RunAll::NotSpecified {
+                if cli.run_all != RunAll::NotSpecified || cli.snapshot.is_some() {
                     //PROMPT cargo_e::e_prompts::prompt(&"", 2).ok();
                     // Pass in your default packages, which are now generic.
-                    cargo_e::e_runall::run_all_examples(manager.clone(), &cli, &fuzzy_matches)?;
-                    manager.generate_report(cli.gist);
-                    Arc::clone(&manager).cleanup();
-                    return Ok(());
+                    return run_all(manager.clone(), &cli, &fuzzy_matches);
                 }
 
                 #[cfg(feature = "tui")]
@@ -713,11 +804,8 @@ pub fn main() -> anyhow::Result<()> {
         }
     }
 
-    if cli.run_all != RunAll::NotSpecified {
-        cargo_e::e_runall::run_all_examples(manager.clone(), &cli, &unique_examples)?;
-        manager.generate_report(cli.gist);
-        manager.cleanup();
-        return Ok(());
+    if cli.run_all != RunAll::NotSpecified || cli.snapshot.is_some() {
+        return run_all(manager.clone(), &cli, &unique_examples);
     }
 
     if builtin_examples.len() == 1
@@ -777,6 +865,72 @@ pub fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// `--run-all`, then the report. With `--baseline`, exits non-zero if a target
+/// that passed at the baseline commit failed in this run.
+fn run_all(manager: Arc<ProcessManager>, cli: &Cli, targets: &[CargoTarget]) -> anyhow::Result<()> {
+    // Snapshot the history before this run appends to it.
+    #[cfg(feature = "uses_serde")]
+    let baseline = match &cli.baseline {
+        Some(git_ref) => Some((
+            cargo_e::e_history::ancestors_of(git_ref)?,
+            cargo_e::e_history::read(&cargo_e::e_history::default_history_path())?,
+        )),
+        None => None,
+    };
+    #[cfg(not(feature = "uses_serde"))]
+    if cli.baseline.is_some() {
+        eprintln!("--baseline needs the uses_serde feature; ignoring it.");
+    }
+    cargo_e::e_runall::run_all_examples(manager.clone(), cli, targets)?;
+    manager.generate_report(cli.gist);
+    #[cfg(feature = "uses_serde")]
+    let regressions = match baseline {
+        Some((shas, history)) => {
+            let current: Vec<_> = manager
+                .results()
+                .iter()
+                .map(|r| cargo_e::e_history::HistoryRecord::from_result(r, None))
+                .collect();
+            cargo_e::e_history::regressions(&history, &shas, &current)
+        }
+        None => Vec::new(),
+    };
+    manager.cleanup();
+    #[cfg(feature = "uses_serde")]
+    if !regressions.is_empty() {
+        eprintln!(
+            "{} target(s) failed that passed at {}:",
+            regressions.len(),
+            cli.baseline.as_deref().unwrap_or_default()
+        );
+        for r in &regressions {
+            cargo_e::e_history::print_regression(r);
+        }
+        exit(1);
+    }
+    Ok(())
+}
+
+/// Runs one named target, or keeps re-running it under `--watch`, then the report.
+/// A single run exits with the target's exit code.
+fn run_target(
+    manager: Arc<ProcessManager>,
+    cli: &Cli,
+    target: &CargoTarget,
+    profile: Option<&cargo_e::e_config::RunProfile>,
+) -> anyhow::Result<()> {
+    if cli.watch {
+        cargo_e::e_watch::watch_target(manager.clone(), cli, target, profile)?;
+        manager.generate_report(cli.gist);
+        manager.cleanup();
+        return Ok(());
+    }
+    let ret = cargo_e::e_runner::run_example_with_profile(manager.clone(), cli, target, profile)?;
+    manager.generate_report(cli.gist);
+    manager.cleanup();
+    std::process::exit(ret.map(|status| status.code().unwrap_or(1)).unwrap_or(1));
+}
+
 #[allow(dead_code)]
 fn do_tui_and_exit(manager: Arc<ProcessManager>, cli: &Cli, unique_examples: &[CargoTarget]) -> ! {
     #[cfg(feature = "tui")]
@@ -1120,6 +1274,15 @@ fn select_and_run_target_loop(
     println!("{}", &final_input);
     process_input(manager, &final_input, &combined, cli, 0)
 }
+
+/// Open `target` in the `--editor`/`$VISUAL`/`$EDITOR` editor, reporting failures.
+fn open_in_editor(target: &CargoTarget) {
+    let editor = cargo_e::e_editor::Editor::configured();
+    if let Err(e) = cargo_e::e_findmain::open_target_in_editor(target, &editor) {
+        eprintln!("error: failed to open editor: {}", e);
+    }
+}
+
 pub fn append_run_history(target_name: &str) -> io::Result<()> {
     let manifest_dir = cargo_e::e_manifest::find_manifest_dir()
         .unwrap_or_else(|_| std::env::current_dir().expect("Failed to get current directory"));
@@ -1196,9 +1359,7 @@ fn process_input(
             } else {
                 let (target_type, target) = &combined[abs_index];
                 println!("editing {} \"{}\"...", target_type, target.name);
-                // Call the appropriate function to open the target for editing.
-                // For example, if using VSCode:
-                block_on(cargo_e::e_findmain::open_vscode_for_sample(target));
+                open_in_editor(target);
                 // After editing, you might want to pause briefly or simply return to the menu.
                 Ok(LoopResult::Run(
                     <std::process::ExitStatus as process::ExitStatusExt>::from_raw(0),
@@ -1433,7 +1594,7 @@ fn handle_single_target(
             std::process::exit(0);
         }
         Some('e') => {
-            block_on(cargo_e::e_findmain::open_vscode_for_sample(target));
+            open_in_editor(target);
         }
         Some('i') => {
             futures::executor::block_on(crate::e_runner::open_ai_summarize_for_target(target));
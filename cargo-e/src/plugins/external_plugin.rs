@@ -1,21 +1,52 @@
+//! Executable plugins following the cargo-e-ext protocol.
+//!
+//! Any executable named `cargo-e-ext-*` on PATH or in a plugin directory is a
+//! candidate. See `documents/cargo-e-ext-protocol.md` for the versioned spec.
+use crate::e_cargocommand_ext::{CargoDiagnostic, CargoProcessResult};
+use crate::plugins::hooks::{self, BuildEvent};
+use crate::plugins::plugin_api::{CommandSpec, Plugin, Target};
 use anyhow::{anyhow, Context, Result};
-use serde_json;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use crate::plugin_api::{Plugin, Target};
-use crate::lua_plugin::CommandSpec;
+use std::process::{Command, Output, Stdio};
+use std::time::UNIX_EPOCH;
 
 // On Unix, check executable permission bits; on Windows, check for .exe extension
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 
+/// Version of the cargo-e-ext protocol implemented by this client.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Environment variable carrying [`PROTOCOL_VERSION`] to every plugin invocation.
+pub const PROTOCOL_ENV: &str = "CARGO_E_EXT_PROTOCOL";
+/// File name prefix that marks an executable as a cargo-e-ext plugin.
+pub const PLUGIN_PREFIX: &str = "cargo-e-ext-";
+
 /// An external CLI plugin following the cargo-e-ext plugin protocol.
 #[derive(Debug)]
 pub struct ExternalPlugin {
     path: PathBuf,
     name: String,
     version: String,
+    /// Lifecycle hooks the plugin declared via `--hooks`.
+    hooks: Vec<String>,
+}
+
+/// Handshake answers remembered per plugin executable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Handshake {
+    /// Size and mtime of the executable; a rebuilt plugin is asked again.
+    stamp: String,
+    client_version: String,
+    protocol: u32,
+    /// None if the plugin rejected this client.
+    name: Option<String>,
+    version: Option<String>,
+    #[serde(default)]
+    hooks: Vec<String>,
 }
 
 impl ExternalPlugin {
@@ -23,40 +54,174 @@ impl ExternalPlugin {
     /// Returns Ok(Some(plugin)) if the path is an executable CLI plugin
     /// that supports the current client version, else Ok(None).
     pub fn load(path: &Path) -> Result<Option<Self>> {
+        match default_cache_path() {
+            Some(cache) => Self::load_cached(path, &cache),
+            None => Self::handshake(path).map(|h| Self::from_handshake(path, h)),
+        }
+    }
+
+    /// Like [`ExternalPlugin::load`], remembering the `--name`/`--version` answers in `cache`.
+    pub fn load_cached(path: &Path, cache: &Path) -> Result<Option<Self>> {
         // Only consider executable files
         if !Self::is_executable(path) {
             return Ok(None);
         }
-        let path_buf = path.to_path_buf();
+        let key = fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .to_string_lossy()
+            .into_owned();
+        let stamp = stamp(path);
+        let mut entries: HashMap<String, Handshake> = fs::read_to_string(cache)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        if let Some(h) = entries.get(&key) {
+            if h.stamp == stamp
+                && h.client_version == env!("CARGO_PKG_VERSION")
+                && h.protocol == PROTOCOL_VERSION
+            {
+                log::trace!("Using cached handshake for {}", path.display());
+                return Ok(Self::from_handshake(path, h.clone()));
+            }
+        }
+        let h = Self::handshake(path)?;
+        entries.insert(key, h.clone());
+        if let Some(parent) = cache.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Err(e) = fs::write(cache, serde_json::to_string_pretty(&entries)?) {
+            log::warn!("Could not write plugin cache {}: {}", cache.display(), e);
+        }
+        Ok(Self::from_handshake(path, h))
+    }
+
+    fn from_handshake(path: &Path, h: Handshake) -> Option<Self> {
+        match (h.name, h.version) {
+            (Some(name), Some(version)) => Some(Self {
+                path: path.to_path_buf(),
+                name,
+                version,
+                hooks: h.hooks,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Run `--client-version`, `--name` and `--version` against the executable.
+    fn handshake(path: &Path) -> Result<Handshake> {
         // Ensure plugin supports this client version
         let client_version = env!("CARGO_PKG_VERSION");
-        let status = Command::new(&path_buf)
+        let mut h = Handshake {
+            stamp: stamp(path),
+            client_version: client_version.to_string(),
+            protocol: PROTOCOL_VERSION,
+            name: None,
+            version: None,
+            hooks: Vec::new(),
+        };
+        let status = Self::command(path)
             .arg("--client-version")
             .arg(client_version)
             .status()
-            .with_context(|| format!("failed to run plugin {} for client-version check", path_buf.display()))?;
+            .with_context(|| {
+                format!(
+                    "failed to run plugin {} for client-version check",
+                    path.display()
+                )
+            })?;
         if !status.success() {
-            return Ok(None);
+            return Ok(h);
         }
-        // Query plugin name
-        let output = Command::new(&path_buf)
-            .arg("--name")
+        // Query plugin name and version
+        h.name = Self::query(path, "--name")?;
+        h.version = Self::query(path, "--version")?;
+        // Hooks are optional; plugins that don't know `--hooks` declare none.
+        h.hooks = Self::query(path, "--hooks")
+            .ok()
+            .flatten()
+            .and_then(|s| serde_json::from_str::<Vec<String>>(&s).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|name| hooks::HOOK_NAMES.contains(&name.as_str()))
+            .collect();
+        Ok(h)
+    }
+
+    fn query(path: &Path, flag: &str) -> Result<Option<String>> {
+        let output = Self::command(path)
+            .arg(flag)
             .output()
-            .with_context(|| format!("failed to run plugin {} --name", path_buf.display()))?;
+            .with_context(|| format!("failed to run plugin {} {}", path.display(), flag))?;
         if !output.status.success() {
             return Ok(None);
         }
-        let name = String::from_utf8(output.stdout)?.trim().to_string();
-        // Query plugin version
-        let output_v = Command::new(&path_buf)
-            .arg("--version")
+        let answer = String::from_utf8(output.stdout)?.trim().to_string();
+        Ok(Some(answer).filter(|s| !s.is_empty()))
+    }
+
+    /// A command for the plugin with the protocol version in its environment.
+    fn command(path: &Path) -> Command {
+        let mut cmd = Command::new(path);
+        cmd.env(PROTOCOL_ENV, PROTOCOL_VERSION.to_string());
+        cmd
+    }
+
+    /// Run a protocol subcommand, failing with the plugin's stderr if it exits non-zero.
+    fn call(&self, args: &[&std::ffi::OsStr]) -> Result<Output> {
+        let sub = args[0].to_string_lossy();
+        let output = Self::command(&self.path)
+            .args(args)
             .output()
-            .with_context(|| format!("failed to run plugin {} --version", path_buf.display()))?;
-        if !output_v.status.success() {
+            .with_context(|| format!("failed to run plugin {} {}", self.path.display(), sub))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "plugin {} {} failed: {}",
+                self.name,
+                sub,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(output)
+    }
+
+    /// Run `hook <name>` with the JSON payload on stdin, if the plugin declared that hook.
+    fn call_hook(&self, hook: &str, payload: String) -> Result<Option<String>> {
+        if !self.hooks.iter().any(|h| h == hook) {
             return Ok(None);
         }
-        let version = String::from_utf8(output_v.stdout)?.trim().to_string();
-        Ok(Some(Self { path: path_buf, name, version }))
+        let mut child = Self::command(&self.path)
+            .arg("hook")
+            .arg(hook)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| {
+                format!("failed to run plugin {} hook {}", self.path.display(), hook)
+            })?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(payload.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "plugin {} hook {} failed: {}",
+                self.name,
+                hook,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(Some(String::from_utf8(output.stdout)?))
+    }
+
+    /// Lifecycle hooks the plugin declared.
+    pub fn hooks(&self) -> &[String] {
+        &self.hooks
+    }
+
+    /// The version string reported by the plugin.
+    pub fn version(&self) -> &str {
+        &self.version
     }
 
     #[cfg(unix)]
@@ -64,7 +229,7 @@ impl ExternalPlugin {
         if let Ok(meta) = fs::metadata(path) {
             let perm = meta.permissions();
             // any execute bit set
-            perm.mode() & 0o111 != 0
+            meta.is_file() && perm.mode() & 0o111 != 0
         } else {
             false
         }
@@ -72,56 +237,90 @@ impl ExternalPlugin {
 
     #[cfg(windows)]
     fn is_executable(path: &Path) -> bool {
-        path.is_file() && path.extension().map_or(false, |ext| ext.eq_ignore_ascii_case("exe"))
+        path.is_file()
+            && path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
     }
 }
 
+/// Default handshake cache: `$HOME/.cargo-e/ext-plugins.json`.
+fn default_cache_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(
+        PathBuf::from(home)
+            .join(".cargo-e")
+            .join("ext-plugins.json"),
+    )
+}
+
+fn stamp(path: &Path) -> String {
+    let meta = fs::metadata(path).ok();
+    let len = meta.as_ref().map(|m| m.len()).unwrap_or(0);
+    let mtime = meta
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("{}:{}", len, mtime)
+}
+
 impl Plugin for ExternalPlugin {
     fn name(&self) -> &str {
         &self.name
     }
 
     fn matches(&self, dir: &Path) -> bool {
-        match Command::new(&self.path).arg("matches").arg(dir).output() {
-            Ok(output) if output.status.success() => {
+        match self.call(&["matches".as_ref(), dir.as_os_str()]) {
+            Ok(output) => {
                 let s = String::from_utf8_lossy(&output.stdout);
                 let s = s.trim();
                 s == "true" || s == "1"
             }
-            _ => false,
+            Err(e) => {
+                log::trace!("{}", e);
+                false
+            }
         }
     }
 
     fn collect_targets(&self, dir: &Path) -> Result<Vec<Target>> {
-        let output = Command::new(&self.path)
-            .arg("collect-targets")
-            .arg(dir)
-            .output()
-            .with_context(|| format!("failed to run plugin {} collect-targets", self.path.display()))?;
-        if !output.status.success() {
-            return Err(anyhow!("plugin collect-targets failed"));
-        }
+        let output = self.call(&["collect-targets".as_ref(), dir.as_os_str()])?;
         let json = String::from_utf8(output.stdout)?;
-        let targets: Vec<Target> = serde_json::from_str(&json)?;
+        let targets: Vec<Target> = serde_json::from_str(&json)
+            .with_context(|| format!("plugin {} returned invalid targets JSON", self.name))?;
         Ok(targets)
     }
 
     fn build_command(&self, dir: &Path, target: &Target) -> Result<Command> {
-        let output = Command::new(&self.path)
-            .arg("build-command")
-            .arg(dir)
-            .arg(&target.name)
-            .output()
-            .with_context(|| format!("failed to run plugin {} build-command", self.path.display()))?;
-        if !output.status.success() {
-            return Err(anyhow!("plugin build-command failed"));
-        }
+        let output = self.call(&[
+            "build-command".as_ref(),
+            dir.as_os_str(),
+            target.name.as_ref(),
+        ])?;
         let json = String::from_utf8(output.stdout)?;
-        let spec: CommandSpec = serde_json::from_str(&json)?;
+        let spec: CommandSpec = serde_json::from_str(&json)
+            .with_context(|| format!("plugin {} returned invalid command JSON", self.name))?;
         Ok(spec.into_command(dir))
     }
 
     fn source(&self) -> Option<String> {
         Some(self.path.to_string_lossy().into_owned())
     }
-}
\ No newline at end of file
+
+    fn before_build(&self, event: &BuildEvent) -> Result<Vec<(String, String)>> {
+        hooks::via_json(|h, p| self.call_hook(h, p)).before_build(event)
+    }
+
+    fn on_diagnostic(&self, target: &str, diag: &CargoDiagnostic) -> Result<()> {
+        hooks::via_json(|h, p| self.call_hook(h, p)).on_diagnostic(target, diag)
+    }
+
+    fn after_build(&self, result: &mut CargoProcessResult) -> Result<()> {
+        hooks::via_json(|h, p| self.call_hook(h, p)).after_build(result)
+    }
+
+    fn on_exit(&self, results: &[CargoProcessResult]) -> Result<Option<String>> {
+        hooks::via_json(|h, p| self.call_hook(h, p)).on_exit(results)
+    }
+}
@@ -0,0 +1,346 @@
+//! Lifecycle hooks that let plugins react to runs.
+//!
+//! Plugins are not `Send`, so [`install`] loads them once, on a dedicated thread,
+//! and the hook functions below forward events to it; [`with_plugins`] runs other
+//! plugin work (collecting and running plugin targets) against the same set. When
+//! no plugins are installed every hook is a cheap no-op.
+//!
+//! Script and external plugins exchange hook payloads as JSON strings, the same
+//! way they return targets and command specs.
+use crate::e_cargocommand_ext::{CargoDiagnostic, CargoProcessResult};
+use crate::e_command_builder::CargoCommandBuilder;
+use crate::e_processmanager::ProcessManager;
+use crate::plugins::plugin_api::{load_plugins, Plugin};
+use crate::Cli;
+use anyhow::{Context, Result};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+
+/// Names of the hooks, as used by script functions and the external protocol.
+pub const HOOK_NAMES: [&str; 4] = ["before_build", "on_diagnostic", "after_build", "on_exit"];
+
+/// What is about to be built, passed to [`Plugin::before_build`].
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildEvent {
+    pub target: String,
+    pub manifest_path: String,
+    pub subcommand: String,
+    pub args: Vec<String>,
+}
+
+impl BuildEvent {
+    pub fn from_builder(builder: &CargoCommandBuilder) -> Self {
+        BuildEvent {
+            target: builder.target_name.clone(),
+            manifest_path: builder.manifest_path.to_string_lossy().into_owned(),
+            subcommand: builder.subcommand.clone(),
+            args: builder.args.clone(),
+        }
+    }
+}
+
+/// Plugin work sent to the hook thread by [`with_plugins`].
+type PluginWork = Box<dyn FnOnce(&[Box<dyn Plugin>]) + Send>;
+
+/// The plugins loaded by [`install`], owned by the hook thread.
+type Plugins = Rc<[Box<dyn Plugin>]>;
+
+enum HookCall {
+    BeforeBuild(BuildEvent, Sender<Vec<(String, String)>>),
+    Diagnostic(String, Box<CargoDiagnostic>),
+    AfterBuild(Box<CargoProcessResult>, Sender<CargoProcessResult>),
+    Exit(Vec<CargoProcessResult>, Sender<Vec<String>>),
+    With(PluginWork),
+}
+
+static HOST: OnceCell<Sender<HookCall>> = OnceCell::new();
+
+thread_local! {
+    /// The installed plugins, set on the hook thread only. Hooks raised by plugin work
+    /// running there (a plugin target's own build) are dispatched directly.
+    static LOCAL: RefCell<Option<Plugins>> = const { RefCell::new(None) };
+}
+
+/// Hand `call` to the installed plugins: directly on the hook thread, else through it.
+fn send(call: HookCall) -> bool {
+    if let Some(plugins) = LOCAL.with(|local| local.borrow().clone()) {
+        dispatch(&plugins, call);
+        return true;
+    }
+    HOST.get().is_some_and(|host| host.send(call).is_ok())
+}
+
+/// Send a call built around a reply channel and wait for the answer.
+fn request<R>(call: impl FnOnce(Sender<R>) -> HookCall) -> Option<R> {
+    let (tx, rx) = mpsc::channel();
+    if !send(call(tx)) {
+        return None;
+    }
+    rx.recv().ok()
+}
+
+/// Runs `f` with the plugins loaded by [`install`], on the thread that owns them, and
+/// returns its result; `None` when no plugins are loaded.
+///
+/// Hooks from other threads wait while `f` runs.
+pub fn with_plugins<R, F>(f: F) -> Option<R>
+where
+    R: Send + 'static,
+    F: FnOnce(&[Box<dyn Plugin>]) -> R + Send + 'static,
+{
+    HOST.get()?;
+    request(|tx| {
+        HookCall::With(Box::new(move |plugins| {
+            let _ = tx.send(f(plugins));
+        }))
+    })
+}
+
+/// Load plugins on the hook thread. Hooks stay disabled if none are found.
+pub fn install(cli: &Cli, manager: Arc<ProcessManager>) {
+    if HOST.get().is_some() {
+        return;
+    }
+    let (tx, rx) = mpsc::channel::<HookCall>();
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let cli = cli.clone();
+    let spawned = std::thread::Builder::new()
+        .name("cargo-e-hooks".into())
+        .spawn(move || {
+            let plugins = load_plugins(&cli, manager).unwrap_or_else(|e| {
+                log::warn!("Plugin hooks disabled: {}", e);
+                Vec::new()
+            });
+            let _ = ready_tx.send(!plugins.is_empty());
+            if plugins.is_empty() {
+                return;
+            }
+            let plugins: Plugins = plugins.into();
+            LOCAL.with(|local| *local.borrow_mut() = Some(plugins.clone()));
+            for call in rx {
+                dispatch(&plugins, call);
+            }
+        });
+    if spawned.is_ok() && ready_rx.recv().unwrap_or(false) {
+        let _ = HOST.set(tx);
+    }
+}
+
+fn dispatch(plugins: &[Box<dyn Plugin>], call: HookCall) {
+    let report = |plugin: &dyn Plugin, hook: &str, e: anyhow::Error| {
+        log::warn!("Plugin {} failed in {}: {}", plugin.name(), hook, e);
+    };
+    match call {
+        HookCall::BeforeBuild(event, reply) => {
+            let mut envs = Vec::new();
+            for plugin in plugins {
+                match plugin.before_build(&event) {
+                    Ok(mut more) => envs.append(&mut more),
+                    Err(e) => report(plugin.as_ref(), "before_build", e),
+                }
+            }
+            let _ = reply.send(envs);
+        }
+        HookCall::Diagnostic(target, diag) => {
+            for plugin in plugins {
+                if let Err(e) = plugin.on_diagnostic(&target, &diag) {
+                    report(plugin.as_ref(), "on_diagnostic", e);
+                }
+            }
+        }
+        HookCall::AfterBuild(result, reply) => {
+            let mut result = *result;
+            for plugin in plugins {
+                // Only the name, profile and plugin outcome are the plugin's to change.
+                let mut patched = result.clone();
+                match plugin.after_build(&mut patched) {
+                    Ok(()) => {
+                        result.target_name = patched.target_name;
+                        result.profile = patched.profile;
+                        result.annotations = patched.annotations;
+                        result.plugin_failure = patched.plugin_failure;
+                    }
+                    Err(e) => report(plugin.as_ref(), "after_build", e),
+                }
+            }
+            let _ = reply.send(result);
+        }
+        HookCall::Exit(results, reply) => {
+            let mut sections = Vec::new();
+            for plugin in plugins {
+                match plugin.on_exit(&results) {
+                    Ok(Some(section)) => sections.push(section),
+                    Ok(None) => {}
+                    Err(e) => report(plugin.as_ref(), "on_exit", e),
+                }
+            }
+            let _ = reply.send(sections);
+        }
+        HookCall::With(f) => f(plugins),
+    }
+}
+
+/// Environment variables plugins want set for this build.
+pub fn before_build(builder: &CargoCommandBuilder) -> Vec<(String, String)> {
+    if HOST.get().is_none() {
+        return Vec::new();
+    }
+    request(|tx| HookCall::BeforeBuild(BuildEvent::from_builder(builder), tx)).unwrap_or_default()
+}
+
+/// Forward a freshly parsed diagnostic; does not wait for the plugins.
+pub fn on_diagnostic(target: &str, diag: &CargoDiagnostic) {
+    if HOST.get().is_some() {
+        send(HookCall::Diagnostic(
+            target.to_string(),
+            Box::new(diag.clone()),
+        ));
+    }
+}
+
+/// Let plugins rename a finished run, change its profile, annotate it or fail it before
+/// it is recorded.
+pub fn after_build(result: CargoProcessResult) -> CargoProcessResult {
+    if HOST.get().is_none() {
+        return result;
+    }
+    let fallback = result.clone();
+    request(|tx| HookCall::AfterBuild(Box::new(result), tx)).unwrap_or(fallback)
+}
+
+/// Markdown sections plugins want appended to the run report.
+pub fn on_exit(results: &[CargoProcessResult]) -> Vec<String> {
+    if HOST.get().is_none() {
+        return Vec::new();
+    }
+    request(|tx| HookCall::Exit(results.to_vec(), tx)).unwrap_or_default()
+}
+
+/// JSON payload for `on_diagnostic`.
+pub fn diagnostic_json(target: &str, diag: &CargoDiagnostic) -> String {
+    json!({
+        "target": target,
+        "level": diag.level,
+        "message": diag.message,
+        "lineref": diag.lineref,
+        "error_code": diag.error_code,
+        "diag_number": diag.diag_number,
+    })
+    .to_string()
+}
+
+/// JSON summary of a run, used by `after_build` and `on_exit`.
+pub fn result_value(result: &CargoProcessResult) -> Value {
+    let count = |level: &str| {
+        result
+            .diagnostics
+            .iter()
+            .filter(|d| d.level == level)
+            .count()
+    };
+    let millis = |d: Option<std::time::Duration>| d.map(|d| d.as_millis() as u64);
+    json!({
+        "target_name": result.target_name,
+        "profile": result.profile,
+        "pid": result.pid,
+        "exit_code": result.exit_status.and_then(|s| s.code()),
+        "success": result.exit_status.map(|s| s.success()),
+        "is_could_not_compile": result.is_could_not_compile,
+        "errors": count("error"),
+        "warnings": count("warning"),
+        "elapsed_ms": millis(result.elapsed_time),
+        "build_elapsed_ms": millis(result.build_elapsed),
+        "runtime_elapsed_ms": millis(result.runtime_elapsed),
+        "annotations": result.annotations,
+        "plugin_failure": result.plugin_failure,
+    })
+}
+
+/// JSON payload for `on_exit`.
+pub fn results_json(results: &[CargoProcessResult]) -> String {
+    Value::Array(results.iter().map(result_value).collect()).to_string()
+}
+
+/// Parse a `before_build` answer: a JSON object of string values.
+pub fn parse_env(answer: &str) -> Result<Vec<(String, String)>> {
+    let map: std::collections::BTreeMap<String, String> =
+        serde_json::from_str(answer).context("before_build must return a JSON object")?;
+    Ok(map.into_iter().collect())
+}
+
+/// Apply an `after_build` answer: `target_name` and `profile` replace the run's,
+/// `annotations` (a string or an array of strings) are added to the report, and
+/// `fail` (a reason) makes the run count as failed. Other keys are ignored.
+pub fn apply_result_patch(result: &mut CargoProcessResult, answer: &str) -> Result<()> {
+    let patch: Value =
+        serde_json::from_str(answer).context("after_build must return a JSON object")?;
+    if let Some(name) = patch.get("target_name").and_then(Value::as_str) {
+        result.target_name = name.to_string();
+    }
+    match patch.get("profile") {
+        Some(Value::String(profile)) => result.profile = Some(profile.clone()),
+        Some(Value::Null) => result.profile = None,
+        _ => {}
+    }
+    match patch.get("annotations") {
+        Some(Value::String(note)) => result.annotations.push(note.clone()),
+        Some(Value::Array(notes)) => result
+            .annotations
+            .extend(notes.iter().filter_map(Value::as_str).map(str::to_string)),
+        _ => {}
+    }
+    if let Some(reason) = patch.get("fail").and_then(Value::as_str) {
+        result.plugin_failure = Some(reason.to_string());
+    }
+    Ok(())
+}
+
+/// Route the four hooks through a single `call(hook, json) -> Option<answer>` function.
+///
+/// Script and external plugins implement hooks this way; an empty answer means
+/// the hook has nothing to add.
+pub fn via_json<F>(call: F) -> JsonHooks<F>
+where
+    F: Fn(&str, String) -> Result<Option<String>>,
+{
+    JsonHooks(call)
+}
+
+pub struct JsonHooks<F>(F);
+
+impl<F> JsonHooks<F>
+where
+    F: Fn(&str, String) -> Result<Option<String>>,
+{
+    fn call(&self, hook: &str, payload: String) -> Result<Option<String>> {
+        Ok((self.0)(hook, payload)?.filter(|s| !s.trim().is_empty()))
+    }
+
+    pub fn before_build(&self, event: &BuildEvent) -> Result<Vec<(String, String)>> {
+        match self.call("before_build", serde_json::to_string(event)?)? {
+            Some(answer) => parse_env(&answer),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn on_diagnostic(&self, target: &str, diag: &CargoDiagnostic) -> Result<()> {
+        self.call("on_diagnostic", diagnostic_json(target, diag))
+            .map(|_| ())
+    }
+
+    pub fn after_build(&self, result: &mut CargoProcessResult) -> Result<()> {
+        if let Some(answer) = self.call("after_build", result_value(result).to_string())? {
+            apply_result_patch(result, &answer)?;
+        }
+        Ok(())
+    }
+
+    pub fn on_exit(&self, results: &[CargoProcessResult]) -> Result<Option<String>> {
+        self.call("on_exit", results_json(results))
+    }
+}
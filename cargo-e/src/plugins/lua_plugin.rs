@@ -1,4 +1,6 @@
+use crate::e_cargocommand_ext::{CargoDiagnostic, CargoProcessResult};
 use crate::e_processmanager::ProcessManager;
+use crate::plugins::hooks::{self, BuildEvent};
 use crate::plugins::plugin_api::CommandSpec;
 use crate::plugins::plugin_api::{Plugin, Target};
 use crate::Cli;
@@ -69,6 +71,19 @@ impl LuaPlugin {
             path: path.to_path_buf(),
         })
     }
+
+    /// Call the optional hook function `hook(json)` if the script defines it.
+    fn call_hook(&self, hook: &str, payload: String) -> Result<Option<String>> {
+        let Some(f) = self
+            .tbl
+            .get::<Option<Function>>(hook)
+            .map_err(|e| anyhow::anyhow!("Lua error getting '{}' function: {:?}", hook, e))?
+        else {
+            return Ok(None);
+        };
+        f.call::<Option<String>>(payload)
+            .map_err(|e| anyhow::anyhow!("Lua error calling {}: {:?}", hook, e))
+    }
 }
 
 impl Plugin for LuaPlugin {
@@ -120,6 +135,22 @@ impl Plugin for LuaPlugin {
         Some(self.path.to_string_lossy().into())
     }
 
+    fn before_build(&self, event: &BuildEvent) -> Result<Vec<(String, String)>> {
+        hooks::via_json(|h, p| self.call_hook(h, p)).before_build(event)
+    }
+
+    fn on_diagnostic(&self, target: &str, diag: &CargoDiagnostic) -> Result<()> {
+        hooks::via_json(|h, p| self.call_hook(h, p)).on_diagnostic(target, diag)
+    }
+
+    fn after_build(&self, result: &mut CargoProcessResult) -> Result<()> {
+        hooks::via_json(|h, p| self.call_hook(h, p)).after_build(result)
+    }
+
+    fn on_exit(&self, results: &[CargoProcessResult]) -> Result<Option<String>> {
+        hooks::via_json(|h, p| self.call_hook(h, p)).on_exit(results)
+    }
+
     /// Override in-process plugin run: call script-defined `run`, or fallback to external command.
     fn run(&self, dir: &Path, target: &Target) -> Result<Vec<String>> {
         let dir_str = dir.to_string_lossy().to_string();
@@ -0,0 +1,412 @@
+//! `cargo e plugins list|info|enable|disable|new`: inspect and manage plugins.
+use crate::e_processmanager::ProcessManager;
+use crate::plugins::plugin_api::{
+    discover_plugins, plugin_directories, plugin_key, PluginCandidate, PluginStatus,
+};
+use crate::Cli;
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "cargo e plugins",
+    about = "Inspect and manage cargo-e plugins."
+)]
+pub struct PluginsCli {
+    #[command(subcommand)]
+    pub command: Option<PluginsCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PluginsCommand {
+    /// Show the plugin search path and every plugin found, with its status (default).
+    List,
+    /// Show details for one plugin, by name or file name.
+    Info { name: String },
+    /// Enable a plugin in the project `.cargo-e.toml` (or the global config).
+    Enable {
+        name: String,
+        #[arg(long, help = "Write to ~/.cargo-e/config.toml instead.")]
+        global: bool,
+    },
+    /// Disable a plugin in the project `.cargo-e.toml` (or the global config).
+    Disable {
+        name: String,
+        #[arg(long, help = "Write to ~/.cargo-e/config.toml instead.")]
+        global: bool,
+    },
+    /// Write a working template plugin.
+    New {
+        name: String,
+        #[arg(long, value_enum)]
+        lang: PluginLang,
+        #[arg(
+            long,
+            help = "Directory to create the plugin in [default: .cargo-e/plugins]."
+        )]
+        dir: Option<PathBuf>,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginLang {
+    Lua,
+    Rhai,
+    Wasm,
+    Ext,
+}
+
+/// Entry point for `cargo e plugins ...`; `args[0]` is the `plugins` word itself.
+pub fn run(cli: &Cli, args: &[String]) -> Result<()> {
+    let parsed = PluginsCli::try_parse_from(args).unwrap_or_else(|e| e.exit());
+    match parsed.command.unwrap_or(PluginsCommand::List) {
+        PluginsCommand::List => list(cli),
+        PluginsCommand::Info { name } => info(cli, &name),
+        PluginsCommand::Enable { name, global } => set_enabled(cli, &name, true, global),
+        PluginsCommand::Disable { name, global } => set_enabled(cli, &name, false, global),
+        PluginsCommand::New { name, lang, dir } => {
+            let dir = match dir {
+                Some(dir) => dir,
+                None => std::env::current_dir()?.join(".cargo-e").join("plugins"),
+            };
+            for path in scaffold(&name, lang, &dir)? {
+                println!("created {}", path.display());
+            }
+            match lang {
+                PluginLang::Wasm => println!(
+                    "build it with `cargo build --release --target wasm32-wasip1` inside {}",
+                    dir.join(&name).display()
+                ),
+                PluginLang::Ext => println!(
+                    "install it with `cargo install --path {}` so cargo-e-ext-{} is on PATH",
+                    dir.join(format!("cargo-e-ext-{}", name)).display(),
+                    name
+                ),
+                _ => {}
+            }
+            Ok(())
+        }
+    }
+}
+
+fn candidates(cli: &Cli) -> Vec<PluginCandidate> {
+    discover_plugins(cli, ProcessManager::new(cli))
+}
+
+fn list(cli: &Cli) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    println!("plugin search path (earlier wins):");
+    for (i, dir) in plugin_directories().iter().enumerate() {
+        println!("  {}. {}", i + 1, dir.display());
+    }
+    println!("  then PATH (cargo-e-ext-* executables only)");
+    println!("plugins (matches checked against {}):", cwd.display());
+    let candidates = candidates(cli);
+    if candidates.is_empty() {
+        println!("  (none found)");
+    }
+    for c in &candidates {
+        let matches = match &c.plugin {
+            Some(plugin) if plugin.matches(&cwd) => "yes",
+            Some(_) => "no",
+            None => "-",
+        };
+        println!(
+            "  {:<20} {:<11} {:<8} matches={:<3} {}",
+            c.name.as_deref().unwrap_or("?"),
+            c.kind,
+            match &c.status {
+                PluginStatus::Loaded => "loaded",
+                PluginStatus::Disabled => "disabled",
+                PluginStatus::Overridden(_) => "shadowed",
+                PluginStatus::Skipped(_) => "skipped",
+            },
+            matches,
+            c.path.display()
+        );
+        match &c.status {
+            PluginStatus::Overridden(_) | PluginStatus::Skipped(_) => {
+                println!("  {:<20} {}", "", c.status)
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn info(cli: &Cli, name: &str) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let candidates = candidates(cli);
+    let found: Vec<_> = candidates
+        .iter()
+        .filter(|c| {
+            c.name.as_deref() == Some(name)
+                || c.path.file_stem().and_then(|s| s.to_str()) == Some(name)
+                || plugin_key(&c.path) == name
+        })
+        .collect();
+    if found.is_empty() {
+        bail!("no plugin named `{}`; see `cargo e plugins list`", name);
+    }
+    for c in found {
+        println!("{}", c.name.as_deref().unwrap_or(name));
+        println!("  kind:    {}", c.kind);
+        println!("  path:    {}", c.path.display());
+        println!("  status:  {}", c.status);
+        let state = crate::GLOBAL_CONFIG
+            .get()
+            .and_then(|config| config.plugins.get(&plugin_key(&c.path)));
+        match state {
+            Some((enabled, source)) => println!(
+                "  config:  {} [{}]",
+                if *enabled { "enabled" } else { "disabled" },
+                source
+            ),
+            None => println!("  config:  enabled [default]"),
+        }
+        let Some(plugin) = &c.plugin else {
+            continue;
+        };
+        let matches = plugin.matches(&cwd);
+        println!("  matches: {} ({})", matches, cwd.display());
+        if matches {
+            match plugin.collect_targets(&cwd) {
+                Ok(targets) => {
+                    println!("  targets: {}", targets.len());
+                    for t in targets {
+                        println!("    {}", t.name);
+                    }
+                }
+                Err(e) => println!("  targets: error: {}", e),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Plugins are enabled and disabled by file name (see [`plugin_key`]), so a disabled
+/// one is never loaded; a name a loaded plugin reports is mapped to its file.
+fn set_enabled(cli: &Cli, name: &str, enabled: bool, global: bool) -> Result<()> {
+    let key = candidates(cli)
+        .iter()
+        .find(|c| c.name.as_deref() == Some(name))
+        .map(|c| plugin_key(&c.path));
+    let name = key.as_deref().unwrap_or(name);
+    let path = crate::e_config::set_plugin_enabled(name, enabled, global)?;
+    println!(
+        "{} {} in {}",
+        if enabled { "enabled" } else { "disabled" },
+        name,
+        path.display()
+    );
+    Ok(())
+}
+
+/// Write the template for `lang` into `dir`, returning the files created.
+pub fn scaffold(name: &str, lang: PluginLang, dir: &Path) -> Result<Vec<PathBuf>> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!("plugin names may only contain letters, digits, `-` and `_`");
+    }
+    let files: Vec<(PathBuf, &str)> = match lang {
+        PluginLang::Lua => vec![(PathBuf::from(format!("{}.lua", name)), LUA_TEMPLATE)],
+        PluginLang::Rhai => vec![(PathBuf::from(format!("{}.rhai", name)), RHAI_TEMPLATE)],
+        PluginLang::Wasm => vec![
+            (Path::new(name).join("Cargo.toml"), WASM_CARGO_TEMPLATE),
+            (
+                Path::new(name).join("src").join("main.rs"),
+                WASM_MAIN_TEMPLATE,
+            ),
+        ],
+        PluginLang::Ext => {
+            let krate = PathBuf::from(format!("cargo-e-ext-{}", name));
+            vec![
+                (krate.join("Cargo.toml"), EXT_CARGO_TEMPLATE),
+                (krate.join("src").join("main.rs"), EXT_MAIN_TEMPLATE),
+            ]
+        }
+    };
+    let mut created = Vec::new();
+    for (rel, template) in files {
+        let path = dir.join(rel);
+        if path.exists() {
+            bail!("{} already exists", path.display());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, template.replace("{{name}}", name))
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        created.push(path);
+    }
+    Ok(created)
+}
+
+const LUA_TEMPLATE: &str = r###"-- cargo-e Lua plugin "{{name}}".
+-- Offers one target per line of `{{name}}.targets` in the project directory.
+local is_windows = package.config:sub(1, 1) == '\\'
+
+local function read_targets(dir)
+  local targets = {}
+  local f = io.open(dir .. "/{{name}}.targets")
+  if not f then return targets end
+  for line in f:lines() do
+    if line:match("%S") then table.insert(targets, line) end
+  end
+  f:close()
+  return targets
+end
+
+return {
+  name = "{{name}}",
+  matches = function(dir)
+    return #read_targets(dir) > 0
+  end,
+  collect_targets = function(dir)
+    local items = {}
+    for _, t in ipairs(read_targets(dir)) do
+      table.insert(items, string.format('{"name":%q,"metadata":null}', t))
+    end
+    return "[" .. table.concat(items, ",") .. "]"
+  end,
+  build_command = function(dir, target_name)
+    if is_windows then
+      return string.format('{"prog":"cmd","args":["/c","echo",%q],"cwd":null}', target_name)
+    end
+    return string.format('{"prog":"echo","args":[%q],"cwd":null}', target_name)
+  end,
+  -- Optional hooks take and return JSON strings; return nil to pass.
+  on_exit = function(results)
+    return "## {{name}}\n\nRun finished."
+  end,
+}
+"###;
+
+const RHAI_TEMPLATE: &str = r###"// cargo-e Rhai plugin "{{name}}".
+// Offers a single `hello` target in every directory.
+fn name() {
+    "{{name}}"
+}
+
+fn matches(dir) {
+    true
+}
+
+fn collect_targets(dir) {
+    "[{\"name\":\"hello\",\"metadata\":null}]"
+}
+
+fn build_command(dir, target) {
+    "{\"prog\":\"echo\",\"args\":[\"Hello from {{name}}: " + target + "\"],\"cwd\":null}"
+}
+
+// Optional hooks take and return JSON strings; return () to pass.
+fn on_exit(results) {
+    "## {{name}}\n\nRun finished."
+}
+"###;
+
+const WASM_CARGO_TEMPLATE: &str = r#"[package]
+name = "{{name}}"
+version = "0.1.0"
+edition = "2021"
+
+[package.metadata.wasm]
+target = "wasm32-wasip1"
+
+[dependencies]
+
+[workspace]
+"#;
+
+const WASM_MAIN_TEMPLATE: &str = r#"//! cargo-e WASM plugin "{{name}}" (WASI command).
+//!
+//! cargo-e runs this module in-process with the project directory preopened read-only.
+//! It matches directories containing `{{name}}.targets` and offers one target per line.
+use std::{env, fs, path::Path};
+
+fn targets(dir: &str) -> Vec<String> {
+    fs::read_to_string(Path::new(dir).join("{{name}}.targets"))
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["--name"] => println!("{{name}}"),
+        ["--matches", dir] => println!("{}", !targets(dir).is_empty()),
+        ["--collect_targets", dir] => {
+            let items: Vec<String> = targets(dir)
+                .iter()
+                .map(|t| format!("{{\"name\":{:?},\"metadata\":null}}", t))
+                .collect();
+            println!("[{}]", items.join(","));
+        }
+        ["--build_command", _dir, name] => {
+            println!("{{\"prog\":\"echo\",\"args\":[{:?}],\"cwd\":null}}", name)
+        }
+        _ => std::process::exit(1),
+    }
+}
+"#;
+
+const EXT_CARGO_TEMPLATE: &str = r#"[package]
+name = "cargo-e-ext-{{name}}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+
+[workspace]
+"#;
+
+const EXT_MAIN_TEMPLATE: &str = r#"//! cargo-e-ext plugin "{{name}}" (protocol v1).
+//!
+//! Matches directories containing `{{name}}.targets` and offers one target per line.
+use std::{env, fs, path::Path, process::exit};
+
+fn targets(dir: &str) -> Vec<String> {
+    fs::read_to_string(Path::new(dir).join("{{name}}.targets"))
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn main() {
+    if env::var("CARGO_E_EXT_PROTOCOL").as_deref() != Ok("1") {
+        exit(2);
+    }
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["--client-version", _] => {}
+        ["--name"] => println!("{{name}}"),
+        ["--version"] => println!("{}", env!("CARGO_PKG_VERSION")),
+        ["--hooks"] => println!("[]"),
+        ["matches", dir] => println!("{}", !targets(dir).is_empty()),
+        ["collect-targets", dir] => {
+            let items: Vec<String> = targets(dir)
+                .iter()
+                .map(|t| format!("{{\"name\":{:?},\"metadata\":null}}", t))
+                .collect();
+            println!("[{}]", items.join(","));
+        }
+        ["build-command", _dir, name] => {
+            println!("{{\"prog\":\"echo\",\"args\":[{:?}],\"cwd\":null}}", name)
+        }
+        _ => exit(1),
+    }
+}
+"#;
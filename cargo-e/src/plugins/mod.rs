@@ -3,6 +3,18 @@
 #[cfg(feature = "uses_plugins")]
 pub mod plugin_api;
 
+// `cargo e plugins` subcommand
+#[cfg(feature = "uses_plugins")]
+pub mod manage;
+
+// Lifecycle hooks dispatched to loaded plugins
+#[cfg(feature = "uses_plugins")]
+pub mod hooks;
+
+// Executable plugins speaking the cargo-e-ext protocol
+#[cfg(feature = "uses_plugins")]
+pub mod external_plugin;
+
 // Lua-based plugin
 #[cfg(all(feature = "uses_plugins", feature = "uses_lua"))]
 pub mod lua_plugin;
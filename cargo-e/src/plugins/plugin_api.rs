@@ -7,8 +7,11 @@ use std::{path::Path, process::Command};
 use toml;
 use walkdir::WalkDir;
 // Generic export plugin for Wasm/DLL exports (always available)
+use crate::e_cargocommand_ext::{CargoDiagnostic, CargoProcessResult};
 use crate::e_processmanager::ProcessManager;
 use crate::e_target::CargoTarget;
+use crate::plugins::external_plugin::{ExternalPlugin, PLUGIN_PREFIX};
+use crate::plugins::hooks::BuildEvent;
 #[cfg(feature = "uses_lua")]
 use crate::plugins::lua_plugin::LuaPlugin;
 #[cfg(feature = "uses_rhai")]
@@ -24,7 +27,7 @@ use std::sync::Arc;
 /// Returns the directories to search for plugins in precedence order:
 /// 1) development-time CARGO_MANIFEST_DIR/plugins
 /// 2) project-local .cargo-e/plugins in the current working directory
-fn plugin_directories() -> Vec<PathBuf> {
+pub fn plugin_directories() -> Vec<PathBuf> {
     let mut dirs = Vec::new();
     // 1. Development plugins from source tree (when running in the repo)
     let dev_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("plugins");
@@ -86,6 +89,35 @@ pub fn find_wasm_plugins() -> Vec<PathBuf> {
     wasm_paths
 }
 
+/// Finds `cargo-e-ext-*` executables in the plugin directories, then on PATH.
+/// The first executable found for a given file name wins.
+pub fn find_external_plugins() -> Vec<PathBuf> {
+    let mut dirs = plugin_directories();
+    if let Some(path) = std::env::var_os("PATH") {
+        dirs.extend(std::env::split_paths(&path));
+    }
+    let mut seen = std::collections::HashSet::new();
+    let mut found = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if !stem.starts_with(PLUGIN_PREFIX) || !path.is_file() {
+                continue;
+            }
+            if seen.insert(stem.to_string()) {
+                found.push(path);
+            }
+        }
+    }
+    found
+}
+
 // Allow construction of a plugin_api::Target directly from a CargoTarget
 impl From<crate::e_target::CargoTarget> for Target {
     fn from(ct: crate::e_target::CargoTarget) -> Self {
@@ -160,58 +192,247 @@ pub trait Plugin {
     ) -> Result<Option<ExitStatus>> {
         crate::e_runner::run_example(manager, cli, cargo_target)
     }
+    /// Hook: called before a target is built; returns environment variables to inject.
+    fn before_build(&self, _event: &BuildEvent) -> Result<Vec<(String, String)>> {
+        Ok(Vec::new())
+    }
+    /// Hook: called with each diagnostic as it is parsed.
+    fn on_diagnostic(&self, _target: &str, _diag: &CargoDiagnostic) -> Result<()> {
+        Ok(())
+    }
+    /// Hook: called when a run finishes, before its result is recorded; may change the
+    /// result's `target_name`, `profile`, `annotations` or `plugin_failure`. Other
+    /// changes are not kept.
+    fn after_build(&self, _result: &mut CargoProcessResult) -> Result<()> {
+        Ok(())
+    }
+    /// Hook: called when the run report is generated; returns a markdown section to append.
+    fn on_exit(&self, _results: &[CargoProcessResult]) -> Result<Option<String>> {
+        Ok(None)
+    }
 }
 
-/// Load all plugins by scanning supported script and WASM plugin directories.
-pub fn load_plugins(cli: &Cli, manager: Arc<ProcessManager>) -> Result<Vec<Box<dyn Plugin>>> {
-    let mut plugins: Vec<Box<dyn Plugin>> = Vec::new();
-    log::trace!(
-        "Initializing plugin loading; current dir = {:?}",
-        std::env::current_dir()?
-    );
-    let cwd = std::env::current_dir()?;
+/// Why a plugin candidate was or was not loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginStatus {
+    Loaded,
+    /// Disabled under `[plugins]` in the configuration.
+    Disabled,
+    /// An earlier candidate with the same name won.
+    Overridden(PathBuf),
+    /// The file could not be loaded as a plugin.
+    Skipped(String),
+}
+
+impl std::fmt::Display for PluginStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PluginStatus::Loaded => write!(f, "loaded"),
+            PluginStatus::Disabled => write!(f, "disabled"),
+            PluginStatus::Overridden(by) => write!(f, "overridden by {}", by.display()),
+            PluginStatus::Skipped(reason) => write!(f, "skipped: {}", reason),
+        }
+    }
+}
+
+/// A file found while scanning for plugins, and what became of it.
+pub struct PluginCandidate {
+    pub path: PathBuf,
+    /// "lua", "rhai", "ext", "wasm" or "wasm-export".
+    pub kind: &'static str,
+    /// Plugin name, once the plugin could be loaded.
+    pub name: Option<String>,
+    pub status: PluginStatus,
+    /// The plugin instance; kept for overridden plugins so they can be inspected.
+    pub plugin: Option<Box<dyn Plugin>>,
+}
 
-    // Load Lua and Rhai script plugins
+impl PluginCandidate {
+    fn skipped(path: PathBuf, kind: &'static str, reason: impl ToString) -> Self {
+        PluginCandidate {
+            path,
+            kind,
+            name: None,
+            status: PluginStatus::Skipped(reason.to_string()),
+            plugin: None,
+        }
+    }
+
+    /// A candidate disabled under `[plugins]`, which is left unloaded.
+    fn disabled(path: &Path, kind: &'static str) -> Option<Self> {
+        let key = plugin_key(path);
+        (!crate::e_config::plugin_enabled(&key)).then(|| PluginCandidate {
+            path: path.to_path_buf(),
+            kind,
+            name: Some(key),
+            status: PluginStatus::Disabled,
+            plugin: None,
+        })
+    }
+
+    fn loaded(path: PathBuf, kind: &'static str, plugin: Box<dyn Plugin>) -> Self {
+        PluginCandidate {
+            path,
+            kind,
+            name: Some(plugin.name().to_string()),
+            status: PluginStatus::Loaded,
+            plugin: Some(plugin),
+        }
+    }
+}
+
+/// The name a plugin file is enabled or disabled by under `[plugins]`: its file name
+/// without the extension, and for executables without the `cargo-e-ext-` prefix.
+pub fn plugin_key(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match stem.strip_prefix(PLUGIN_PREFIX) {
+        Some(name) => name.to_string(),
+        None => stem,
+    }
+}
+
+/// Scan every plugin location and report each candidate, loaded or not.
+///
+/// Candidates are visited in precedence order. Disabled plugins are not loaded; of the
+/// rest, the first loaded plugin with a given name wins and later ones are marked overridden.
+pub fn discover_plugins(cli: &Cli, manager: Arc<ProcessManager>) -> Vec<PluginCandidate> {
+    let mut candidates = Vec::new();
+    #[cfg(not(any(feature = "uses_lua", feature = "uses_rhai")))]
+    let _ = (cli, &manager);
+
+    // Lua and Rhai script plugins
     for base in plugin_directories() {
         log::trace!("Scanning plugin directory: {:?}", base);
-        if !base.is_dir() {
+        let Ok(entries) = fs::read_dir(&base) else {
             continue;
-        }
-        for entry in fs::read_dir(&base)? {
-            let path = entry?.path();
+        };
+        for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
             log::trace!("Found plugin candidate: {:?}", path);
-            if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
-                #[cfg(feature = "uses_lua")]
-                if ext == "lua" {
-                    log::trace!("Loading Lua plugin at {:?}", path);
-                    let plugin = LuaPlugin::load(&path, cli, manager.clone())?;
-                    plugins.push(Box::new(plugin));
+            let kind = match path.extension().and_then(|s| s.to_str()) {
+                Some("lua") => "lua",
+                Some("rhai") => "rhai",
+                _ => continue,
+            };
+            if let Some(candidate) = PluginCandidate::disabled(&path, kind) {
+                candidates.push(candidate);
+                continue;
+            }
+            match kind {
+                "lua" => {
+                    #[cfg(feature = "uses_lua")]
+                    candidates.push(match LuaPlugin::load(&path, cli, manager.clone()) {
+                        Ok(plugin) => PluginCandidate::loaded(path, "lua", Box::new(plugin)),
+                        Err(e) => PluginCandidate::skipped(path, "lua", e),
+                    });
+                    #[cfg(not(feature = "uses_lua"))]
+                    candidates.push(PluginCandidate::skipped(
+                        path,
+                        "lua",
+                        "built without the uses_lua feature",
+                    ));
                 }
-                #[cfg(feature = "uses_rhai")]
-                if ext == "rhai" {
-                    log::trace!("Loading Rhai plugin at {:?}", path);
-                    let plugin = RhaiPlugin::load(&path, cli, manager.clone())?;
-                    plugins.push(Box::new(plugin));
+                "rhai" => {
+                    #[cfg(feature = "uses_rhai")]
+                    candidates.push(match RhaiPlugin::load(&path, cli, manager.clone()) {
+                        Ok(plugin) => PluginCandidate::loaded(path, "rhai", Box::new(plugin)),
+                        Err(e) => PluginCandidate::skipped(path, "rhai", e),
+                    });
+                    #[cfg(not(feature = "uses_rhai"))]
+                    candidates.push(PluginCandidate::skipped(
+                        path,
+                        "rhai",
+                        "built without the uses_rhai feature",
+                    ));
                 }
+                _ => {}
             }
         }
     }
-    log::trace!("Loaded {} script plugins", plugins.len());
 
-    // Load WASM and export plugins
+    // Executable cargo-e-ext plugins
+    for ext_path in find_external_plugins() {
+        log::trace!("Trying external plugin at {}", ext_path.display());
+        if let Some(candidate) = PluginCandidate::disabled(&ext_path, "ext") {
+            candidates.push(candidate);
+            continue;
+        }
+        candidates.push(match ExternalPlugin::load(&ext_path) {
+            Ok(Some(ep)) => PluginCandidate::loaded(ext_path, "ext", Box::new(ep)),
+            Ok(None) => PluginCandidate::skipped(
+                ext_path,
+                "ext",
+                "not executable or rejected this cargo-e version",
+            ),
+            Err(e) => PluginCandidate::skipped(ext_path, "ext", e),
+        });
+    }
+
+    // WASM and export plugins
     #[cfg(feature = "uses_wasm")]
-    for wasm_path in find_wasm_plugins() {
-        log::trace!("Trying WASM plugin at {}", wasm_path.display());
-        if let Some(wp) = WasmPlugin::load(&wasm_path)? {
-            if wp.matches(&cwd) {
-                plugins.push(Box::new(wp));
+    if let Ok(cwd) = std::env::current_dir() {
+        for wasm_path in find_wasm_plugins() {
+            log::trace!("Trying WASM plugin at {}", wasm_path.display());
+            if let Some(candidate) = PluginCandidate::disabled(&wasm_path, "wasm") {
+                candidates.push(candidate);
                 continue;
             }
+            match WasmPlugin::load(&wasm_path) {
+                Ok(Some(wp)) if wp.matches(&cwd) => {
+                    candidates.push(PluginCandidate::loaded(wasm_path, "wasm", Box::new(wp)));
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => log::trace!("{} is not a WASI plugin: {}", wasm_path.display(), e),
+            }
+            candidates.push(match WasmExportPlugin::load(&wasm_path) {
+                Ok(Some(gp)) => PluginCandidate::loaded(wasm_path, "wasm-export", Box::new(gp)),
+                Ok(None) => PluginCandidate::skipped(wasm_path, "wasm-export", "no plugin exports"),
+                Err(e) => PluginCandidate::skipped(wasm_path, "wasm-export", e),
+            });
+        }
+    }
+    #[cfg(not(feature = "uses_wasm"))]
+    for wasm_path in find_wasm_plugins() {
+        candidates.push(PluginCandidate::skipped(
+            wasm_path,
+            "wasm",
+            "built without the uses_wasm feature",
+        ));
+    }
+
+    // Resolve name conflicts between the plugins that were loaded.
+    let mut winners: std::collections::HashMap<String, PathBuf> = Default::default();
+    for candidate in &mut candidates {
+        let (PluginStatus::Loaded, Some(name)) = (&candidate.status, &candidate.name) else {
+            continue;
+        };
+        match winners.get(name) {
+            Some(winner) => candidate.status = PluginStatus::Overridden(winner.clone()),
+            None => {
+                winners.insert(name.clone(), candidate.path.clone());
+            }
         }
-        if let Some(gp) = WasmExportPlugin::load(&wasm_path)? {
-            plugins.push(Box::new(gp));
+    }
+    candidates
+}
+
+/// Load all enabled plugins by scanning supported script, executable and WASM plugin locations.
+pub fn load_plugins(cli: &Cli, manager: Arc<ProcessManager>) -> Result<Vec<Box<dyn Plugin>>> {
+    log::trace!(
+        "Initializing plugin loading; current dir = {:?}",
+        std::env::current_dir()?
+    );
+    let mut plugins = Vec::new();
+    for candidate in discover_plugins(cli, manager) {
+        match candidate.status {
+            PluginStatus::Loaded => plugins.extend(candidate.plugin),
+            status => log::trace!("Not using {}: {}", candidate.path.display(), status),
         }
     }
+    log::trace!("Loaded {} plugins", plugins.len());
     Ok(plugins)
 }
 /// Manager for in-process plugin discovery and execution.
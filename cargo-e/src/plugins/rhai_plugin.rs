@@ -5,6 +5,11 @@
 //   fn matches(dir: String) -> bool
 //   fn collect_targets(dir: String) -> String  // JSON-encoded Targets array
 //   fn build_command(dir: String, target: String) -> String  // JSON CommandSpec
+// Optional lifecycle hooks (JSON in, JSON or markdown out, return () to pass):
+//   fn before_build(event: String) -> String  // JSON object of env vars to inject
+//   fn on_diagnostic(diag: String)
+//   fn after_build(result: String) -> String  // JSON patch: target_name, profile, annotations, fail
+//   fn on_exit(results: String) -> String  // markdown section for the run report
 // Optional in-process execution:
 //   fn <target>(dir: String, target: String) -> Array  // [exit_code, output...]
 //   fn run(dir: String, target: String) -> Array  // fallback if no per-target fn
@@ -18,9 +23,11 @@ use rhai::{Array, Engine, Scope, AST};
 // Import cargo-e library for target resolution
 // Reference the internal crate modules rather than the external crate name
 // Import target collection from the main library crate
+use crate::e_cargocommand_ext::{CargoDiagnostic, CargoProcessResult};
 use crate::e_collect::collect_all_targets_silent;
 use crate::e_processmanager::ProcessManager;
 use crate::e_target::CargoTarget;
+use crate::plugins::hooks::{self, BuildEvent};
 use crate::plugins::plugin_api::{CommandSpec, Plugin, Target};
 use crate::Cli;
 use serde_json;
@@ -118,6 +125,25 @@ impl RhaiPlugin {
             manager,
         })
     }
+
+    /// Call the optional hook function `hook(json)` if the script defines it.
+    fn call_hook(&self, hook: &str, payload: String) -> Result<Option<String>> {
+        if !self.ast.iter_functions().any(|f| f.name == hook) {
+            return Ok(None);
+        }
+        let mut scope = Scope::new();
+        let answer = self
+            .engine
+            .call_fn::<rhai::Dynamic>(&mut scope, &self.ast, hook, (payload,))
+            .map_err(|e| anyhow!("Rhai error calling {}: {:?}", hook, e))?;
+        if answer.is_unit() {
+            return Ok(None);
+        }
+        answer
+            .into_string()
+            .map(Some)
+            .map_err(|t| anyhow!("Rhai {} must return a string, got {}", hook, t))
+    }
 }
 
 impl Plugin for RhaiPlugin {
@@ -193,6 +219,22 @@ impl Plugin for RhaiPlugin {
     fn source(&self) -> Option<String> {
         Some(self.path.to_string_lossy().into())
     }
+
+    fn before_build(&self, event: &BuildEvent) -> Result<Vec<(String, String)>> {
+        hooks::via_json(|h, p| self.call_hook(h, p)).before_build(event)
+    }
+
+    fn on_diagnostic(&self, target: &str, diag: &CargoDiagnostic) -> Result<()> {
+        hooks::via_json(|h, p| self.call_hook(h, p)).on_diagnostic(target, diag)
+    }
+
+    fn after_build(&self, result: &mut CargoProcessResult) -> Result<()> {
+        hooks::via_json(|h, p| self.call_hook(h, p)).after_build(result)
+    }
+
+    fn on_exit(&self, results: &[CargoProcessResult]) -> Result<Option<String>> {
+        hooks::via_json(|h, p| self.call_hook(h, p)).on_exit(results)
+    }
     /// Override in-process plugin run: call script-defined `run`, or fallback to Cargo-e runner.
     fn run(&self, dir: &Path, target: &Target) -> Result<Vec<String>> {
         // 1. Try a per-target function matching the target name in the script
@@ -1,63 +1,155 @@
 use crate::plugins::plugin_api::{Plugin, Target};
 use anyhow::{bail, Context, Result};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use std::{path::Path, process::Command};
-use which::which;
+use wasmtime::{Config, Engine, Linker, Module, Store, Trap};
+use wasmtime_wasi::pipe::MemoryOutputPipe;
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{DirPerms, FilePerms, I32Exit, WasiCtxBuilder};
 
+/// Instructions a single plugin call may execute before it is stopped.
+const FUEL_LIMIT: u64 = 10_000_000_000;
+/// Interval between epoch ticks; the deadline below is counted in ticks.
+const EPOCH_TICK: Duration = Duration::from_millis(100);
+/// Wall-clock limit for a single plugin call, in epoch ticks (10 seconds).
+const EPOCH_DEADLINE: u64 = 100;
+/// Maximum bytes captured from the plugin's stdout and stderr.
+const OUTPUT_CAPACITY: usize = 4 * 1024 * 1024;
+
+/// A WASI preview1 command module run in-process with wasmtime.
+///
+/// The module is called like a CLI (`--matches <dir>`, `--collect_targets <dir>`,
+/// `--build_command <dir> <name>`) and answers on stdout. The project directory is
+/// preopened read-only and every call is bounded by fuel and an epoch deadline.
 pub struct WasmPlugin {
-    path: std::path::PathBuf,
-    wasmtime_path: PathBuf,
+    path: PathBuf,
+    name: String,
+    engine: Engine,
+    module: Module,
 }
 
 impl WasmPlugin {
-    /// Attempt to locate `wasmtime`; if found, return a WasmPlugin, else skip
+    /// Compile the module; returns None if it is not a WASI command (no `_start` export),
+    /// so the caller can fall back to the export plugin.
     pub fn load(path: &Path) -> Result<Option<Self>> {
-        match which("wasmtime") {
-            Ok(wasmtime_path) => Ok(Some(Self {
-                path: path.to_path_buf(),
-                wasmtime_path,
-            })),
-            Err(_) => {
-                eprintln!(
-                    "[warn] wasmtime not found in PATH — skipping wasm plugin: {}",
-                    path.display()
-                );
-                Ok(None)
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        config.epoch_interruption(true);
+        let engine = Engine::new(&config)?;
+        let module = Module::from_file(&engine, path)
+            .with_context(|| format!("Failed to compile WASM plugin {}", path.display()))?;
+        if module.get_export("_start").is_none() {
+            log::trace!(
+                "{} has no _start export; not a WASI command",
+                path.display()
+            );
+            return Ok(None);
+        }
+        let mut plugin = Self {
+            path: path.to_path_buf(),
+            name: "wasm-plugin".to_string(),
+            engine,
+            module,
+        };
+        // Plugins may report their own name; older ones reject `--name`.
+        let cwd = std::env::current_dir()?;
+        if let Ok(name) = plugin.run_wasm(&["--name"], &cwd) {
+            let name = name.trim();
+            if !name.is_empty() && !name.contains('\n') {
+                plugin.name = name.to_string();
             }
         }
+        Ok(Some(plugin))
     }
 
-    // fn run_wasm(&self, args: &[&str]) -> Result<String> {
-    //     let output = Command::new(&self.wasmtime_path)
-    //     .arg("--dir").arg(".") // allow access to current dir
-    //         .arg(&self.path)
-    //         .args(args)
-    //         .output()?;
-    //     if !output.status.success() {
-    //         return Err(anyhow::anyhow!(
-    //             "WASM plugin error: {}",
-    //             String::from_utf8_lossy(&output.stderr)
-    //         ));
-    //     }
-    //     Ok(String::from_utf8(output.stdout)?)
-    // }
-    /// Run the Wasm plugin via Wasmtime, preopening `dir` for WASI, passing `args` to the module
+    /// Run the module's `_start` with `args`, preopening `dir` read-only, and return its stdout.
     fn run_wasm(&self, args: &[&str], dir: &Path) -> Result<String> {
-        let output = Command::new(&self.wasmtime_path)
-            .arg("--dir")
-            .arg(dir)
-            .arg(&self.path)
-            .args(args)
-            .output()?;
-        if !output.status.success() {
-            eprintln!("[wasm stderr] {}", String::from_utf8_lossy(&output.stderr));
-            bail!(
-                "WASM plugin error: {}",
-                String::from_utf8_lossy(&output.stderr)
-            );
+        let program = self
+            .path
+            .file_name()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "plugin.wasm".into());
+        let argv: Vec<String> = std::iter::once(program)
+            .chain(args.iter().map(|s| s.to_string()))
+            .collect();
+
+        let stdout = MemoryOutputPipe::new(OUTPUT_CAPACITY);
+        let stderr = MemoryOutputPipe::new(OUTPUT_CAPACITY);
+        let wasi = WasiCtxBuilder::new()
+            .args(&argv)
+            .stdout(stdout.clone())
+            .stderr(stderr.clone())
+            // The guest sees the directory under the same path the host passes in `args`.
+            .preopened_dir(dir, dir.to_string_lossy(), DirPerms::READ, FilePerms::READ)?
+            .build_p1();
+
+        let mut linker: Linker<WasiP1Ctx> = Linker::new(&self.engine);
+        preview1::add_to_linker_sync(&mut linker, |ctx| ctx)?;
+        let mut store = Store::new(&self.engine, wasi);
+        store.set_fuel(FUEL_LIMIT)?;
+        store.set_epoch_deadline(EPOCH_DEADLINE);
+
+        let done = Arc::new(AtomicBool::new(false));
+        let ticker = {
+            let engine = self.engine.clone();
+            let done = Arc::clone(&done);
+            std::thread::spawn(move || {
+                while !done.load(Ordering::Relaxed) {
+                    std::thread::sleep(EPOCH_TICK);
+                    engine.increment_epoch();
+                }
+            })
+        };
+        let result = linker
+            .instantiate(&mut store, &self.module)
+            .and_then(|instance| instance.get_typed_func::<(), ()>(&mut store, "_start"))
+            .and_then(|start| start.call(&mut store, ()));
+        done.store(true, Ordering::Relaxed);
+        let _ = ticker.join();
+
+        let err_text = String::from_utf8_lossy(&stderr.contents())
+            .trim()
+            .to_string();
+        if !err_text.is_empty() {
+            log::trace!("WASM stderr ({}): {}", self.path.display(), err_text);
         }
+        if let Err(e) = result {
+            let exit_code = e.downcast_ref::<I32Exit>().map(|exit| exit.0);
+            match (exit_code, e.downcast_ref::<Trap>()) {
+                (Some(0), _) => {}
+                (Some(code), _) => bail!(
+                    "WASM plugin {} exited with code {}: {}",
+                    self.path.display(),
+                    code,
+                    err_text
+                ),
+                (None, Some(Trap::OutOfFuel)) => bail!(
+                    "WASM plugin {} exceeded its fuel limit of {} instructions",
+                    self.path.display(),
+                    FUEL_LIMIT
+                ),
+                (None, Some(Trap::Interrupt)) => bail!(
+                    "WASM plugin {} timed out after {:?}",
+                    self.path.display(),
+                    EPOCH_TICK * EPOCH_DEADLINE as u32
+                ),
+                (None, _) => {
+                    return Err(e.context(format!(
+                        "WASM plugin {} trapped: {}",
+                        self.path.display(),
+                        err_text
+                    )))
+                }
+            }
+        }
+
         // Capture and trim stdout from the WASM module
-        let out = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let out = String::from_utf8_lossy(&stdout.contents())
+            .trim()
+            .to_string();
         // Use structured logging to trace plugin output without polluting stdout
         log::trace!("WASM stdout ({}): {}", self.path.display(), out);
         Ok(out)
@@ -66,7 +158,7 @@ impl WasmPlugin {
 
 impl Plugin for WasmPlugin {
     fn name(&self) -> &str {
-        "wasm-plugin"
+        &self.name
     }
 
     fn matches(&self, dir: &Path) -> bool {
@@ -0,0 +1,67 @@
+//! Minimal cargo-e-ext protocol v1 plugin used by `tests/test_external_plugin.rs`.
+//!
+//! Matches any directory containing `fixture.txt`; each non-empty line of that
+//! file is a target, run with `echo <name>`. Declares the `before_build` and
+//! `on_exit` hooks.
+use std::io::{Read, Write};
+use std::{env, fs, path::Path, process::exit};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Ok(log) = env::var("CARGO_E_EXT_FIXTURE_LOG") {
+        if let Ok(mut f) = fs::OpenOptions::new().create(true).append(true).open(log) {
+            let _ = writeln!(f, "{}", args.join(" "));
+        }
+    }
+    if env::var("CARGO_E_EXT_PROTOCOL").as_deref() != Ok("1") {
+        eprintln!("unsupported protocol");
+        exit(2);
+    }
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["--client-version", _] => {}
+        ["--name"] => println!("fixture"),
+        ["--version"] => println!("0.1.0"),
+        ["--hooks"] => println!("[\"before_build\",\"on_exit\"]"),
+        ["hook", "before_build"] => {
+            let mut event = String::new();
+            let _ = std::io::stdin().read_to_string(&mut event);
+            println!("{{\"FIXTURE_EVENT_BYTES\":\"{}\"}}", event.len());
+        }
+        ["hook", "on_exit"] => println!("## fixture\n\nrun finished"),
+        ["matches", dir] => println!("{}", Path::new(dir).join("fixture.txt").is_file()),
+        ["collect-targets", dir] => {
+            let names = targets(dir);
+            let items: Vec<String> = names
+                .iter()
+                .map(|n| format!("{{\"name\":{:?},\"metadata\":null}}", n))
+                .collect();
+            println!("[{}]", items.join(","));
+        }
+        ["build-command", dir, name] => {
+            if !targets(dir).iter().any(|t| t == name) {
+                eprintln!("unknown target {}", name);
+                exit(1);
+            }
+            println!("{{\"prog\":\"echo\",\"args\":[{:?}],\"cwd\":null}}", name);
+        }
+        _ => {
+            eprintln!("usage: cargo-e-ext-fixture <command>");
+            exit(1);
+        }
+    }
+}
+
+fn targets(dir: &str) -> Vec<String> {
+    fs::read_to_string(Path::new(dir).join("fixture.txt"))
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect()
+}
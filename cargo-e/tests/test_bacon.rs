@@ -0,0 +1,134 @@
+use cargo_e::e_bacon::{generate, job_names, package_job_names};
+use cargo_e::e_target::{CargoTarget, TargetKind, TargetOrigin};
+use std::fs;
+
+#[test]
+fn generate_writes_jobs_per_example_and_binary() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[features]\ngpu = []\n\n\
+         [[example]]\nname = \"fancy\"\nrequired-features = [\"gpu\"]\n",
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("member")).unwrap();
+    fs::write(
+        root.join("member").join("Cargo.toml"),
+        "[package]\nname = \"member\"\n",
+    )
+    .unwrap();
+
+    let target = |name: &str, kind: TargetKind, manifest: &str| CargoTarget {
+        name: name.to_string(),
+        display_name: name.to_string(),
+        manifest_path: root.join(manifest),
+        kind,
+        extended: false,
+        toml_specified: false,
+        origin: None,
+    };
+    let targets = vec![
+        target("fancy", TargetKind::Example, "Cargo.toml"),
+        target("tool", TargetKind::Binary, "member/Cargo.toml"),
+        target("bench_me", TargetKind::Bench, "Cargo.toml"),
+    ];
+    assert!(job_names(&targets[2]).is_none());
+
+    let contents = generate(&targets, root);
+    let parsed: toml::Table = contents.parse().unwrap();
+    let jobs = parsed["jobs"].as_table().unwrap();
+    assert_eq!(jobs.len(), 6);
+
+    let command = |job: &str| -> Vec<String> {
+        jobs[job]["command"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(
+        command("run-example-fancy"),
+        ["cargo", "run", "--example", "fancy", "--features", "gpu"]
+    );
+    assert_eq!(
+        jobs["run-example-fancy"]["need_stdout"].as_bool(),
+        Some(true)
+    );
+    assert_eq!(
+        command("clippy-bin-tool"),
+        [
+            "cargo",
+            "clippy",
+            "--bin",
+            "tool",
+            "--manifest-path",
+            "member/Cargo.toml",
+            "--color",
+            "always"
+        ]
+    );
+    assert!(jobs.contains_key("check-bin-tool"));
+}
+
+#[test]
+fn colliding_names_get_the_package_in_the_job_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    for package in ["alpha", "beta"] {
+        fs::create_dir_all(root.join(package)).unwrap();
+        fs::write(
+            root.join(package).join("Cargo.toml"),
+            format!("[package]\nname = \"{package}-pkg\"\nversion = \"0.1.0\"\n"),
+        )
+        .unwrap();
+    }
+
+    let target = |name: &str, manifest: &str| CargoTarget {
+        name: name.to_string(),
+        display_name: name.to_string(),
+        manifest_path: root.join(manifest),
+        kind: TargetKind::Example,
+        extended: false,
+        toml_specified: false,
+        origin: Some(TargetOrigin::SingleFile(root.join(name))),
+    };
+    let targets = vec![
+        target("demo", "alpha/Cargo.toml"),
+        target("demo", "beta/Cargo.toml"),
+        target("solo", "beta/Cargo.toml"),
+    ];
+    assert_eq!(
+        package_job_names(&targets[0]).unwrap().run,
+        "run-example-alpha-pkg-demo"
+    );
+
+    let parsed: toml::Table = generate(&targets, root).parse().unwrap();
+    let jobs = parsed["jobs"].as_table().unwrap();
+    assert_eq!(jobs.len(), 9);
+    assert!(!jobs.contains_key("run-example-demo"));
+    assert!(jobs.contains_key("run-example-solo"));
+    for (package, manifest) in [
+        ("alpha-pkg", "alpha/Cargo.toml"),
+        ("beta-pkg", "beta/Cargo.toml"),
+    ] {
+        let command: Vec<&str> = jobs[&format!("run-example-{package}-demo")]["command"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap())
+            .collect();
+        assert_eq!(
+            command,
+            [
+                "cargo",
+                "run",
+                "--example",
+                "demo",
+                "--manifest-path",
+                manifest
+            ]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use cargo_e::e_config::{resolve, ConfigSource};
+use std::path::PathBuf;
+
+fn table(src: &str) -> toml::value::Table {
+    match src.parse::<toml::Value>().unwrap() {
+        toml::Value::Table(t) => t,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn cli_flags_override_config_layers() {
+    let global = ConfigSource::Global(PathBuf::from("global.toml"));
+    let project = ConfigSource::Project(PathBuf::from(".cargo-e.toml"));
+    let layers = vec![
+        (global.clone(), table("wait = 3\nnT = true\n")),
+        (
+            project.clone(),
+            table(
+                "filter = true\nwait = 5\n\n[targets.demo]\nargs = [\"--fast\"]\nenv = { RUST_LOG = \"debug\" }\ntimeout = 7\n",
+            ),
+        ),
+    ];
+    let args = ["cargo-e", "-J", "4"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let (cli, config) = resolve(args, layers);
+
+    assert!(cli.filter);
+    assert!(cli.no_tts);
+    assert_eq!(cli.wait, 5);
+    assert_eq!(cli.run_at_a_time, 4);
+
+    let source_of = |key: &str| {
+        config
+            .values
+            .iter()
+            .find(|(k, _, _)| k == key)
+            .map(|(_, _, s)| s.clone())
+            .unwrap()
+    };
+    assert_eq!(source_of("wait"), project);
+    assert_eq!(source_of("nT"), global);
+    assert_eq!(source_of("run-at-a-time"), ConfigSource::Cli);
+    assert_eq!(source_of("release"), ConfigSource::Default);
+
+    let demo = config.target("demo").unwrap();
+    assert_eq!(demo.args, vec!["--fast".to_string()]);
+    assert_eq!(demo.env.get("RUST_LOG").map(String::as_str), Some("debug"));
+    assert_eq!(demo.timeout, Some(7));
+}
+
+#[test]
+fn cli_value_wins_over_config() {
+    let layers = vec![(
+        ConfigSource::Project(PathBuf::from(".cargo-e.toml")),
+        table("wait = 5\n"),
+    )];
+    let args = ["cargo-e", "--wait", "9"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let (cli, _) = resolve(args, layers);
+    assert_eq!(cli.wait, 9);
+}
+
+#[test]
+fn explicit_run_all_wins_over_target_timeout() {
+    use std::time::Duration;
+
+    let layers = || {
+        vec![(
+            ConfigSource::Project(PathBuf::from(".cargo-e.toml")),
+            table("run-all = 20\n\n[targets.demo]\ntimeout = 7\n"),
+        )]
+    };
+    let run = |args: &[&str]| {
+        let args = args.iter().map(|s| s.to_string()).collect();
+        let (cli, config) = resolve(args, layers());
+        (
+            config.run_all_timeout("demo", &cli.run_all, cli.wait),
+            config.run_all_timeout("other", &cli.run_all, cli.wait),
+        )
+    };
+
+    assert_eq!(
+        run(&["cargo-e"]),
+        (Duration::from_secs(7), Duration::from_secs(20))
+    );
+    assert_eq!(
+        run(&["cargo-e", "--run-all", "3"]),
+        (Duration::from_secs(3), Duration::from_secs(3))
+    );
+    assert_eq!(
+        run(&["cargo-e", "--run-all"]),
+        (Duration::from_secs(u64::MAX), Duration::from_secs(u64::MAX))
+    );
+}
+
+#[test]
+fn profiles_are_parsed_and_selectable() {
+    use cargo_e::e_config::split_profile;
+
+    let source = ConfigSource::Project(PathBuf::from("/proj/.cargo-e.toml"));
+    let layers = vec![(
+        source,
+        table(
+            "[[profile]]\nname = \"hires\"\ntarget = \"wgpu_image\"\nargs = [\"--width\", \"3840\"]\nstdin = \"input.txt\"\n\n[[profile]]\nname = \"lowres\"\ntarget = \"wgpu_image\"\n",
+        ),
+    )];
+    let (_, config) = resolve(vec!["cargo-e".to_string()], layers);
+
+    assert_eq!(config.profiles_for("wgpu_image").len(), 2);
+    let hires = config.profile("wgpu_image", "hires").unwrap();
+    assert_eq!(hires.args, vec!["--width".to_string(), "3840".to_string()]);
+    assert_eq!(hires.stdin, Some(PathBuf::from("/proj/input.txt")));
+    assert_eq!(hires.label(), "wgpu_image@hires");
+
+    assert_eq!(
+        split_profile("wgpu_image@hires"),
+        ("wgpu_image", Some("hires"))
+    );
+    assert_eq!(split_profile("wgpu_image"), ("wgpu_image", None));
+}
+
+#[test]
+fn plugins_table_enables_and_disables_by_name() {
+    let global = ConfigSource::Global(PathBuf::from("global.toml"));
+    let project = ConfigSource::Project(PathBuf::from(".cargo-e.toml"));
+    let layers = vec![
+        (
+            global,
+            table("[plugins]\nnpm = false\nwasm-plugin = false\n"),
+        ),
+        (project.clone(), table("[plugins]\nnpm = true\n")),
+    ];
+    let (_, config) = resolve(vec!["cargo-e".to_string()], layers);
+
+    assert!(config.plugin_enabled("npm"));
+    assert_eq!(config.plugins.get("npm").map(|(_, s)| s), Some(&project));
+    assert!(!config.plugin_enabled("wasm-plugin"));
+    assert!(config.plugin_enabled("never-mentioned"));
+}
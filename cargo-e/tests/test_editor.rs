@@ -0,0 +1,157 @@
+use cargo_e::e_editor::{parse_lineref, Editor, GotoStyle};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+fn argv(command: &str) -> Vec<String> {
+    Editor::from_command(command).argv(Path::new("src/main.rs"), 12, 5)
+}
+
+#[test]
+fn each_editor_family_gets_its_goto_syntax() {
+    assert_eq!(argv("code"), ["code", "--goto", "src/main.rs:12:5"]);
+    assert_eq!(argv("nvim"), ["nvim", "+12", "src/main.rs"]);
+    assert_eq!(
+        argv("emacsclient -nw"),
+        ["emacsclient", "-nw", "+12:5", "src/main.rs"]
+    );
+    assert_eq!(argv("/usr/bin/hx"), ["/usr/bin/hx", "src/main.rs:12:5"]);
+    assert_eq!(argv("zed"), ["zed", "src/main.rs:12:5"]);
+    assert_eq!(
+        argv("rustrover.exe"),
+        [
+            "rustrover.exe",
+            "--line",
+            "12",
+            "--column",
+            "5",
+            "src/main.rs"
+        ]
+    );
+    assert_eq!(argv("kate"), ["kate", "src/main.rs"]);
+}
+
+#[test]
+fn templates_substitute_placeholders() {
+    let editor = Editor::from_template("{editor} --wait {file}:{line}:{col}", "subl -n");
+    assert_eq!(
+        editor.argv(Path::new("lib.rs"), 3, 0),
+        ["subl", "-n", "--wait", "lib.rs:3:1"]
+    );
+}
+
+#[test]
+fn resolution_prefers_flag_then_visual_then_editor() {
+    let env: HashMap<&str, &str> = [("VISUAL", "nvim"), ("EDITOR", "nano")].into();
+    let lookup = |key: &str| env.get(key).map(|v| v.to_string());
+    let style = |editor: Editor| match editor {
+        Editor::Program { style, .. } => style,
+        Editor::Template(_) => panic!("expected a program"),
+    };
+    assert_eq!(
+        style(Editor::resolve_with(Some("idea"), lookup)),
+        GotoStyle::JetBrains
+    );
+    assert_eq!(
+        Editor::resolve_with(None, lookup),
+        Editor::from_command("nvim")
+    );
+    assert_eq!(
+        Editor::resolve_with(None, |key| (key == "EDITOR").then(|| "hx".to_string())),
+        Editor::from_command("hx")
+    );
+    assert_eq!(
+        Editor::resolve_with(None, |_| None),
+        Editor::from_command("code")
+    );
+    assert_eq!(
+        Editor::resolve_with(Some("{editor} {file}"), lookup),
+        Editor::Template(vec!["nvim".into(), "{file}".into()])
+    );
+}
+
+#[test]
+fn terminal_editors_are_detected() {
+    assert!(Editor::from_command("vim").in_terminal());
+    assert!(Editor::from_command("emacs -nw").in_terminal());
+    assert!(!Editor::from_command("emacs").in_terminal());
+    assert!(!Editor::from_command("code").in_terminal());
+}
+
+#[test]
+fn linerefs_split_into_file_line_and_column() {
+    assert_eq!(
+        parse_lineref("src/a.rs:4:9"),
+        (PathBuf::from("src/a.rs"), 4, 9)
+    );
+    assert_eq!(
+        parse_lineref("C:\\src\\a.rs:4:9"),
+        (PathBuf::from("C:\\src\\a.rs"), 4, 9)
+    );
+    assert_eq!(
+        parse_lineref("src/a.rs:4"),
+        (PathBuf::from("src/a.rs"), 4, 1)
+    );
+    assert_eq!(parse_lineref("src/a.rs"), (PathBuf::from("src/a.rs"), 1, 1));
+}
+
+#[cfg(feature = "uses_serde")]
+#[test]
+fn diagnostic_specs_match_level_and_number() {
+    use cargo_e::e_editor::last_run::{find, SavedDiagnostic};
+    let diag = |level: &str, number| SavedDiagnostic {
+        target: "demo".to_string(),
+        level: level.to_string(),
+        number,
+        file: PathBuf::from("src/main.rs"),
+        line: 1,
+        col: 1,
+        message: String::new(),
+    };
+    let saved = vec![diag("error", 1), diag("warning", 1), diag("error", 3)];
+    assert_eq!(find(&saved, "W01").unwrap().level, "warning");
+    assert_eq!(find(&saved, "e3").unwrap().number, 3);
+    assert_eq!(find(&saved, "1").unwrap().level, "error");
+    assert_eq!(find(&saved, "E01").unwrap().label(), "E01");
+    assert!(find(&saved, "W3").is_none());
+    assert!(find(&saved, "x").is_none());
+
+    let mut other = diag("error", 1);
+    other.target = "other@fast".to_string();
+    let saved = vec![diag("error", 1), other];
+    assert_eq!(find(&saved, "E01").unwrap().target, "demo");
+    assert_eq!(find(&saved, "other@fast:E01").unwrap().target, "other@fast");
+    assert!(find(&saved, "missing:E01").is_none());
+}
+
+#[cfg(feature = "uses_serde")]
+#[test]
+fn saved_diagnostics_resolve_against_the_package() {
+    use cargo_e::e_cargocommand_ext::{CargoDiagnostic, CargoProcessResult};
+    use cargo_e::e_editor::last_run::from_result;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("examples").join("only_in_tempdir.rs");
+    std::fs::create_dir_all(source.parent().unwrap()).unwrap();
+    std::fs::write(&source, "fn main() {}\n").unwrap();
+    let result = CargoProcessResult {
+        target_name: "only_in_tempdir".to_string(),
+        manifest_path: dir.path().join("Cargo.toml"),
+        diagnostics: vec![CargoDiagnostic::new(
+            "examples/only_in_tempdir.rs:1:4".to_string(),
+            "warning".to_string(),
+            "unused".to_string(),
+            None,
+            None,
+            None,
+            None,
+            false,
+            Some(1),
+            None,
+        )],
+        ..Default::default()
+    };
+    let saved = from_result(&result);
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0].target, "only_in_tempdir");
+    assert_eq!(saved[0].file, std::fs::canonicalize(&source).unwrap());
+    assert_eq!((saved[0].line, saved[0].col), (1, 4));
+}
@@ -0,0 +1,201 @@
+use cargo_e::e_cargocommand_ext::CargoProcessResult;
+use cargo_e::e_expect::{Expectations, Monitor, Stream, Verdict};
+use cargo_e::e_reports::result_failed;
+use std::process::ExitStatus;
+use std::time::{Duration, Instant};
+
+fn doc(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|l| l.to_string()).collect()
+}
+
+#[test]
+fn doc_comment_declarations_are_parsed() {
+    let expect = Expectations::from_doc(&doc(&[
+        "Draws a spinning cube.",
+        "cargo-e: expect-stdout = \"frame 1\"",
+        "cargo-e: reject-stderr = ['ERROR', 'wgpu error']",
+        "cargo-e: survive = 3",
+        "cargo-e: this is not toml",
+    ]));
+    assert_eq!(expect.stdout, vec!["frame 1"]);
+    assert_eq!(expect.reject_stderr, vec!["ERROR", "wgpu error"]);
+    assert_eq!(expect.survive, Some(3));
+    assert_eq!(expect.exit, None);
+    assert!(expect.needs_output());
+    assert!(Expectations::from_doc(&doc(&["Just a description."])).is_empty());
+}
+
+#[test]
+fn configuration_overrides_the_doc_comment_key_by_key() {
+    let mut expect = Expectations::from_doc(&doc(&[
+        "cargo-e: ready = 'listening'",
+        "cargo-e: survive = 10",
+    ]));
+    let table: toml::value::Table = toml::from_str("survive = 2\nexpect-exit = 0").unwrap();
+    let mut configured = Expectations::default();
+    configured.merge_toml(&table);
+    expect.overlay(&configured);
+    assert_eq!(expect.ready.as_deref(), Some("listening"));
+    assert_eq!(expect.survive, Some(2));
+    assert_eq!(expect.exit, Some(0));
+}
+
+#[test]
+fn ready_pattern_stops_and_passes() {
+    let start = Instant::now();
+    let monitor = Monitor::new_at(
+        Expectations {
+            ready: Some(r"listening on \S+".to_string()),
+            ..Default::default()
+        },
+        false,
+        start,
+    );
+    // cargo's own stderr does not count as the target's output.
+    monitor.line_at(Stream::Stderr, "   Compiling server v0.1.0", start);
+    monitor.line_at(
+        Stream::Stderr,
+        "\u{1b}[1m\u{1b}[32m     Running\u{1b}[0m `target/debug/examples/server`",
+        start + Duration::from_secs(4),
+    );
+    assert_eq!(monitor.stop_reason_at(start + Duration::from_secs(5)), None);
+    monitor.line_at(
+        Stream::Stdout,
+        "listening on 127.0.0.1:8080",
+        start + Duration::from_secs(6),
+    );
+    assert_eq!(
+        monitor.stop_reason_at(start + Duration::from_secs(6)),
+        Some("ready".to_string())
+    );
+    assert_eq!(
+        monitor.verdict_at(None, start + Duration::from_secs(6)),
+        Verdict {
+            passed: true,
+            reason: "ready after 2.0s".to_string()
+        }
+    );
+}
+
+#[test]
+fn survive_fails_on_an_early_exit_or_a_panic() {
+    let start = Instant::now();
+    let expect = Expectations {
+        survive: Some(5),
+        ..Default::default()
+    };
+    let monitor = Monitor::new_at(expect.clone(), true, start);
+    monitor.line_at(Stream::Stdout, "starting", start);
+    let verdict = monitor.verdict_at(Some(ExitStatus::default()), start + Duration::from_secs(2));
+    assert!(!verdict.passed);
+    assert_eq!(verdict.reason, "exited after 2.0s; expected to survive 5s");
+
+    let monitor = Monitor::new_at(expect.clone(), true, start);
+    monitor.line_at(
+        Stream::Stderr,
+        "thread 'main' panicked at examples/cube.rs:10:5:",
+        start + Duration::from_secs(1),
+    );
+    assert!(monitor.stop_reason_at(start).is_some());
+    assert!(monitor
+        .verdict_at(None, start)
+        .reason
+        .starts_with("panicked"));
+
+    let monitor = Monitor::new_at(expect, true, start);
+    assert_eq!(
+        monitor.stop_reason_at(start + Duration::from_secs(5)),
+        Some("survived 5s".to_string())
+    );
+    assert!(
+        monitor
+            .verdict_at(None, start + Duration::from_secs(5))
+            .passed
+    );
+}
+
+#[test]
+fn output_and_exit_code_rules() {
+    let start = Instant::now();
+    let expect = Expectations {
+        exit: Some(0),
+        stdout: vec!["^done$".to_string()],
+        reject_stdout: vec!["(?i)warning".to_string()],
+        ..Default::default()
+    };
+    let monitor = Monitor::new_at(expect.clone(), true, start);
+    monitor.line_at(Stream::Stdout, "done", start);
+    let verdict = monitor.verdict_at(Some(ExitStatus::default()), start);
+    assert_eq!(
+        verdict.reason,
+        "exited with 0, matched 1 output pattern(s)".to_string()
+    );
+    assert!(verdict.passed);
+    // Stopped by the timeout before exiting.
+    assert!(!monitor.verdict_at(None, start).passed);
+
+    let monitor = Monitor::new_at(expect.clone(), true, start);
+    monitor.line_at(Stream::Stdout, "Warning: low disk", start);
+    assert!(monitor.stop_reason_at(start).is_some());
+    assert_eq!(
+        monitor
+            .verdict_at(Some(ExitStatus::default()), start)
+            .reason,
+        "stdout matched rejected `(?i)warning`: Warning: low disk"
+    );
+
+    let monitor = Monitor::new_at(expect, true, start);
+    let verdict = monitor.verdict_at(Some(ExitStatus::default()), start);
+    assert_eq!(verdict.reason, "stdout never matched `^done$`");
+
+    let monitor = Monitor::new_at(
+        Expectations {
+            stdout: vec!["(unclosed".to_string()],
+            ..Default::default()
+        },
+        true,
+        start,
+    );
+    assert!(monitor
+        .verdict_at(None, start)
+        .reason
+        .starts_with("invalid pattern"));
+}
+
+#[cfg(unix)]
+#[test]
+fn wrong_exit_code_fails() {
+    use std::os::unix::process::ExitStatusExt;
+    let monitor = Monitor::new(
+        Expectations {
+            exit: Some(0),
+            ..Default::default()
+        },
+        true,
+    );
+    let verdict = monitor.verdict(Some(ExitStatus::from_raw(2 << 8)));
+    assert_eq!(verdict.reason, "exited with 2; expected 0");
+}
+
+#[test]
+fn verdict_decides_whether_the_run_failed() {
+    let mut result = CargoProcessResult {
+        target_name: "server".to_string(),
+        cmd: "cargo".to_string(),
+        // Killed once ready: no exit status.
+        exit_status: None,
+        expectation: Some(Verdict {
+            passed: true,
+            reason: "ready after 1.0s".to_string(),
+        }),
+        ..Default::default()
+    };
+    assert!(!result_failed(&result));
+    result.expectation = Some(Verdict {
+        passed: false,
+        reason: "never printed ready `listening`".to_string(),
+    });
+    assert!(result_failed(&result));
+    let xml = cargo_e::e_reports::generate_junit_report(&[result]);
+    assert!(xml.contains("type=\"expectation\""));
+}
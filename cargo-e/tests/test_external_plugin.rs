@@ -0,0 +1,108 @@
+#![cfg(all(feature = "uses_plugins", unix))]
+
+use cargo_e::plugins::external_plugin::ExternalPlugin;
+use cargo_e::plugins::plugin_api::Plugin;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, fs};
+use tempfile::tempdir;
+
+/// Builds the fixture plugin and returns the path of its executable.
+fn build_fixture() -> PathBuf {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("cargo-e-ext-fixture")
+        .join("Cargo.toml");
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ext-fixture");
+    let status = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+        .args(["build", "--quiet", "--manifest-path"])
+        .arg(&manifest)
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("failed to run cargo");
+    assert!(status.success(), "fixture plugin failed to build");
+    target_dir.join("debug").join("cargo-e-ext-fixture")
+}
+
+#[test]
+fn fixture_plugin_speaks_protocol_v1() {
+    let exe = build_fixture();
+    let tmp = tempdir().unwrap();
+    let cache = tmp.path().join("ext-plugins.json");
+    let log = tmp.path().join("calls.log");
+    env::set_var("CARGO_E_EXT_FIXTURE_LOG", &log);
+
+    let plugin = ExternalPlugin::load_cached(&exe, &cache)
+        .unwrap()
+        .expect("fixture should accept this client");
+    assert_eq!(plugin.name(), "fixture");
+    assert_eq!(plugin.version(), "0.1.0");
+    assert!(cache.is_file());
+
+    // A second load is answered from the cache.
+    let handshake_calls = fs::read_to_string(&log).unwrap().lines().count();
+    let again = ExternalPlugin::load_cached(&exe, &cache).unwrap().unwrap();
+    assert_eq!(again.name(), "fixture");
+    assert_eq!(
+        fs::read_to_string(&log).unwrap().lines().count(),
+        handshake_calls
+    );
+
+    let project = tmp.path().join("project");
+    fs::create_dir_all(&project).unwrap();
+    assert!(!plugin.matches(&project));
+    fs::write(project.join("fixture.txt"), "alpha\nbeta\n").unwrap();
+    assert!(plugin.matches(&project));
+
+    let targets = plugin.collect_targets(&project).unwrap();
+    let names: Vec<_> = targets.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["alpha", "beta"]);
+
+    let output = plugin
+        .build_command(&project, &targets[1])
+        .unwrap()
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "beta");
+
+    let missing = cargo_e::plugins::plugin_api::Target {
+        name: "gamma".into(),
+        metadata: None,
+        cargo_target: None,
+    };
+    let err = plugin.build_command(&project, &missing).unwrap_err();
+    assert!(err.to_string().contains("unknown target gamma"));
+}
+
+#[test]
+fn fixture_plugin_answers_declared_hooks() {
+    use cargo_e::plugins::hooks::BuildEvent;
+
+    let exe = build_fixture();
+    let tmp = tempdir().unwrap();
+    let plugin = ExternalPlugin::load_cached(&exe, &tmp.path().join("ext-plugins.json"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(plugin.hooks(), ["before_build", "on_exit"]);
+
+    let event = BuildEvent {
+        target: "demo".into(),
+        manifest_path: "Cargo.toml".into(),
+        subcommand: "run".into(),
+        args: vec!["--example".into(), "demo".into()],
+    };
+    let envs = plugin.before_build(&event).unwrap();
+    assert_eq!(envs.len(), 1);
+    assert_eq!(envs[0].0, "FIXTURE_EVENT_BYTES");
+    assert_ne!(envs[0].1, "0");
+
+    let section = plugin.on_exit(&[]).unwrap().unwrap();
+    assert!(section.starts_with("## fixture"));
+
+    // Hooks the plugin did not declare are never invoked.
+    let mut result = cargo_e::e_cargocommand_ext::CargoProcessResult::default();
+    plugin.after_build(&mut result).unwrap();
+    assert_eq!(result.target_name, "");
+}
@@ -0,0 +1,76 @@
+use cargo_e::e_fuzzy::{fuzzy_match, rank_targets, rank_targets_by_name, TargetField};
+use cargo_e::e_target::{CargoTarget, TargetKind};
+use std::path::PathBuf;
+
+fn target(name: &str, kind: TargetKind, manifest: &str) -> CargoTarget {
+    CargoTarget {
+        name: name.to_string(),
+        display_name: name.to_string(),
+        manifest_path: PathBuf::from(manifest),
+        kind,
+        extended: false,
+        toml_specified: false,
+        origin: None,
+    }
+}
+
+#[test]
+fn matches_characters_in_order_and_reports_positions() {
+    let m = fuzzy_match("wgi", "wgpu_image").unwrap();
+    assert_eq!(m.positions, vec![0, 1, 5]);
+    assert!(fuzzy_match("igw", "wgpu_image").is_none());
+    assert_eq!(fuzzy_match("", "anything").unwrap().score, 0);
+}
+
+#[test]
+fn smart_case_only_when_pattern_has_uppercase() {
+    assert!(fuzzy_match("hello", "HelloWorld").is_some());
+    assert!(fuzzy_match("HW", "HelloWorld").is_some());
+    assert!(fuzzy_match("HW", "helloworld").is_none());
+}
+
+#[test]
+fn word_starts_and_consecutive_runs_score_higher() {
+    let boundary = fuzzy_match("img", "wgpu_img").unwrap().score;
+    let scattered = fuzzy_match("img", "minimalog").unwrap().score;
+    assert!(boundary > scattered);
+    let prefix = fuzzy_match("tree", "tree_view").unwrap().score;
+    let inner = fuzzy_match("tree", "btree_map").unwrap().score;
+    assert!(prefix > inner);
+}
+
+#[test]
+fn ranks_targets_best_first_and_keeps_order_for_empty_query() {
+    let targets = vec![
+        target("btree_map", TargetKind::Example, "core/Cargo.toml"),
+        target("tree_view", TargetKind::Example, "ui/Cargo.toml"),
+        target("server", TargetKind::Binary, "core/Cargo.toml"),
+    ];
+    let ranked = rank_targets("tree", &targets);
+    let order: Vec<usize> = ranked.iter().map(|m| m.index).collect();
+    assert_eq!(order, vec![1, 0]);
+
+    let all: Vec<usize> = rank_targets("", &targets).iter().map(|m| m.index).collect();
+    assert_eq!(all, vec![0, 1, 2]);
+
+    // Kind labels and package directories are searched too.
+    let by_kind = rank_targets("bin", &targets);
+    assert_eq!(by_kind[0].index, 2);
+    assert_eq!(by_kind[0].field, TargetField::Kind);
+    let by_package = rank_targets("ui", &targets);
+    assert_eq!(by_package[0].index, 1);
+}
+
+#[test]
+fn cli_fallback_matches_names_only() {
+    let targets = vec![
+        target("btree_map", TargetKind::Example, "core/Cargo.toml"),
+        target("server", TargetKind::Binary, "core/Cargo.toml"),
+    ];
+    // "core" is the package of both targets but the name of neither.
+    assert!(rank_targets_by_name("core", &targets).is_empty());
+    assert_eq!(rank_targets("core", &targets).len(), 2);
+    let by_name = rank_targets_by_name("serv", &targets);
+    assert_eq!(by_name.len(), 1);
+    assert_eq!(by_name[0].field, TargetField::Name);
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "uses_serde")]
+
+use cargo_e::e_history::{
+    append, flaky, latest_per_target, read, regressions, slowest, HistoryRecord,
+};
+use std::collections::HashSet;
+
+fn record(target: &str, sha: &str, success: bool, build_secs: f64) -> HistoryRecord {
+    HistoryRecord {
+        timestamp: "2025-01-01T00:00:00+00:00".to_string(),
+        sha: Some(sha.to_string()),
+        target: target.to_string(),
+        profile: None,
+        build_secs: Some(build_secs),
+        run_secs: Some(0.5),
+        elapsed_secs: Some(build_secs + 0.5),
+        exit_code: Some(if success { 0 } else { 101 }),
+        success,
+        errors: if success { 0 } else { 1 },
+        warnings: 0,
+    }
+}
+
+#[test]
+fn history_round_trips_through_jsonl() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history").join("runs.jsonl");
+    let first = record("alpha", "aaaaaaa", true, 1.0);
+    let second = record("beta", "aaaaaaa", false, 2.0);
+    append(&path, &first).unwrap();
+    append(&path, &second).unwrap();
+    assert_eq!(read(&path).unwrap(), vec![first, second]);
+    assert!(read(&dir.path().join("missing.jsonl")).unwrap().is_empty());
+}
+
+#[test]
+fn queries_find_slow_flaky_and_regressed_targets() {
+    let history = vec![
+        record("alpha", "aaaaaaa", true, 1.0),
+        record("beta", "aaaaaaa", true, 9.0),
+        record("beta", "bbbbbbb", true, 3.0),
+        record("beta", "bbbbbbb", false, 3.0),
+        record("alpha", "ccccccc", false, 1.0),
+    ];
+
+    let slow = slowest(&history, 1);
+    assert_eq!(slow.len(), 1);
+    assert_eq!(slow[0].label, "beta");
+    assert_eq!(slow[0].runs, 3);
+    assert_eq!(slow[0].max_secs, 9.0);
+
+    let flaky = flaky(&history);
+    assert_eq!(flaky.len(), 1);
+    assert_eq!(flaky[0].label, "beta");
+    assert_eq!(flaky[0].sha.as_deref(), Some("bbbbbbb"));
+
+    // Only aaaaaaa is at or before the baseline.
+    let baseline: HashSet<String> = ["aaaaaaa".to_string()].into_iter().collect();
+    let found = regressions(&history, &baseline, &latest_per_target(&history));
+    let labels: Vec<_> = found.iter().map(|r| r.label.as_str()).collect();
+    assert_eq!(labels, ["alpha", "beta"]);
+    assert_eq!(found[0].current.sha.as_deref(), Some("ccccccc"));
+
+    // A current run where everything passes has no regressions.
+    let current = vec![record("alpha", "ddddddd", true, 1.0)];
+    assert!(regressions(&history, &baseline, &current).is_empty());
+}
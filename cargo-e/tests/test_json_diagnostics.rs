@@ -0,0 +1,97 @@
+use cargo_e::e_cargocommand_ext::CargoDiagnostic;
+use cargo_e::e_prebuild::Prebuilt;
+use cargo_e::e_target::{CargoTarget, TargetKind, TargetOrigin};
+use std::path::PathBuf;
+
+/// `cargo build --message-format=json-diagnostic-rendered-ansi` output for a binary
+/// with a type error and, in a second build, two unused variables.
+const MESSAGES: &str = include_str!("fixtures/rustc-json/compiler-messages.jsonl");
+
+fn compiler_messages() -> Vec<cargo_metadata::CompilerMessage> {
+    cargo_metadata::Message::parse_stream(MESSAGES.as_bytes())
+        .filter_map(|m| match m.unwrap() {
+            cargo_metadata::Message::CompilerMessage(msg) => Some(msg),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A package holding the fixture's source file, so span paths resolve against it.
+fn package() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src").join("dj.rs"), "fn main() {}").unwrap();
+    let manifest = dir.path().join("Cargo.toml");
+    (dir, manifest)
+}
+
+#[test]
+fn compiler_message_keeps_spans_and_code() {
+    let (dir, manifest) = package();
+    let src = dir.path().join("src").join("dj.rs");
+    let messages = compiler_messages();
+
+    let error = CargoDiagnostic::from_compiler_diagnostic(&messages[0].message, &manifest, None)
+        .expect("error diagnostic");
+    assert_eq!(error.level, "error");
+    assert_eq!(error.message, "mismatched types");
+    assert_eq!(error.error_code.as_deref(), Some("E0308"));
+    assert_eq!(error.lineref, format!("{}:4:18", src.display()));
+    assert_eq!(error.spans.len(), 2);
+    assert!(error.spans[0].is_primary && !error.spans[1].is_primary);
+    assert_eq!(
+        (error.spans[1].line_start, error.spans[1].column_start),
+        (4, 12)
+    );
+    assert!(error
+        .suggestion
+        .as_deref()
+        .is_some_and(|s| s.contains("4 | ")));
+
+    // "For more information about this error" is not a diagnostic of its own.
+    assert!(
+        CargoDiagnostic::from_compiler_diagnostic(&messages[1].message, &manifest, None).is_none()
+    );
+
+    let warning =
+        CargoDiagnostic::from_compiler_diagnostic(&messages[2].message, &manifest, Some(1))
+            .expect("warning diagnostic");
+    assert_eq!(warning.level, "warning");
+    assert_eq!(warning.error_code.as_deref(), Some("unused_variables"));
+    assert_eq!(warning.lineref, format!("{}:2:9", src.display()));
+    assert_eq!(warning.diag_number, Some(1));
+}
+
+#[test]
+fn diagnostics_are_numbered_per_level() {
+    let (dir, manifest) = package();
+    let bin = CargoTarget {
+        name: "dj".to_string(),
+        display_name: "dj".to_string(),
+        manifest_path: manifest.clone(),
+        kind: TargetKind::Binary,
+        extended: false,
+        toml_specified: false,
+        origin: Some(TargetOrigin::CargoMetadata {
+            src_path: dir.path().join("src").join("dj.rs"),
+            edition: "2021".to_string(),
+            required_features: Vec::new(),
+        }),
+    };
+    let mut prebuilt = Prebuilt::default();
+    prebuilt.ingest(&manifest, &[&bin], MESSAGES.as_bytes());
+
+    let numbered: Vec<(&str, Option<usize>)> = prebuilt
+        .diagnostics(&bin)
+        .iter()
+        .map(|d| (d.level.as_str(), d.diag_number))
+        .collect();
+    assert_eq!(
+        numbered,
+        [
+            ("error", Some(1)),
+            ("warning", Some(1)),
+            ("warning", Some(2))
+        ]
+    );
+}
@@ -0,0 +1,69 @@
+use cargo_e::{
+    e_collect::{collect_all_targets_silent, collect_metadata_packages, collect_metadata_targets},
+    e_target::{TargetKind, TargetOrigin},
+};
+use std::path::PathBuf;
+
+#[test]
+fn metadata_discovers_own_targets() {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let packages = collect_metadata_packages(&manifest).expect("cargo metadata failed");
+    let key = std::fs::canonicalize(&manifest).unwrap();
+    let package = packages.get(&key).expect("cargo-e package not reported");
+
+    let targets = collect_metadata_targets("-", &manifest, package, false);
+
+    let bin = targets
+        .iter()
+        .find(|t| t.name == "cargo-e")
+        .expect("cargo-e binary missing");
+    assert_eq!(bin.kind, TargetKind::Binary);
+    match &bin.origin {
+        Some(TargetOrigin::CargoMetadata {
+            src_path, edition, ..
+        }) => {
+            assert!(src_path.ends_with("main.rs"));
+            assert!(!edition.is_empty());
+        }
+        other => panic!("unexpected origin: {:?}", other),
+    }
+
+    assert!(targets
+        .iter()
+        .any(|t| t.name == "funny_example" && t.kind == TargetKind::Example));
+}
+
+#[test]
+fn silent_collection_keeps_every_metadata_example() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("Cargo.toml"),
+        "[package]\nname = \"many\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    std::fs::create_dir_all(dir.path().join("examples")).unwrap();
+    std::fs::write(dir.path().join("src").join("main.rs"), "fn main() {}").unwrap();
+    for name in ["alpha", "beta", "gamma"] {
+        std::fs::write(
+            dir.path().join("examples").join(format!("{}.rs", name)),
+            "fn main() {}",
+        )
+        .unwrap();
+    }
+
+    // The collection starts from the current directory's manifest.
+    std::env::set_current_dir(dir.path()).unwrap();
+    let targets = collect_all_targets_silent(false, 2).unwrap();
+
+    let mut examples: Vec<&str> = targets
+        .iter()
+        .filter(|t| t.kind == TargetKind::Example)
+        .map(|t| t.name.as_str())
+        .collect();
+    examples.sort();
+    assert_eq!(examples, ["alpha", "beta", "gamma"]);
+    assert!(targets
+        .iter()
+        .any(|t| t.name == "many" && t.kind == TargetKind::Binary));
+}
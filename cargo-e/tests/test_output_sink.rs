@@ -0,0 +1,62 @@
+use cargo_e::e_eventdispatcher::{set_output_sink, OutputSink};
+use cargo_e::{out_eprintln, out_println};
+use std::thread;
+
+#[test]
+fn captures_prints_from_a_thread_with_the_sink_set() {
+    let sink = OutputSink::new();
+    let for_thread = sink.clone();
+    thread::spawn(move || {
+        set_output_sink(Some(for_thread));
+        out_println!("\x1b[32mbuilding\x1b[0m {}", "demo");
+        out_eprintln!("warning: two\nlines\r");
+    })
+    .join()
+    .unwrap();
+    assert_eq!(sink.lines(), vec!["building demo", "warning: two", "lines"]);
+
+    // Other threads are unaffected.
+    out_println!("not captured");
+    assert_eq!(sink.len(), 3);
+}
+
+#[test]
+fn keeps_only_the_most_recent_lines() {
+    let sink = OutputSink::new();
+    for i in 0..OutputSink::CAPACITY + 5 {
+        sink.push(&i.to_string());
+    }
+    let lines = sink.lines();
+    assert_eq!(lines.len(), OutputSink::CAPACITY);
+    assert_eq!(lines[0], "5");
+    sink.clear();
+    assert!(sink.is_empty());
+}
+
+#[test]
+fn current_sink_can_be_handed_to_spawned_threads() {
+    let sink = OutputSink::new();
+    set_output_sink(Some(sink.clone()));
+    let inherited = cargo_e::e_eventdispatcher::current_output_sink();
+    set_output_sink(None);
+    assert!(cargo_e::e_eventdispatcher::current_output_sink().is_none());
+    thread::spawn(move || {
+        set_output_sink(inherited);
+        out_println!("from a run thread");
+    })
+    .join()
+    .unwrap();
+    assert_eq!(sink.lines(), vec!["from a run thread"]);
+}
+
+#[test]
+fn window_is_the_visible_slice_above_the_scroll() {
+    let sink = OutputSink::new();
+    for i in 0..10 {
+        sink.push(&i.to_string());
+    }
+    assert_eq!(sink.window(3, 0), vec!["7", "8", "9"]);
+    assert_eq!(sink.window(3, 2), vec!["5", "6", "7"]);
+    assert_eq!(sink.window(3, 9), vec!["0"]);
+    assert!(sink.window(3, 20).is_empty());
+}
@@ -0,0 +1,175 @@
+#![cfg(feature = "uses_plugins")]
+use cargo_e::e_cargocommand_ext::{CargoDiagnostic, CargoProcessResult};
+use cargo_e::e_processmanager::ProcessManager;
+use cargo_e::e_reports::{generate_junit_report, result_failed};
+use cargo_e::plugins::hooks::{self, BuildEvent};
+use cargo_e::plugins::plugin_api::Plugin;
+use cargo_e::Cli;
+use clap::Parser;
+
+fn event() -> BuildEvent {
+    BuildEvent {
+        target: "demo".to_string(),
+        manifest_path: "/proj/Cargo.toml".to_string(),
+        subcommand: "run".to_string(),
+        args: Vec::new(),
+    }
+}
+
+fn result_with_error() -> CargoProcessResult {
+    CargoProcessResult {
+        target_name: "demo".to_string(),
+        cmd: "cargo".to_string(),
+        diagnostics: vec![CargoDiagnostic::new(
+            "src/main.rs:1:1".to_string(),
+            "error".to_string(),
+            "mismatched types".to_string(),
+            None,
+            None,
+            None,
+            None,
+            false,
+            Some(1),
+            None,
+        )],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn after_build_can_annotate_and_fail_a_run() {
+    let mut result = CargoProcessResult {
+        target_name: "demo".to_string(),
+        timed_out: true,
+        ..Default::default()
+    };
+    assert!(!result_failed(&result));
+
+    hooks::apply_result_patch(&mut result, r#"{"annotations": "first"}"#).unwrap();
+    hooks::apply_result_patch(
+        &mut result,
+        r#"{"annotations": ["second"], "fail": "output was empty"}"#,
+    )
+    .unwrap();
+    assert_eq!(result.annotations, ["first", "second"]);
+    assert_eq!(result.plugin_failure.as_deref(), Some("output was empty"));
+    assert!(result_failed(&result));
+
+    let xml = generate_junit_report(&[result]);
+    assert!(xml.contains(r#"<failure message="output was empty" type="plugin">"#));
+}
+
+#[cfg(feature = "uses_rhai")]
+#[test]
+fn rhai_scripts_implement_the_hooks() {
+    use cargo_e::plugins::rhai_plugin::RhaiPlugin;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("hooked.rhai");
+    std::fs::write(
+        &path,
+        r#"
+fn name() { "hooked" }
+fn matches(dir) { false }
+fn collect_targets(dir) { "[]" }
+fn build_command(dir, target) { "{}" }
+fn before_build(event) { `{"HOOKED_BY": "rhai"}` }
+fn after_build(result) {
+    if result.contains("\"errors\":1") {
+        `{"fail": "errors are not allowed", "annotations": ["checked by rhai"]}`
+    }
+}
+fn on_exit(results) { "Rhai section" }
+"#,
+    )
+    .unwrap();
+    let cli = Cli::parse_from(["cargo-e"]);
+    let plugin = RhaiPlugin::load(&path, &cli, ProcessManager::new(&cli)).unwrap();
+
+    assert_eq!(
+        plugin.before_build(&event()).unwrap(),
+        [("HOOKED_BY".to_string(), "rhai".to_string())]
+    );
+    let mut result = result_with_error();
+    plugin.after_build(&mut result).unwrap();
+    assert_eq!(
+        result.plugin_failure.as_deref(),
+        Some("errors are not allowed")
+    );
+    assert_eq!(result.annotations, ["checked by rhai"]);
+    // Returning nothing leaves the result alone.
+    let mut clean = CargoProcessResult::default();
+    plugin.after_build(&mut clean).unwrap();
+    assert!(clean.plugin_failure.is_none() && clean.annotations.is_empty());
+    assert_eq!(
+        plugin.on_exit(&[result]).unwrap().as_deref(),
+        Some("Rhai section")
+    );
+}
+
+#[cfg(feature = "uses_lua")]
+#[test]
+fn lua_scripts_implement_the_hooks() {
+    use cargo_e::plugins::lua_plugin::LuaPlugin;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("hooked.lua");
+    std::fs::write(
+        &path,
+        r#"
+return {
+  name = "hooked_lua",
+  matches = function(dir) return false end,
+  collect_targets = function(dir) return "[]" end,
+  build_command = function(dir, target) return "{}" end,
+  before_build = function(event) return '{"HOOKED_BY": "lua"}' end,
+  after_build = function(result)
+    if string.find(result, '"target_name":"demo"', 1, true) then
+      return '{"profile": "fast", "annotations": "seen by lua"}'
+    end
+  end,
+  on_exit = function(results) return "Lua section" end,
+}
+"#,
+    )
+    .unwrap();
+    let cli = Cli::parse_from(["cargo-e"]);
+    let plugin = LuaPlugin::load(&path, &cli, ProcessManager::new(&cli)).unwrap();
+
+    assert_eq!(
+        plugin.before_build(&event()).unwrap(),
+        [("HOOKED_BY".to_string(), "lua".to_string())]
+    );
+    let mut result = result_with_error();
+    plugin.after_build(&mut result).unwrap();
+    assert_eq!(result.profile.as_deref(), Some("fast"));
+    assert_eq!(result.annotations, ["seen by lua"]);
+    assert!(result.plugin_failure.is_none());
+    assert_eq!(
+        plugin.on_exit(&[result]).unwrap().as_deref(),
+        Some("Lua section")
+    );
+}
+
+#[cfg(feature = "uses_rhai")]
+#[test]
+fn installed_plugins_are_shared_with_plugin_work() {
+    let cli = Cli::parse_from(["cargo-e"]);
+    hooks::install(&cli, ProcessManager::new(&cli));
+
+    // The development plugins directory holds example.rhai.
+    let names = hooks::with_plugins(|plugins| {
+        plugins
+            .iter()
+            .map(|p| p.name().to_string())
+            .collect::<Vec<_>>()
+    })
+    .expect("plugins are installed");
+    assert!(
+        names.iter().any(|name| name == "cargo_e_collect"),
+        "{:?}",
+        names
+    );
+
+    // Hooks raised from plugin work run on the hook thread without waiting on it.
+    let sections = hooks::with_plugins(|_| hooks::on_exit(&[])).expect("plugins are installed");
+    assert!(sections.iter().all(|s| !s.is_empty()));
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "uses_plugins")]
+
+use cargo_e::plugins::manage::{scaffold, PluginLang};
+use tempfile::tempdir;
+
+#[test]
+fn scaffold_writes_each_template_once() {
+    let dir = tempdir().unwrap();
+    for lang in [
+        PluginLang::Lua,
+        PluginLang::Rhai,
+        PluginLang::Wasm,
+        PluginLang::Ext,
+    ] {
+        let created = scaffold("demo", lang, dir.path()).unwrap();
+        assert!(!created.is_empty());
+        for path in &created {
+            let content = std::fs::read_to_string(path).unwrap();
+            assert!(!content.contains("{{name}}"), "{}", path.display());
+        }
+    }
+    assert!(dir.path().join("demo.lua").is_file());
+    assert!(dir
+        .path()
+        .join("demo")
+        .join("src")
+        .join("main.rs")
+        .is_file());
+    assert!(dir
+        .path()
+        .join("cargo-e-ext-demo")
+        .join("Cargo.toml")
+        .is_file());
+
+    let again = scaffold("demo", PluginLang::Lua, dir.path());
+    assert!(again.is_err(), "existing plugins must not be overwritten");
+    assert!(scaffold("../escape", PluginLang::Lua, dir.path()).is_err());
+}
+
+#[cfg(feature = "uses_rhai")]
+#[test]
+fn scaffolded_rhai_plugin_loads() {
+    use cargo_e::e_processmanager::ProcessManager;
+    use cargo_e::plugins::plugin_api::Plugin;
+    use cargo_e::plugins::rhai_plugin::RhaiPlugin;
+    use cargo_e::Cli;
+    use clap::Parser;
+
+    let dir = tempdir().unwrap();
+    let created = scaffold("hello_rhai", PluginLang::Rhai, dir.path()).unwrap();
+    let cli = Cli::parse_from(["cargo-e"]);
+    let plugin = RhaiPlugin::load(&created[0], &cli, ProcessManager::new(&cli)).unwrap();
+    assert_eq!(plugin.name(), "hello_rhai");
+    assert!(plugin.matches(dir.path()));
+    let targets = plugin.collect_targets(dir.path()).unwrap();
+    assert_eq!(targets[0].name, "hello");
+    let section = plugin.on_exit(&[]).unwrap().unwrap();
+    assert!(section.starts_with("## hello_rhai"));
+}
+
+#[test]
+fn plugins_are_keyed_by_file_name() {
+    use cargo_e::plugins::plugin_api::plugin_key;
+    use std::path::Path;
+
+    assert_eq!(plugin_key(Path::new("/p/.cargo-e/plugins/npm.lua")), "npm");
+    assert_eq!(plugin_key(Path::new("/usr/bin/cargo-e-ext-bevy")), "bevy");
+    assert_eq!(plugin_key(Path::new("C:/bin/cargo-e-ext-bevy.exe")), "bevy");
+    assert_eq!(
+        plugin_key(Path::new("/p/demo/target/wasm32-wasip1/release/demo.wasm")),
+        "demo"
+    );
+}
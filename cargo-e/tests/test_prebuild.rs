@@ -0,0 +1,158 @@
+use cargo_e::e_command_builder::CargoCommandBuilder;
+use cargo_e::e_prebuild::{build_args, build_groups, is_prebuildable, owning_target};
+use cargo_e::e_target::{CargoTarget, TargetKind, TargetOrigin};
+use std::path::{Path, PathBuf};
+
+fn target(name: &str, kind: TargetKind, src: &str, features: &[&str]) -> CargoTarget {
+    CargoTarget {
+        name: name.to_string(),
+        display_name: name.to_string(),
+        manifest_path: PathBuf::from("/proj/Cargo.toml"),
+        kind,
+        extended: false,
+        toml_specified: false,
+        origin: Some(TargetOrigin::CargoMetadata {
+            src_path: PathBuf::from(src),
+            edition: "2021".to_string(),
+            required_features: features.iter().map(|f| f.to_string()).collect(),
+        }),
+    }
+}
+
+#[test]
+fn one_build_names_every_target_with_the_union_of_features() {
+    let cube = target(
+        "cube",
+        TargetKind::Example,
+        "/proj/examples/cube.rs",
+        &["wgpu"],
+    );
+    let shader = target(
+        "shader",
+        TargetKind::Example,
+        "/proj/examples/shader/main.rs",
+        &["wgpu", "naga"],
+    );
+    let tool = target("tool", TargetKind::Binary, "/proj/src/main.rs", &[]);
+    let args = build_args(
+        Path::new("/proj/Cargo.toml"),
+        &[&cube, &shader, &tool],
+        true,
+        false,
+    );
+    assert_eq!(
+        args,
+        [
+            "build",
+            "--message-format=json-diagnostic-rendered-ansi",
+            "--keep-going",
+            "--manifest-path",
+            "/proj/Cargo.toml",
+            "--release",
+            "--example",
+            "cube",
+            "--example",
+            "shader",
+            "--bin",
+            "tool",
+            "--features",
+            "wgpu,naga",
+        ]
+    );
+    assert!(is_prebuildable(&tool));
+    assert!(!is_prebuildable(&target(
+        "it",
+        TargetKind::Test,
+        "/proj/tests/it.rs",
+        &[]
+    )));
+}
+
+#[test]
+fn diagnostics_are_attributed_by_span_path() {
+    let cube = target("cube", TargetKind::Example, "/proj/examples/cube.rs", &[]);
+    let shader = target(
+        "shader",
+        TargetKind::Example,
+        "/proj/examples/shader/main.rs",
+        &[],
+    );
+    let tool = target("tool", TargetKind::Binary, "/proj/src/main.rs", &[]);
+    let targets = [&cube, &shader, &tool];
+    let owner = |file: &str| owning_target(Path::new(file), &targets).map(|t| t.name.as_str());
+
+    assert_eq!(owner("/proj/examples/cube.rs"), Some("cube"));
+    // A multi-file example owns its whole directory.
+    assert_eq!(owner("/proj/examples/shader/pipeline.rs"), Some("shader"));
+    assert_eq!(owner("/proj/src/main.rs"), Some("tool"));
+    // Library code is shared, not the default binary's.
+    assert_eq!(owner("/proj/src/lib.rs"), None);
+    assert_eq!(owner("/proj/examples/other.rs"), None);
+}
+
+#[test]
+fn artifact_runs_the_executable_with_only_the_target_arguments() {
+    let cube = target("cube", TargetKind::Example, "/proj/examples/cube.rs", &[]);
+    let exe = Path::new("/proj/target/debug/examples/cube");
+    let builder = CargoCommandBuilder::new(
+        "cube",
+        &cube.manifest_path,
+        "run",
+        true,
+        false,
+        false,
+        false,
+        false,
+        false,
+    )
+    .with_target(&cube)
+    .with_extra_args(&["--width".to_string(), "800".to_string()])
+    .with_artifact(exe, &[]);
+
+    let (program, args) = builder.injected_args();
+    assert_eq!(program, exe.to_string_lossy());
+    assert_eq!(args, ["--width", "800"]);
+    assert!(!builder.is_compiler_target());
+    assert!(!builder.uses_json_diagnostics());
+    assert!(builder
+        .envs
+        .iter()
+        .any(|(k, v)| k == "CARGO_MANIFEST_DIR" && v == "/proj"));
+    #[cfg(target_os = "linux")]
+    assert!(builder.envs.iter().any(|(k, v)| k == "LD_LIBRARY_PATH"
+        && v.starts_with("/proj/target/debug/deps:/proj/target/debug")));
+}
+
+#[test]
+fn targets_are_grouped_by_manifest_and_required_features() {
+    let targets = vec![
+        target(
+            "cube",
+            TargetKind::Example,
+            "/proj/examples/cube.rs",
+            &["wgpu"],
+        ),
+        target("tool", TargetKind::Binary, "/proj/src/main.rs", &[]),
+        target(
+            "shader",
+            TargetKind::Example,
+            "/proj/examples/shader/main.rs",
+            &["wgpu"],
+        ),
+        target(
+            "hdr",
+            TargetKind::Example,
+            "/proj/examples/hdr.rs",
+            &["wgpu", "exr"],
+        ),
+        target("it", TargetKind::Test, "/proj/tests/it.rs", &[]),
+    ];
+    let groups: Vec<Vec<&str>> = build_groups(&targets)
+        .into_iter()
+        .map(|(_, group)| group.iter().map(|t| t.name.as_str()).collect())
+        .collect();
+    assert_eq!(
+        groups,
+        vec![vec!["cube", "shader"], vec!["tool"], vec!["hdr"]]
+    );
+}
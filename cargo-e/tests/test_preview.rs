@@ -0,0 +1,105 @@
+use cargo_e::e_preview::{highlight, leading_doc, Preview, Token};
+use cargo_e::e_target::{CargoTarget, TargetKind, TargetOrigin};
+use std::fs;
+use std::path::PathBuf;
+
+const SOURCE: &str = "#!/usr/bin/env rust-script
+//! Draws a spinning cube.
+//! Needs a GPU.
+//!
+//! Press q to quit.
+#![allow(unused)]
+
+use std::time::Duration;
+
+fn main() {
+    println!(\"hello\");
+}
+";
+
+fn example(manifest: PathBuf, source: PathBuf) -> CargoTarget {
+    CargoTarget {
+        name: "cube".to_string(),
+        display_name: "cube".to_string(),
+        manifest_path: manifest,
+        kind: TargetKind::Example,
+        extended: false,
+        toml_specified: false,
+        origin: Some(TargetOrigin::CargoMetadata {
+            src_path: source,
+            edition: "2021".to_string(),
+            required_features: vec!["gpu".to_string()],
+        }),
+    }
+}
+
+#[test]
+fn leading_doc_skips_shebang_and_stops_at_code() {
+    assert_eq!(
+        leading_doc(SOURCE),
+        vec![
+            "Draws a spinning cube.",
+            "Needs a GPU.",
+            "",
+            "Press q to quit."
+        ]
+    );
+    assert!(leading_doc("fn main() {}\n//! too late").is_empty());
+}
+
+#[test]
+fn preview_header_and_main_line() {
+    let target = example(
+        PathBuf::from("/project/Cargo.toml"),
+        PathBuf::from("/project/examples/cube.rs"),
+    );
+    let preview = Preview::from_source(&target, PathBuf::from("cube.rs"), SOURCE);
+    assert_eq!(preview.summary(), "Draws a spinning cube. Needs a GPU.");
+    assert_eq!(preview.required_features.as_deref(), Some("gpu"));
+    assert_eq!(preview.main_line, Some(9));
+}
+
+#[test]
+fn load_uses_the_targets_own_source_file() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::create_dir_all(dir.path().join("examples")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+    let source = dir.path().join("examples/cube.rs");
+    fs::write(&source, SOURCE).unwrap();
+    let target = example(dir.path().join("Cargo.toml"), source.clone());
+    let preview = Preview::load(&target).unwrap();
+    assert_eq!(preview.source, source);
+}
+
+#[test]
+fn highlights_keywords_strings_macros_and_comments() {
+    let tokens = highlight("    let s: String = format!(\"{}\", 42); // done");
+    let kinds: Vec<Token> = tokens
+        .iter()
+        .filter(|(t, _)| *t != Token::Plain)
+        .map(|(t, _)| *t)
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            Token::Keyword,
+            Token::Type,
+            Token::Macro,
+            Token::String,
+            Token::Number,
+            Token::Comment
+        ]
+    );
+    let text: String = tokens.iter().map(|(_, s)| s.as_str()).collect();
+    assert_eq!(text, "    let s: String = format!(\"{}\", 42); // done");
+    assert_eq!(highlight("#[derive(Debug)]")[0].0, Token::Attribute);
+    assert_eq!(
+        highlight("fn f<'a>(c: char) -> bool { c == 'x' }")
+            .iter()
+            .filter(|(t, _)| matches!(t, Token::Lifetime | Token::String))
+            .map(|(_, s)| s.as_str())
+            .collect::<Vec<_>>(),
+        vec!["'a", "'x'"]
+    );
+}
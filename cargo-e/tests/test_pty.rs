@@ -0,0 +1,33 @@
+#![cfg(unix)]
+use cargo_e::e_pty::{strip_ansi, Pty};
+use std::io::Read;
+use std::process::Command;
+
+#[test]
+fn strip_ansi_removes_csi_and_osc_sequences() {
+    assert_eq!(strip_ansi("\x1b[1;32mok\x1b[0m"), "ok");
+    assert_eq!(
+        strip_ansi("\x1b]0;title\x07listening on \x1b]8;;http://x\x1b\\http://x"),
+        "listening on http://x"
+    );
+    assert_eq!(strip_ansi("\x1b[?1049h\x1b[2J\x1b[Hframe"), "frame");
+    assert_eq!(strip_ansi("plain text"), "plain text");
+}
+
+#[test]
+fn child_sees_a_terminal_and_lines_are_cleaned() {
+    let pty = Pty::open().expect("open pty");
+    let mut cmd = Command::new("sh");
+    cmd.args([
+        "-c",
+        r#"[ -t 0 ] && [ -t 1 ] && printf '\033[1mtty\033[0m\n'"#,
+    ]);
+    pty.attach(&mut cmd).expect("attach pty");
+    let mut child = cmd.spawn().expect("spawn sh");
+    drop(cmd);
+
+    let mut out = String::new();
+    pty.start().read_to_string(&mut out).unwrap();
+    assert!(child.wait().unwrap().success());
+    assert_eq!(out, "tty\n");
+}
@@ -0,0 +1,112 @@
+use cargo_e::e_cargocommand_ext::{CargoDiagnostic, CargoProcessResult};
+use cargo_e::e_reports::{generate_junit_report, result_failed};
+use std::time::Duration;
+
+#[cfg(unix)]
+fn exit_status(code: i32) -> std::process::ExitStatus {
+    use std::os::unix::process::ExitStatusExt;
+    std::process::ExitStatus::from_raw(code << 8)
+}
+
+#[cfg(windows)]
+fn exit_status(code: i32) -> std::process::ExitStatus {
+    use std::os::windows::process::ExitStatusExt;
+    std::process::ExitStatus::from_raw(code as u32)
+}
+
+fn passed() -> CargoProcessResult {
+    CargoProcessResult {
+        target_name: "ok_example".to_string(),
+        cmd: "cargo".to_string(),
+        exit_status: Some(exit_status(0)),
+        elapsed_time: Some(Duration::from_millis(1500)),
+        build_output_size: 42,
+        ..Default::default()
+    }
+}
+
+fn failed() -> CargoProcessResult {
+    let diag = CargoDiagnostic::new(
+        "src/main.rs:3:5".to_string(),
+        "error".to_string(),
+        "mismatched types <i32> & \"str\"".to_string(),
+        Some("E0308".to_string()),
+        None,
+        None,
+        None,
+        false,
+        Some(1),
+        None,
+    );
+    CargoProcessResult {
+        target_name: "broken".to_string(),
+        profile: Some("release".to_string()),
+        cmd: "cargo".to_string(),
+        exit_status: Some(exit_status(101)),
+        is_could_not_compile: true,
+        diagnostics: vec![diag],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn junit_reports_first_error_diagnostic() {
+    let results = vec![passed(), failed()];
+    assert!(!result_failed(&results[0]));
+    assert!(result_failed(&results[1]));
+
+    let xml = generate_junit_report(&results);
+    assert!(xml.contains("tests=\"2\" failures=\"1\""));
+    assert!(
+        xml.contains("<testcase name=\"ok_example\" classname=\"cargo-e.cargo\" time=\"1.500\"/>")
+    );
+    assert!(xml.contains("<testcase name=\"broken@release\""));
+    assert!(xml.contains(
+        "<failure message=\"mismatched types &lt;i32&gt; &amp; &quot;str&quot;\" type=\"E0308\">src/main.rs:3:5"
+    ));
+}
+
+#[cfg(feature = "uses_serde")]
+#[test]
+fn json_report_serializes_results_and_diagnostics() {
+    let json = cargo_e::e_reports::generate_json_report(&[passed(), failed()]);
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let results = value["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0]["success"], true);
+    assert_eq!(results[0]["exit_status"]["code"], 0);
+    assert_eq!(results[0]["elapsed_secs"], 1.5);
+    assert_eq!(results[0]["build_output_size"], 42);
+
+    let broken = &results[1];
+    assert_eq!(broken["label"], "broken@release");
+    assert_eq!(broken["success"], false);
+    assert_eq!(broken["exit_status"]["code"], 101);
+    let diag = &broken["diagnostics"][0];
+    assert_eq!(diag["level"], "error");
+    assert_eq!(diag["code"], "E0308");
+    assert_eq!(diag["file"], "src/main.rs");
+    assert_eq!(diag["line"], 3);
+    assert_eq!(diag["column"], 5);
+}
+
+#[test]
+fn stopping_at_the_timeout_counts_as_having_run() {
+    let stopped = CargoProcessResult {
+        target_name: "window".to_string(),
+        cmd: "cargo".to_string(),
+        timed_out: true,
+        ..Default::default()
+    };
+    let killed = CargoProcessResult {
+        timed_out: false,
+        ..stopped.clone()
+    };
+    assert!(!result_failed(&stopped));
+    assert!(result_failed(&killed));
+
+    let xml = generate_junit_report(&[stopped, killed]);
+    assert!(xml.contains("tests=\"2\" failures=\"1\""));
+    assert!(xml.contains("no exit status recorded"));
+}
@@ -0,0 +1,103 @@
+use cargo_e::e_cargocommand_ext::CargoProcessResult;
+use cargo_e::e_reports::{generate_junit_report, result_failed};
+use cargo_e::e_resources::{
+    parse_size, target_usage, tree_usage, ProcessSample, ResourceLimits, ResourceUsage,
+};
+
+const MB: u64 = 1 << 20;
+
+#[test]
+fn sizes_parse_with_binary_suffixes() {
+    assert_eq!(parse_size("512M"), Ok(512 * MB));
+    assert_eq!(parse_size("1.5g"), Ok(3 * 512 * MB));
+    assert_eq!(parse_size("800KiB"), Ok(800 * 1024));
+    assert_eq!(parse_size("4096"), Ok(4096));
+    assert!(parse_size("lots").is_err());
+    assert!(parse_size("5X").is_err());
+}
+
+fn process(pid: u32, parent: u32, name: &str, cpu: f32, rss: u64) -> ProcessSample {
+    ProcessSample {
+        pid,
+        parent: Some(parent),
+        name: name.to_string(),
+        cpu,
+        rss,
+    }
+}
+
+#[test]
+fn usage_sums_the_process_tree() {
+    // cargo (10) runs the example (11), which spawns a helper (12); 20 is unrelated.
+    let processes = vec![
+        process(10, 1, "cargo", 1.0, 20 * MB),
+        process(11, 10, "cube", 50.0, 100 * MB),
+        process(12, 11, "helper", 25.0, 30 * MB),
+        process(20, 1, "other", 90.0, 900 * MB),
+    ];
+    assert_eq!(tree_usage(10, &processes), Some((76.0, 150 * MB)));
+    assert_eq!(tree_usage(12, &processes), Some((25.0, 30 * MB)));
+    assert_eq!(tree_usage(99, &processes), None);
+    // Under `cargo run`, the target counts but cargo does not.
+    assert_eq!(target_usage(10, &processes), Some((75.0, 130 * MB)));
+}
+
+#[test]
+fn the_build_does_not_count_as_the_target() {
+    let building = vec![
+        process(10, 1, "cargo", 5.0, 40 * MB),
+        process(11, 10, "rustc", 100.0, 800 * MB),
+        process(13, 10, "build-script-build", 30.0, 10 * MB),
+        process(14, 11, "cc", 80.0, 200 * MB),
+    ];
+    assert_eq!(target_usage(10, &building), None);
+    let mut running = building;
+    running.push(process(15, 10, "cube", 20.0, 64 * MB));
+    assert_eq!(target_usage(10, &running), Some((20.0, 64 * MB)));
+}
+
+#[test]
+fn peaks_and_averages_over_samples() {
+    let mut usage = ResourceUsage::default();
+    assert_eq!(usage.avg_rss(), None);
+    assert_eq!(usage.rss_summary(), "-");
+    usage.record(10.0, 100 * MB);
+    usage.record(30.0, 300 * MB);
+    assert_eq!(usage.peak_cpu, 30.0);
+    assert_eq!(usage.avg_cpu(), Some(20.0));
+    assert_eq!(usage.peak_rss, 300 * MB);
+    assert_eq!(usage.avg_rss(), Some(200 * MB));
+    assert_eq!(usage.rss_summary(), "peak 300.00 MB / avg 200.00 MB");
+}
+
+#[test]
+fn exceeding_a_limit_fails_the_run() {
+    let mut usage = ResourceUsage::default();
+    usage.record(150.0, 600 * MB);
+    let limits = ResourceLimits {
+        max_rss: Some(512 * MB),
+        max_cpu: None,
+    };
+    let violation = limits.violation(&usage).unwrap();
+    assert_eq!(violation, "peak RSS 600.00 MB exceeds --max-rss 512.00 MB");
+    let cpu_only = ResourceLimits {
+        max_rss: None,
+        max_cpu: Some(100.0),
+    };
+    assert!(cpu_only.violation(&usage).unwrap().contains("--max-cpu"));
+    assert_eq!(ResourceLimits::default().violation(&usage), None);
+
+    let result = CargoProcessResult {
+        target_name: "hog".to_string(),
+        cmd: "cargo".to_string(),
+        // A clean exit.
+        exit_status: Some(std::process::ExitStatus::default()),
+        resources: usage,
+        resource_violation: Some(violation),
+        ..Default::default()
+    };
+    assert!(result_failed(&result));
+    let xml = generate_junit_report(&[result]);
+    assert!(xml.contains("type=\"resources\""));
+    assert!(xml.contains("exceeds --max-rss"));
+}
@@ -0,0 +1,106 @@
+#![cfg(feature = "uses_serde")]
+
+use cargo_e::e_cargocommand_ext::CargoDiagnostic;
+use cargo_e::e_processmanager::ProcessManager;
+use cargo_e::e_serve::{diagnostic_json, Client, Server};
+use cargo_e::ext::ExtContext;
+use cargo_e::Cli;
+use clap::Parser;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+fn server() -> (Server, Arc<Client>) {
+    let cli = Cli::parse_from(["cargo-e"]);
+    let manager = ProcessManager::new(&cli);
+    let server = Server::new(ExtContext::new(cli, manager).unwrap());
+    (server, Arc::new(Client::new(Box::new(std::io::sink()))))
+}
+
+fn error_code(response: &Value) -> i64 {
+    response["error"]["code"].as_i64().unwrap()
+}
+
+#[test]
+fn answers_requests_and_reports_protocol_errors() {
+    let (mut server, client) = server();
+    let response = server
+        .handle_line(
+            &client,
+            r#"{"jsonrpc":"2.0","id":1,"method":"subscribeOutput"}"#,
+        )
+        .unwrap();
+    assert_eq!(response, json!({"jsonrpc": "2.0", "id": 1, "result": true}));
+
+    let response = server.handle_line(&client, "{not json").unwrap();
+    assert_eq!(error_code(&response), -32700);
+    let response = server
+        .handle_line(&client, r#"{"jsonrpc":"2.0","id":2,"method":"build"}"#)
+        .unwrap();
+    assert_eq!(error_code(&response), -32601);
+    let response = server
+        .handle_line(
+            &client,
+            r#"{"jsonrpc":"2.0","id":3,"method":"kill","params":{}}"#,
+        )
+        .unwrap();
+    assert_eq!(error_code(&response), -32602);
+
+    // Notifications get no response.
+    assert!(server
+        .handle_line(
+            &client,
+            r#"{"jsonrpc":"2.0","method":"subscribeDiagnostics"}"#
+        )
+        .is_none());
+}
+
+#[test]
+fn killing_an_unknown_pid_is_false() {
+    let (mut server, client) = server();
+    let response = server
+        .handle_line(
+            &client,
+            r#"{"jsonrpc":"2.0","id":"k","method":"kill","params":{"pid":4000000}}"#,
+        )
+        .unwrap();
+    assert_eq!(response["result"], json!(false));
+}
+
+#[test]
+fn diagnostics_are_labelled_and_located() {
+    let diagnostic = CargoDiagnostic::new(
+        "no/such/dir/main.rs:7:3".to_string(),
+        "warning".to_string(),
+        "unused variable: `x`".to_string(),
+        None,
+        None,
+        None,
+        None,
+        false,
+        Some(4),
+        None,
+    );
+    let value = diagnostic_json(42, &diagnostic);
+    assert_eq!(value["pid"], json!(42));
+    assert_eq!(value["label"], json!("W04"));
+    assert_eq!(value["file"], json!("no/such/dir/main.rs"));
+    assert_eq!(
+        (value["line"].clone(), value["col"].clone()),
+        (json!(7), json!(3))
+    );
+    assert_eq!(value["message"], json!("unused variable: `x`"));
+}
+
+#[cfg(unix)]
+#[test]
+fn refuses_to_replace_a_file_that_is_not_a_socket() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("notes.txt");
+    std::fs::write(&path, "keep me").unwrap();
+    let cli = Cli::parse_from(["cargo-e"]);
+    let manager = ProcessManager::new(&cli);
+    let ctx = ExtContext::new(cli, manager).unwrap();
+    let err = cargo_e::e_serve::serve(ctx, path.to_str().unwrap()).unwrap_err();
+    assert!(err.to_string().contains("not a socket"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+}
@@ -0,0 +1,155 @@
+use cargo_e::e_cargocommand_ext::CargoProcessResult;
+use cargo_e::e_cli::SnapshotMode;
+use cargo_e::e_reports::{generate_junit_report, result_failed};
+use cargo_e::e_snapshot::{
+    finish_run, settle, snapshot_path, unified_diff, Capture, Redactor, SnapshotOutcome,
+};
+use cargo_e::e_target::TargetKind;
+use std::path::Path;
+
+#[test]
+fn timestamps_paths_and_pids_are_redacted() {
+    let redactor = Redactor::new(&[r"\d+ms=>[N]ms".to_string(), "secret-\\w+".to_string()])
+        .unwrap()
+        .with_path(Path::new("/home/u"), "[HOME]")
+        .with_path(Path::new("/home/u/proj"), "[ROOT]");
+    let line = "2024-05-01T12:30:00Z pid=4242 loaded /home/u/proj/assets and /home/u/.cache";
+    assert_eq!(
+        redactor.apply(line),
+        "[TIMESTAMP] pid=[PID] loaded [ROOT]/assets and [HOME]/.cache"
+    );
+    assert_eq!(
+        redactor.apply("done at 09:15:02 in 35ms with secret-abc"),
+        "done at [TIME] in [N]ms with [REDACTED]"
+    );
+    assert!(Redactor::new(&["(unclosed".to_string()]).is_err());
+}
+
+#[test]
+fn capture_keeps_lines_without_carriage_returns() {
+    let capture = Capture::default();
+    capture.push("frame 1\r");
+    capture.push("frame 2");
+    assert_eq!(capture.text(), "frame 1\nframe 2\n");
+    assert_eq!(Capture::default().text(), "");
+}
+
+#[test]
+fn unified_diff_shows_changes_with_context() {
+    let diff = unified_diff(
+        "a\nb\nc\nd\ne\nf\ng\nh\n",
+        "a\nB\nc\nd\ne\nf\ng\nh\ni\n",
+        "old",
+        "new",
+        1,
+    );
+    assert_eq!(
+        diff,
+        "--- old\n+++ new\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n@@ -8,1 +8,2 @@\n h\n+i\n"
+    );
+    assert_eq!(
+        unified_diff("same\n", "same\n", "old", "new", 3),
+        "--- old\n+++ new\n"
+    );
+}
+
+#[test]
+fn unified_diff_marks_a_missing_trailing_newline() {
+    assert_eq!(
+        unified_diff("a\nb\n", "a\nb", "old", "new", 3),
+        "--- old\n+++ new\n@@ -1,2 +1,2 @@\n a\n-b\n+b\n\\ No newline at end of file\n"
+    );
+    assert_eq!(
+        unified_diff("a", "a\n", "old", "new", 3),
+        "--- old\n+++ new\n@@ -1,1 +1,1 @@\n-a\n\\ No newline at end of file\n+a\n"
+    );
+}
+
+#[test]
+fn an_unreadable_snapshot_fails_the_check() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = dir.path().join("Cargo.toml");
+    // A directory where the snapshot file should be cannot be read as one.
+    std::fs::create_dir_all(snapshot_path(&manifest, TargetKind::Example, "hello")).unwrap();
+
+    let outcome = finish_run(
+        SnapshotMode::Check,
+        &manifest,
+        TargetKind::Example,
+        "hello",
+        &[],
+        &Capture::default(),
+    )
+    .expect("check mode reports an unreadable snapshot");
+    assert!(matches!(outcome, SnapshotOutcome::Unreadable { .. }));
+    assert_eq!(outcome.status(), "unreadable");
+
+    let result = CargoProcessResult {
+        target_name: "hello".to_string(),
+        cmd: "cargo".to_string(),
+        exit_status: Some(std::process::ExitStatus::default()),
+        snapshot: Some(outcome),
+        ..Default::default()
+    };
+    assert!(result_failed(&result));
+}
+
+#[test]
+fn record_then_check() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = dir.path().join("Cargo.toml");
+    let path = snapshot_path(&manifest, TargetKind::Example, "hello@loud");
+    assert_eq!(
+        path,
+        dir.path()
+            .join("snapshots")
+            .join("examples")
+            .join("hello@loud.out")
+    );
+    // A binary of the same name keeps its own snapshot.
+    assert_eq!(
+        snapshot_path(&manifest, TargetKind::Binary, "hello@loud"),
+        dir.path()
+            .join("snapshots")
+            .join("bins")
+            .join("hello@loud.out")
+    );
+
+    assert_eq!(
+        settle(SnapshotMode::Check, &path, "hello\n").unwrap(),
+        SnapshotOutcome::Missing(path.clone())
+    );
+    assert_eq!(
+        settle(SnapshotMode::Record, &path, "hello\nworld\n").unwrap(),
+        SnapshotOutcome::Recorded(path.clone())
+    );
+    assert_eq!(
+        settle(SnapshotMode::Check, &path, "hello\nworld\n").unwrap(),
+        SnapshotOutcome::Matched(path.clone())
+    );
+    let outcome = settle(SnapshotMode::Check, &path, "hello\nthere\n").unwrap();
+    assert!(outcome.is_failure());
+    let SnapshotOutcome::Mismatch { diff, .. } = &outcome else {
+        panic!("expected a mismatch, got {:?}", outcome);
+    };
+    assert!(diff.contains("-world\n+there\n"));
+
+    let result = CargoProcessResult {
+        target_name: "hello".to_string(),
+        cmd: "cargo".to_string(),
+        exit_status: Some(std::process::ExitStatus::default()),
+        snapshot: Some(outcome),
+        ..Default::default()
+    };
+    assert!(result_failed(&result));
+    let xml = generate_junit_report(&[result]);
+    assert!(xml.contains("type=\"snapshot\""));
+    assert!(xml.contains("+there"));
+}
+
+#[test]
+fn snapshot_mode_parses() {
+    assert_eq!("record".parse::<SnapshotMode>(), Ok(SnapshotMode::Record));
+    assert_eq!("CHECK".parse::<SnapshotMode>(), Ok(SnapshotMode::Check));
+    assert!("update".parse::<SnapshotMode>().is_err());
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "uses_serde")]
+
+use cargo_e::{
+    e_target::{CargoTarget, TargetKind, TargetOrigin},
+    e_target_cache::{cache_path_for, cached_collect},
+};
+use std::cell::Cell;
+use std::fs;
+use tempfile::tempdir;
+
+fn sample(manifest: &std::path::Path, name: &str) -> CargoTarget {
+    CargoTarget {
+        name: name.to_string(),
+        display_name: name.to_string(),
+        manifest_path: manifest.to_path_buf(),
+        kind: TargetKind::Example,
+        extended: false,
+        toml_specified: false,
+        origin: Some(TargetOrigin::SingleFile(manifest.with_file_name(name))),
+    }
+}
+
+#[test]
+fn cache_hits_until_examples_change() {
+    let dir = tempdir().unwrap();
+    let manifest = dir.path().join("Cargo.toml");
+    fs::write(
+        &manifest,
+        "[package]\nname = \"cached\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("Cargo.lock"), "").unwrap();
+    fs::create_dir_all(dir.path().join("examples")).unwrap();
+    fs::write(dir.path().join("examples").join("one.rs"), "fn main() {}").unwrap();
+    let manifests = vec![manifest.clone()];
+    let calls = Cell::new(0);
+
+    let collect = || {
+        calls.set(calls.get() + 1);
+        Ok(vec![sample(&manifest, "one")])
+    };
+    let first = cached_collect(&manifests, false, collect).unwrap();
+    assert_eq!(first.len(), 1);
+    assert!(cache_path_for(&manifest).is_file());
+
+    let second = cached_collect(&manifests, false, || {
+        calls.set(calls.get() + 1);
+        Ok(Vec::new())
+    })
+    .unwrap();
+    assert_eq!(
+        calls.get(),
+        1,
+        "second collection should come from the cache"
+    );
+    assert_eq!(second[0].name, "one");
+
+    fs::write(dir.path().join("examples").join("two.rs"), "fn main() {}").unwrap();
+    let third = cached_collect(&manifests, false, || {
+        calls.set(calls.get() + 1);
+        Ok(vec![sample(&manifest, "one"), sample(&manifest, "two")])
+    })
+    .unwrap();
+    assert_eq!(calls.get(), 2, "a new example must invalidate the cache");
+    assert_eq!(third.len(), 2);
+}
+
+#[test]
+fn a_new_bench_invalidates_the_cache() {
+    let dir = tempdir().unwrap();
+    let manifest = dir.path().join("Cargo.toml");
+    fs::write(
+        &manifest,
+        "[package]\nname = \"benched\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("Cargo.lock"), "").unwrap();
+    let manifests = vec![manifest.clone()];
+    let calls = Cell::new(0);
+    let collect = || {
+        calls.set(calls.get() + 1);
+        Ok(Vec::new())
+    };
+    cached_collect(&manifests, false, collect).unwrap();
+    cached_collect(&manifests, false, collect).unwrap();
+    assert_eq!(calls.get(), 1);
+
+    fs::create_dir_all(dir.path().join("benches")).unwrap();
+    fs::write(dir.path().join("benches").join("speed.rs"), "fn main() {}").unwrap();
+    cached_collect(&manifests, false, collect).unwrap();
+    assert_eq!(calls.get(), 2, "a new bench must invalidate the cache");
+}
@@ -0,0 +1,213 @@
+use cargo_e::e_terminal::{LaunchOptions, Launcher};
+use std::collections::HashMap;
+
+fn detect(preference: Option<&str>, env: &[(&str, &str)], path: &[&str]) -> Option<Launcher> {
+    let env: HashMap<String, String> = env
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    Launcher::detect_with(
+        preference,
+        |key| env.get(key).cloned(),
+        |program| path.contains(&program),
+    )
+}
+
+#[test]
+fn detection_prefers_config_then_multiplexer_then_terminal_env() {
+    let inside_tmux = [("TMUX", "/tmp/tmux-1000/default,1,0"), ("TERMINAL", "foot")];
+    assert_eq!(
+        detect(Some("kitty"), &inside_tmux, &[]),
+        Some(Launcher::from_name("kitty"))
+    );
+    assert_eq!(detect(None, &inside_tmux, &[]), Some(Launcher::Tmux));
+    assert_eq!(
+        detect(None, &[("ZELLIJ", "0")], &["xterm"]),
+        Some(Launcher::Zellij)
+    );
+    assert_eq!(
+        detect(None, &[("TERMINAL", "foot")], &["xterm"]),
+        Some(Launcher::from_name("foot"))
+    );
+    // Probed in order: wezterm comes before xterm.
+    assert_eq!(
+        detect(None, &[], &["xterm", "wezterm"]),
+        Some(Launcher::from_name("wezterm"))
+    );
+}
+
+#[test]
+fn known_terminals_get_their_exec_arguments() {
+    assert_eq!(
+        Launcher::from_name("/usr/bin/gnome-terminal"),
+        Launcher::Terminal {
+            program: "/usr/bin/gnome-terminal".to_string(),
+            exec_args: vec!["--wait".to_string(), "--".to_string()],
+        }
+    );
+    assert_eq!(
+        Launcher::from_name("my-term"),
+        Launcher::Terminal {
+            program: "my-term".to_string(),
+            exec_args: vec!["-e".to_string()],
+        }
+    );
+}
+
+#[test]
+fn commands_wrap_the_target_with_delay_and_hold() {
+    let opts = LaunchOptions {
+        delay: Some(2),
+        hold: Some(30),
+        ..Default::default()
+    };
+    let args = vec![
+        "run".to_string(),
+        "--example".to_string(),
+        "demo".to_string(),
+    ];
+
+    let cmd = Launcher::from_name("alacritty").command("cargo", &args, &opts);
+    assert_eq!(cmd.get_program(), "alacritty");
+    let argv: Vec<String> = cmd
+        .get_args()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(argv[..3], ["-e", "sh", "-c"]);
+    // $0, delay, hold, no status file, then the target itself.
+    assert_eq!(
+        argv[4..],
+        [
+            "cargo-e-detached",
+            "2",
+            "30",
+            "",
+            "cargo",
+            "run",
+            "--example",
+            "demo"
+        ]
+    );
+
+    // Multiplexer panes return at once, so the command waits on a status file.
+    let cmd = Launcher::Tmux.command("cargo", &args, &opts);
+    assert_eq!(cmd.get_program(), "sh");
+    let argv: Vec<String> = cmd
+        .get_args()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect();
+    assert!(argv[3].ends_with(".status"));
+    assert_eq!(argv[4..7], ["tmux", "split-window", "-d"]);
+    assert_eq!(argv[argv.len() - 3..], [";", "select-layout", "tiled"]);
+}
+
+/// Starts `Launcher::Tmux` through a stand-in `tmux` that runs the pane command in the
+/// background, and returns the waiting command with the path of its status file.
+#[cfg(unix)]
+fn fake_tmux_pane(
+    dir: &std::path::Path,
+    program: &str,
+    args: &[&str],
+) -> (std::process::Child, String) {
+    use std::os::unix::fs::PermissionsExt;
+    let tmux = dir.join("tmux");
+    // split-window -d <pane command...> ; select-layout tiled
+    std::fs::write(
+        &tmux,
+        "#!/bin/bash\n\"${@:3:$#-5}\" </dev/null >/dev/null 2>&1 &\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&tmux, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    let mut cmd = Launcher::Tmux.command(program, &args, &LaunchOptions::default());
+    let status_file = cmd
+        .get_args()
+        .nth(3)
+        .unwrap()
+        .to_string_lossy()
+        .into_owned();
+    let path = format!(
+        "{}:{}",
+        dir.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let child = cmd.env("PATH", path).spawn().unwrap();
+    (child, status_file)
+}
+
+#[cfg(unix)]
+fn pane_pid(status_file: &str) -> i32 {
+    let pid_file = format!("{}.pid", status_file);
+    for _ in 0..100 {
+        if let Ok(pid) = std::fs::read_to_string(&pid_file) {
+            if let Ok(pid) = pid.trim().parse() {
+                return pid;
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    panic!("the pane never started");
+}
+
+#[cfg(unix)]
+fn wait_with_deadline(child: &mut std::process::Child) -> std::process::ExitStatus {
+    for _ in 0..100 {
+        if let Some(status) = child.try_wait().unwrap() {
+            return status;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    child.kill().ok();
+    panic!("still waiting on the pane");
+}
+
+#[cfg(unix)]
+fn is_running(pid: i32) -> bool {
+    // A reaped or zombie pane process both count as gone.
+    std::fs::read_to_string(format!("/proc/{}/stat", pid)).is_ok_and(|stat| !stat.contains(") Z "))
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn pane_waits_report_the_target_status() {
+    let dir = tempfile::tempdir().unwrap();
+    let (mut child, _) = fake_tmux_pane(dir.path(), "sh", &["-c", "exit 3"]);
+    assert_eq!(wait_with_deadline(&mut child).code(), Some(3));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn pane_waits_end_when_the_pane_is_closed() {
+    let dir = tempfile::tempdir().unwrap();
+    let (mut child, status_file) = fake_tmux_pane(dir.path(), "sleep", &["30"]);
+    let pane = pane_pid(&status_file);
+    std::process::Command::new("pkill")
+        .args(["-KILL", "-P", &pane.to_string()])
+        .status()
+        .unwrap();
+    std::process::Command::new("kill")
+        .args(["-KILL", &pane.to_string()])
+        .status()
+        .unwrap();
+    assert!(!wait_with_deadline(&mut child).success());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn stopping_a_pane_wait_stops_the_target() {
+    let dir = tempfile::tempdir().unwrap();
+    let (mut child, status_file) = fake_tmux_pane(dir.path(), "sleep", &["30"]);
+    let pane = pane_pid(&status_file);
+    std::process::Command::new("kill")
+        .args(["-HUP", &child.id().to_string()])
+        .status()
+        .unwrap();
+    wait_with_deadline(&mut child);
+    for _ in 0..40 {
+        if !is_running(pane) {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    panic!("the pane's target outlived the wait");
+}
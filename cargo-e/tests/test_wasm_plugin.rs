@@ -0,0 +1,106 @@
+#![cfg(feature = "uses_wasm")]
+use cargo_e::plugins::plugin_api::{Plugin, Target};
+use cargo_e::plugins::wasm_plugin::WasmPlugin;
+use std::path::Path;
+
+/// A WASI command that answers the plugin CLI by the third byte of its first
+/// argument: `--name`, `--matches`, `--collect_targets` or `--build_command`.
+fn answering_module(answers: &[(char, &str)]) -> String {
+    let mut data = String::new();
+    let mut branches = String::new();
+    for (i, (flag, answer)) in answers.iter().enumerate() {
+        let offset = 1024 + i * 256;
+        data.push_str(&format!(
+            "(data (i32.const {}) \"{}\")\n",
+            offset,
+            answer.replace('"', "\\\"")
+        ));
+        branches.push_str(&format!(
+            "(if (i32.eq (local.get $flag) (i32.const {})) \
+             (then (call $say (i32.const {}) (i32.const {})) (return)))\n",
+            *flag as u32,
+            offset,
+            answer.len()
+        ));
+    }
+    format!(
+        r#"(module
+  (import "wasi_snapshot_preview1" "args_sizes_get" (func $args_sizes_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "args_get" (func $args_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+  (memory (export "memory") 1)
+  {data}
+  (func $say (param $ptr i32) (param $len i32)
+    (i32.store (i32.const 0) (local.get $ptr))
+    (i32.store (i32.const 4) (local.get $len))
+    (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))))
+  (func (export "_start")
+    (local $flag i32)
+    (drop (call $args_sizes_get (i32.const 16) (i32.const 20)))
+    (drop (call $args_get (i32.const 64) (i32.const 4096)))
+    (if (i32.lt_u (i32.load (i32.const 16)) (i32.const 2))
+      (then (call $proc_exit (i32.const 2))))
+    (local.set $flag (i32.load8_u (i32.add (i32.load (i32.const 68)) (i32.const 2))))
+    {branches}
+    (call $proc_exit (i32.const 3))))"#
+    )
+}
+
+fn write_module(dir: &Path, name: &str, wat: &str) -> std::path::PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, wat).unwrap();
+    path
+}
+
+#[test]
+fn wasi_plugin_loads_and_answers_calls() {
+    let dir = tempfile::tempdir().unwrap();
+    let wat = answering_module(&[
+        ('n', "wat_demo"),
+        ('m', "true"),
+        ('c', r#"[{"name":"hello","metadata":null}]"#),
+        ('b', r#"{"prog":"echo","args":["hi"],"cwd":null}"#),
+    ]);
+    let path = write_module(dir.path(), "demo.wat", &wat);
+    let plugin = WasmPlugin::load(&path).unwrap().expect("a WASI command");
+
+    assert_eq!(plugin.name(), "wat_demo");
+    assert!(plugin.matches(dir.path()));
+    let targets = plugin.collect_targets(dir.path()).unwrap();
+    assert_eq!(targets.len(), 1);
+    assert_eq!(targets[0].name, "hello");
+    let target = Target {
+        name: "hello".to_string(),
+        metadata: None,
+        cargo_target: None,
+    };
+    let cmd = plugin.build_command(dir.path(), &target).unwrap();
+    assert_eq!(cmd.get_program(), "echo");
+    assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["hi"]);
+    assert_eq!(cmd.get_current_dir(), Some(dir.path()));
+}
+
+#[test]
+fn failing_calls_report_the_exit_code() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_module(dir.path(), "mute.wat", &answering_module(&[]));
+    let plugin = WasmPlugin::load(&path).unwrap().expect("a WASI command");
+
+    // `--name` failed, so the default name is kept.
+    assert_eq!(plugin.name(), "wasm-plugin");
+    assert!(!plugin.matches(dir.path()));
+    let err = plugin.collect_targets(dir.path()).unwrap_err();
+    assert!(err.to_string().contains("exited with code 3"), "{}", err);
+}
+
+#[test]
+fn modules_without_start_are_not_wasi_plugins() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_module(
+        dir.path(),
+        "lib.wat",
+        r#"(module (func (export "plugin_name") (result i32) (i32.const 0)))"#,
+    );
+    assert!(WasmPlugin::load(&path).unwrap().is_none());
+}
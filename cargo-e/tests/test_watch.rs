@@ -0,0 +1,44 @@
+use cargo_e::e_target::{CargoTarget, TargetKind, TargetOrigin};
+use cargo_e::e_watch::WatchSet;
+use std::fs;
+
+#[test]
+fn watch_set_covers_source_src_and_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = fs::canonicalize(dir.path()).unwrap();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("examples").join("multi")).unwrap();
+    fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+    fs::write(root.join("src").join("lib.rs"), "").unwrap();
+    fs::write(root.join("examples").join("single.rs"), "fn main() {}").unwrap();
+    fs::write(root.join("examples").join("multi").join("main.rs"), "").unwrap();
+    fs::write(root.join("examples").join("other.rs"), "fn main() {}").unwrap();
+
+    let target = |name: &str, origin: TargetOrigin| CargoTarget {
+        name: name.to_string(),
+        display_name: name.to_string(),
+        manifest_path: root.join("Cargo.toml"),
+        kind: TargetKind::Example,
+        extended: false,
+        toml_specified: false,
+        origin: Some(origin),
+    };
+
+    let single = WatchSet::for_target(&target(
+        "single",
+        TargetOrigin::SingleFile(root.join("examples").join("single.rs")),
+    ));
+    assert!(single.is_relevant(&root.join("Cargo.toml")));
+    assert!(single.is_relevant(&root.join("src").join("lib.rs")));
+    assert!(single.is_relevant(&root.join("examples").join("single.rs")));
+    assert!(!single.is_relevant(&root.join("examples").join("other.rs")));
+    assert!(!single.is_relevant(&root.join("Cargo.lock")));
+
+    let multi = WatchSet::for_target(&target(
+        "multi",
+        TargetOrigin::MultiFile(root.join("examples").join("multi").join("main.rs")),
+    ));
+    assert_eq!(multi.source, Some(root.join("examples").join("multi")));
+    assert!(multi.is_relevant(&root.join("examples").join("multi").join("util.rs")));
+    assert!(!multi.is_relevant(&root.join("examples").join("single.rs")));
+}